@@ -0,0 +1,3 @@
+// Define allocation constants
+pub const BASIS_POINTS_DENOMINATOR: i128 = 10000; // 100% = 10000 basis points
+pub const MAX_TOTAL_ALLOCATION: u32 = 10000; // Allocations across all pools may not exceed 100%
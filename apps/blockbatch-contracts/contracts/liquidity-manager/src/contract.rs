@@ -1,7 +1,13 @@
-use crate::types::{Asset, DataKey, Error, PoolInfo};
+use crate::constants::*;
+use crate::pool_client::LiquidityPoolClient;
+use crate::types::{
+    AllocationDrift, Asset, AssetTotal, DataKey, DepositorPosition, Error, PoolInfo, PoolInfoV1,
+    PoolStatus, RebalanceTransfer, Role, RouteAllocation,
+};
 use soroban_sdk::xdr::ToXdr;
 use soroban_sdk::{
-    contract, contractimpl, token, Address, Bytes, BytesN, Env, IntoVal, Symbol, Vec,
+    contract, contractimpl, token, Address, Bytes, BytesN, Env, InvokeError, Map, String, Symbol,
+    Vec,
 };
 
 #[contract]
@@ -64,120 +70,49 @@ impl LiquidityManager {
         Ok(())
     }
 
-    // Create a new liquidity pool contract
+    // Create a new liquidity pool contract, deployed at an address derived from the pool nonce
     pub fn create_liquidity_pool(
         env: Env,
         token_addresses: Vec<Address>,
         allocation_percentage: u32,
     ) -> Result<Address, Error> {
-        // Verify caller is admin
-        Self::verify_admin(&env)?;
-
-        // Verify allocation percentage is valid (0-10000 basis points, representing 0-100%)
-        if allocation_percentage > 10000 {
-            return Err(Error::InvalidAllocation);
-        }
-
-        // Initialize an empty assets vector
-        let mut assets = Vec::new(&env);
-
-        // Validate that all tokens exist and retrieve their details
-        for token_address in token_addresses.iter() {
-            // Create token client to verify the token exists and to get token details
-            let token_client = token::Client::new(&env, &token_address);
-
-            let token_decimals = token_client.decimals();
-            let symbol = token_client.symbol();
-
-            // Create the asset with details from the token contract
-            let asset = Asset {
-                token: token_address.clone(),
-                symbol,
-                decimals: token_decimals,
-            };
-
-            // Add it to our assets vector
-            assets.push_back(asset);
-        }
-
-        // Get admin and fee collector
-        let admin = env
-            .storage()
-            .persistent()
-            .get::<_, Address>(&DataKey::Admin)
-            .ok_or(Error::Unauthorized)?;
-
-        let fee_collector = env
-            .storage()
-            .persistent()
-            .get::<_, Address>(&DataKey::FeeCollector)
-            .ok_or(Error::Unauthorized)?;
-
-        // Get the pool contract WASM hash
-        let pool_wasm_hash = env
-            .storage()
-            .persistent()
-            .get::<_, BytesN<32>>(&DataKey::PoolContractWasm)
-            .ok_or(Error::DeploymentFailed)?;
-
-        // Generate a unique salt using admin address and timestamp
-        let timestamp = env.ledger().timestamp();
-        let mut salt = Bytes::new(&env);
-        salt.append(&admin.clone().to_xdr(&env));
-        salt.append(&timestamp.to_xdr(&env));
-        let salt_hash = env.crypto().sha256(&salt);
-
-        // Deploy new pool contract
-        let pool_address = env.deployer().with_current_contract(salt_hash).deploy_v2(
-            pool_wasm_hash,
-            (
-                admin.clone(),
-                fee_collector.clone(),
-                assets.clone(),
-                allocation_percentage,
-            ),
-        );
-
-        // Store pool information
-        let pool_info = PoolInfo {
-            pool_address: pool_address.clone(),
-            assets: assets.clone(),
-            allocation_percentage,
-        };
+        Self::create_pool(&env, token_addresses, allocation_percentage, None)
+    }
 
-        // Add the pool address to the list of pools in persistent storage
-        let mut pool_addresses = env
-            .storage()
-            .persistent()
-            .get::<DataKey, Vec<Address>>(&DataKey::Pools)
-            .unwrap_or_else(|| Vec::new(&env));
+    // Create a new liquidity pool contract, deployed at an address derived from a unique label
+    pub fn create_labeled_liquidity_pool(
+        env: Env,
+        token_addresses: Vec<Address>,
+        allocation_percentage: u32,
+        label: String,
+    ) -> Result<Address, Error> {
+        Self::create_pool(&env, token_addresses, allocation_percentage, Some(label))
+    }
 
-        pool_addresses.push_back(pool_address.clone());
-        env.storage()
-            .persistent()
-            .set(&DataKey::Pools, &pool_addresses);
+    // Predict the address of the next pool, or of a pool created with the given label
+    pub fn predict_pool_address(env: Env, label: Option<String>) -> Address {
+        let salt = Self::pool_salt(&env, &label);
+        env.deployer()
+            .with_current_contract(salt)
+            .deployed_address()
+    }
 
-        // Store the pool info
+    // Look up a pool by the label it was created with
+    pub fn get_pool_by_label(env: Env, label: String) -> Result<Address, Error> {
         env.storage()
             .persistent()
-            .set(&DataKey::PoolInfo(pool_address.clone()), &pool_info);
-
-        // Emit deployment event
-        env.events().publish(
-            (Symbol::new(&env, "deploy_pool"),),
-            (pool_address.clone(), pool_info.clone()),
-        );
+            .get::<_, Address>(&DataKey::PoolLabel(label))
+            .ok_or(Error::PoolNotFound)
+    }
 
-        Ok(pool_address)
+    // Get the nonce the next unlabeled pool will be deployed with
+    pub fn get_pool_nonce(env: Env) -> u64 {
+        Self::pool_nonce(&env)
     }
 
     // Get pool info by address
     pub fn get_pool_info(env: Env, pool_address: Address) -> Result<PoolInfo, Error> {
-        let pool_info_key = DataKey::PoolInfo(pool_address);
-        env.storage()
-            .persistent()
-            .get::<_, PoolInfo>(&pool_info_key)
-            .ok_or(Error::PoolNotFound)
+        Self::read_pool_info(&env, &pool_address).ok_or(Error::PoolNotFound)
     }
 
     // List all pools
@@ -200,19 +135,14 @@ impl LiquidityManager {
         // Require depositor authorization
         depositor.require_auth();
 
-        // Forward the call to the pool contract using direct invocation
-        env.invoke_contract::<()>(
-            &pool_address,
-            &Symbol::new(&env, "add_liquidity"),
-            Vec::from_array(
-                &env,
-                [
-                    asset_address.into_val(&env),
-                    amount.into_val(&env),
-                    depositor.into_val(&env),
-                ],
+        // Forward the call to the pool contract
+        Self::pool_result(
+            LiquidityPoolClient::new(&env, &pool_address).try_add_liquidity(
+                &asset_address,
+                &amount,
+                &depositor,
             ),
-        );
+        )?;
 
         Ok(())
     }
@@ -228,19 +158,14 @@ impl LiquidityManager {
         // Require depositor authorization
         depositor.require_auth();
 
-        // Forward the call to the pool contract using direct invocation
-        env.invoke_contract::<()>(
-            &pool_address,
-            &Symbol::new(&env, "withdraw_deposited_tokens"),
-            Vec::from_array(
-                &env,
-                [
-                    asset_address.into_val(&env),
-                    amount.into_val(&env),
-                    depositor.into_val(&env),
-                ],
+        // Forward the call to the pool contract
+        Self::pool_result(
+            LiquidityPoolClient::new(&env, &pool_address).try_withdraw_deposited_tokens(
+                &asset_address,
+                &amount,
+                &depositor,
             ),
-        );
+        )?;
 
         Ok(())
     }
@@ -252,55 +177,1236 @@ impl LiquidityManager {
         asset_address: Address,
         min_threshold: i128,
         alert_threshold: i128,
+    ) -> Result<(), Error> {
+        // Verify caller is the risk manager
+        Self::verify_role(&env, Role::RiskManager)?;
+
+        // Forward the call to the pool contract
+        Self::pool_result(
+            LiquidityPoolClient::new(&env, &pool_address).try_set_thresholds(
+                &asset_address,
+                &min_threshold,
+                &alert_threshold,
+            ),
+        )?;
+
+        Ok(())
+    }
+
+    // Add a new asset to a pool and record it in the pool info
+    pub fn add_pool_asset(
+        env: Env,
+        pool_address: Address,
+        asset_address: Address,
     ) -> Result<(), Error> {
         // Verify caller is admin
         Self::verify_admin(&env)?;
 
-        // Forward the call to the pool contract using direct invocation
-        env.invoke_contract::<()>(
-            &pool_address,
-            &Symbol::new(&env, "set_thresholds"),
-            Vec::from_array(
-                &env,
-                [
-                    asset_address.into_val(&env),
-                    min_threshold.into_val(&env),
-                    alert_threshold.into_val(&env),
-                ],
-            ),
-        );
+        // Make sure the pool is managed by this factory
+        Self::get_pool_info(env.clone(), pool_address.clone())?;
+
+        // Forward the call to the pool contract, which validates the token
+        Self::pool_result(
+            LiquidityPoolClient::new(&env, &pool_address).try_add_asset(&asset_address),
+        )?;
+
+        Self::sync_pool_assets(&env, &pool_address)
+    }
+
+    // Forward to pool's delist_asset
+    pub fn delist_pool_asset(
+        env: Env,
+        pool_address: Address,
+        asset_address: Address,
+    ) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        // Make sure the pool is managed by this factory
+        Self::get_pool_info(env.clone(), pool_address.clone())?;
+
+        // Forward the call to the pool contract
+        Self::pool_result(
+            LiquidityPoolClient::new(&env, &pool_address).try_delist_asset(&asset_address),
+        )?;
 
         Ok(())
     }
 
-    // Get depositor balance from a pool
-    pub fn get_depositor_balance(
+    // Remove an emptied, delisted asset from a pool and from the pool info
+    pub fn remove_pool_asset(
+        env: Env,
+        pool_address: Address,
+        asset_address: Address,
+    ) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        // Make sure the pool is managed by this factory
+        Self::get_pool_info(env.clone(), pool_address.clone())?;
+
+        // Forward the call to the pool contract
+        Self::pool_result(
+            LiquidityPoolClient::new(&env, &pool_address).try_remove_asset(&asset_address),
+        )?;
+
+        Self::sync_pool_assets(&env, &pool_address)
+    }
+
+    // Change a pool's target allocation, keeping the total within 10000 basis points
+    pub fn set_pool_allocation(
         env: Env,
         pool_address: Address,
+        allocation_percentage: u32,
+    ) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        let mut pool_info = Self::get_pool_info(env.clone(), pool_address.clone())?;
+
+        // Verify allocations across all pools stay within 10000 basis points (100%)
+        let other_allocations = Self::total_allocation(&env) - pool_info.allocation_percentage;
+        if other_allocations + allocation_percentage > MAX_TOTAL_ALLOCATION {
+            return Err(Error::InvalidAllocation);
+        }
+
+        // Forward the call to the pool contract
+        Self::pool_result(
+            LiquidityPoolClient::new(&env, &pool_address)
+                .try_set_allocation_percentage(&allocation_percentage),
+        )?;
+
+        pool_info.allocation_percentage = allocation_percentage;
+        Self::write_pool_info(&env, &pool_info);
+
+        // Emit event for allocation update
+        env.events().publish(
+            (Symbol::new(&env, "set_allocation"), pool_address),
+            allocation_percentage,
+        );
+
+        Ok(())
+    }
+
+    // Get the sum of allocations across all pools, in basis points
+    pub fn get_total_allocation(env: Env) -> u32 {
+        Self::total_allocation(&env)
+    }
+
+    // Split one deposit across the pools holding the asset according to their allocations
+    pub fn route_deposit(
+        env: Env,
         asset_address: Address,
+        amount: i128,
         depositor: Address,
-    ) -> Result<i128, Error> {
-        // Call the pool contract directly
-        let result: i128 = env.invoke_contract::<i128>(
-            &pool_address,
-            &Symbol::new(&env, "get_depositor_balance_amount"),
-            Vec::from_array(
-                &env,
-                [asset_address.into_val(&env), depositor.into_val(&env)],
-            ),
+    ) -> Result<Vec<RouteAllocation>, Error> {
+        // Require depositor authorization
+        depositor.require_auth();
+
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        let pools = Self::allocated_pools_for_asset(&env, &asset_address);
+        if pools.is_empty() {
+            return Err(Error::PoolNotFound);
+        }
+        let total_weight = Self::total_weight(&pools);
+
+        // Pro-rata split, with the rounding remainder going to the last pool
+        let mut routes = Vec::new(&env);
+        let mut remaining = amount;
+        let last_index = pools.len() - 1;
+        for (index, pool_info) in pools.iter().enumerate() {
+            let share = if index as u32 == last_index {
+                remaining
+            } else {
+                amount * pool_info.allocation_percentage as i128 / total_weight
+            };
+            remaining -= share;
+
+            if share == 0 {
+                continue;
+            }
+
+            // Forward the deposit to the pool contract
+            Self::pool_result(
+                LiquidityPoolClient::new(&env, &pool_info.pool_address).try_add_liquidity(
+                    &asset_address,
+                    &share,
+                    &depositor,
+                ),
+            )?;
+
+            routes.push_back(RouteAllocation {
+                pool_address: pool_info.pool_address.clone(),
+                amount: share,
+            });
+        }
+
+        // Emit routing event
+        env.events().publish(
+            (Symbol::new(&env, "route_deposit"), asset_address, depositor),
+            routes.clone(),
         );
 
-        Ok(result)
+        Ok(routes)
     }
 
-    // Admin verification function
-    fn verify_admin(env: &Env) -> Result<(), Error> {
-        let admin: Address = env
+    // Report how far each pool's share of an asset has drifted from its target allocation
+    pub fn get_allocation_report(
+        env: Env,
+        asset_address: Address,
+    ) -> Result<Vec<AllocationDrift>, Error> {
+        let pools = Self::allocated_pools_for_asset(&env, &asset_address);
+        if pools.is_empty() {
+            return Err(Error::PoolNotFound);
+        }
+        let total_weight = Self::total_weight(&pools);
+
+        // Get actual balances from the token contract
+        let token_client = token::Client::new(&env, &asset_address);
+        let mut balances = Vec::new(&env);
+        let mut total_balance: i128 = 0;
+        for pool_info in pools.iter() {
+            let balance = token_client.balance(&pool_info.pool_address);
+            total_balance += balance;
+            balances.push_back(balance);
+        }
+
+        let mut report = Vec::new(&env);
+        for (pool_info, balance) in pools.iter().zip(balances.iter()) {
+            let target_percentage = (pool_info.allocation_percentage as i128
+                * BASIS_POINTS_DENOMINATOR
+                / total_weight) as u32;
+            let actual_percentage = if total_balance > 0 {
+                (balance * BASIS_POINTS_DENOMINATOR / total_balance) as u32
+            } else {
+                0
+            };
+            let target_balance =
+                total_balance * pool_info.allocation_percentage as i128 / total_weight;
+
+            report.push_back(AllocationDrift {
+                pool_address: pool_info.pool_address.clone(),
+                target_percentage,
+                actual_percentage,
+                balance,
+                target_balance,
+                drift: balance - target_balance,
+            });
+        }
+
+        Ok(report)
+    }
+
+    // Allow or disallow an address to trigger rebalances
+    pub fn set_keeper(env: Env, keeper: Address, authorized: bool) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        if authorized {
+            env.storage()
+                .persistent()
+                .set(&DataKey::Keeper(keeper.clone()), &true);
+        } else {
+            env.storage()
+                .persistent()
+                .remove(&DataKey::Keeper(keeper.clone()));
+        }
+
+        // Emit event for keeper update
+        env.events()
+            .publish((Symbol::new(&env, "set_keeper"), keeper), authorized);
+
+        Ok(())
+    }
+
+    // Check whether an address may trigger rebalances
+    pub fn is_keeper(env: Env, keeper: Address) -> bool {
+        env.storage()
+            .persistent()
+            .get::<_, bool>(&DataKey::Keeper(keeper))
+            .unwrap_or(false)
+    }
+
+    // Set the maximum amount of an asset a single rebalance may move
+    pub fn set_rebalance_limit(
+        env: Env,
+        asset_address: Address,
+        max_amount: i128,
+    ) -> Result<(), Error> {
+        // Verify caller is the risk manager
+        Self::verify_role(&env, Role::RiskManager)?;
+
+        if max_amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::RebalanceLimit(asset_address.clone()), &max_amount);
+
+        // Emit event for limit update
+        env.events().publish(
+            (Symbol::new(&env, "set_rebalance_limit"), asset_address),
+            max_amount,
+        );
+
+        Ok(())
+    }
+
+    // Get the maximum amount of an asset a single rebalance may move
+    pub fn get_rebalance_limit(env: Env, asset_address: Address) -> Result<i128, Error> {
+        env.storage()
+            .persistent()
+            .get::<_, i128>(&DataKey::RebalanceLimit(asset_address))
+            .ok_or(Error::RebalanceLimitNotSet)
+    }
+
+    // Move an asset from pools above their target allocation to pools below it
+    pub fn rebalance(
+        env: Env,
+        caller: Address,
+        asset_address: Address,
+    ) -> Result<Vec<RebalanceTransfer>, Error> {
+        // Verify caller is admin or an authorized keeper
+        caller.require_auth();
+        let admin = env
             .storage()
             .persistent()
-            .get(&DataKey::Admin)
+            .get::<_, Address>(&DataKey::Admin)
             .ok_or(Error::Unauthorized)?;
-        admin.require_auth();
+        if caller != admin && !Self::is_keeper(env.clone(), caller.clone()) {
+            return Err(Error::Unauthorized);
+        }
+
+        let mut remaining_limit = Self::get_rebalance_limit(env.clone(), asset_address.clone())?;
+        let report = Self::get_allocation_report(env.clone(), asset_address.clone())?;
+
+        // Split pools into those with movable surplus and those short of target
+        let mut sources: Vec<(Address, i128)> = Vec::new(&env);
+        let mut destinations: Vec<(Address, i128)> = Vec::new(&env);
+        for entry in report.iter() {
+            if entry.drift > 0 {
+                // Never take a source pool below its minimum threshold or into its deposits
+                let pool_client = LiquidityPoolClient::new(&env, &entry.pool_address);
+                let (_, thresholds) =
+                    Self::pool_result(pool_client.try_get_liquidity_status(&asset_address))?;
+                let movable = entry
+                    .drift
+                    .min(entry.balance - thresholds.min_threshold)
+                    .min(pool_client.get_surplus(&asset_address));
+                if movable > 0 {
+                    sources.push_back((entry.pool_address.clone(), movable));
+                }
+            } else if entry.drift < 0 {
+                destinations.push_back((entry.pool_address.clone(), -entry.drift));
+            }
+        }
+
+        // Greedily match surpluses to shortfalls until the limit is used up
+        let mut transfers = Vec::new(&env);
+        let mut source_index = 0;
+        let mut destination_index = 0;
+        while source_index < sources.len()
+            && destination_index < destinations.len()
+            && remaining_limit > 0
+        {
+            let (from_pool, surplus) = sources.get_unchecked(source_index);
+            let (to_pool, shortfall) = destinations.get_unchecked(destination_index);
+            let amount = surplus.min(shortfall).min(remaining_limit);
+
+            // Forward the transfer to the source pool
+            Self::pool_result(
+                LiquidityPoolClient::new(&env, &from_pool).try_rebalance_transfer(
+                    &asset_address,
+                    &amount,
+                    &to_pool,
+                ),
+            )?;
+            Self::pool_result(
+                LiquidityPoolClient::new(&env, &to_pool).try_receive_transfer(
+                    &asset_address,
+                    &amount,
+                    &0,
+                ),
+            )?;
+
+            transfers.push_back(RebalanceTransfer {
+                from_pool: from_pool.clone(),
+                to_pool: to_pool.clone(),
+                amount,
+            });
+            remaining_limit -= amount;
+
+            if surplus == amount {
+                source_index += 1;
+            } else {
+                sources.set(source_index, (from_pool, surplus - amount));
+            }
+            if shortfall == amount {
+                destination_index += 1;
+            } else {
+                destinations.set(destination_index, (to_pool, shortfall - amount));
+            }
+        }
+
+        // Emit rebalance event listing each transfer
+        env.events().publish(
+            (Symbol::new(&env, "rebalance"), asset_address, caller),
+            transfers.clone(),
+        );
+
+        Ok(transfers)
+    }
+
+    // Pause a pool, stopping deposits, withdrawals and drawdowns
+    pub fn pause_pool(env: Env, pool_address: Address) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        let mut pool_info = Self::get_pool_info(env.clone(), pool_address.clone())?;
+        if pool_info.status != PoolStatus::Active {
+            return Err(Error::PoolNotActive);
+        }
+
+        // Forward the call to the pool contract
+        Self::pool_result(LiquidityPoolClient::new(&env, &pool_address).try_pause())?;
+
+        pool_info.status = PoolStatus::Paused;
+        Self::set_pool_status(&env, &pool_info);
+
         Ok(())
     }
+
+    // Resume a paused pool
+    pub fn unpause_pool(env: Env, pool_address: Address) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        let mut pool_info = Self::get_pool_info(env.clone(), pool_address.clone())?;
+        if pool_info.status != PoolStatus::Paused {
+            return Err(Error::PoolNotActive);
+        }
+
+        // Forward the call to the pool contract
+        Self::pool_result(LiquidityPoolClient::new(&env, &pool_address).try_unpause())?;
+
+        pool_info.status = PoolStatus::Active;
+        Self::set_pool_status(&env, &pool_info);
+
+        Ok(())
+    }
+
+    // Deprecate a pool: it keeps serving withdrawals but gets no allocation
+    pub fn deprecate_pool(env: Env, pool_address: Address) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        let mut pool_info = Self::get_pool_info(env.clone(), pool_address.clone())?;
+        if pool_info.status == PoolStatus::Deprecated
+            || pool_info.status == PoolStatus::Migrating
+            || pool_info.status == PoolStatus::Migrated
+        {
+            return Err(Error::PoolNotActive);
+        }
+
+        // Release the pool's allocation for other pools
+        Self::pool_result(
+            LiquidityPoolClient::new(&env, &pool_address).try_set_allocation_percentage(&0u32),
+        )?;
+
+        pool_info.allocation_percentage = 0;
+        pool_info.status = PoolStatus::Deprecated;
+        Self::set_pool_status(&env, &pool_info);
+
+        Ok(())
+    }
+
+    // Upgrade a deployed pool's code in place; its storage, including depositor balances, is kept
+    pub fn upgrade_pool(
+        env: Env,
+        pool_address: Address,
+        new_wasm_hash: BytesN<32>,
+    ) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        // Make sure the pool is managed by this factory
+        Self::get_pool_info(env.clone(), pool_address.clone())?;
+
+        // Forward the call to the pool contract
+        let pool_client = LiquidityPoolClient::new(&env, &pool_address);
+        Self::pool_result(pool_client.try_upgrade(&new_wasm_hash))?;
+
+        // Pools from before the manager was recorded learn it on their first upgrade
+        Self::claim_pool(&env, &pool_client)?;
+
+        // Emit event for the upgrade
+        env.events().publish(
+            (Symbol::new(&env, "upgrade_pool"), pool_address),
+            new_wasm_hash,
+        );
+
+        Ok(())
+    }
+
+    // Start replacing a pool with a new one deployed from the current WASM; depositors then move
+    // over in pages with migrate_pool_depositors and complete_pool_migration moves the tokens
+    pub fn migrate_pool(env: Env, pool_address: Address) -> Result<Address, Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        let mut pool_info = Self::get_pool_info(env.clone(), pool_address.clone())?;
+        if pool_info.status == PoolStatus::Migrated || pool_info.status == PoolStatus::Migrating {
+            return Err(Error::PoolNotActive);
+        }
+
+        // The old pool only releases its balances to its recorded manager
+        let pool_client = LiquidityPoolClient::new(&env, &pool_address);
+        Self::claim_pool(&env, &pool_client)?;
+
+        // Depositors missing from the pool's index would be left behind
+        for asset in pool_info.assets.iter() {
+            if !pool_client.is_depositor_index_complete(&asset.token) {
+                return Err(Error::DepositorIndexIncomplete);
+            }
+        }
+
+        // The old pool hands its allocation over to the replacement
+        let allocation_percentage = pool_info.allocation_percentage;
+        Self::pool_result(pool_client.try_set_allocation_percentage(&0u32))?;
+
+        // Deploy the replacement with the same assets and allocation; its label salt is taken,
+        // so it is deployed from the nonce and inherits the label afterwards
+        let new_pool_address =
+            Self::deploy_pool(&env, pool_info.assets.clone(), allocation_percentage, None)?;
+        if let Some(label) = pool_info.label.clone() {
+            let mut new_pool_info = Self::get_pool_info(env.clone(), new_pool_address.clone())?;
+            new_pool_info.label = Some(label.clone());
+            Self::write_pool_info(&env, &new_pool_info);
+            env.storage()
+                .persistent()
+                .set(&DataKey::PoolLabel(label), &new_pool_address);
+        }
+
+        // Stop activity on the old pool while its balances move
+        if pool_info.status != PoolStatus::Paused {
+            Self::pool_result(pool_client.try_pause())?;
+        }
+
+        // Carry thresholds and yield rates over to the replacement
+        let new_pool_client = LiquidityPoolClient::new(&env, &new_pool_address);
+        for asset in pool_info.assets.iter() {
+            let (_, thresholds) =
+                Self::pool_result(pool_client.try_get_liquidity_status(&asset.token))?;
+            Self::pool_result(new_pool_client.try_set_thresholds(
+                &asset.token,
+                &thresholds.min_threshold,
+                &thresholds.alert_threshold,
+            ))?;
+            Self::pool_result(
+                new_pool_client
+                    .try_set_yield_rate(&asset.token, &pool_client.get_yield_rate(&asset.token)),
+            )?;
+        }
+
+        pool_info.allocation_percentage = 0;
+        pool_info.status = PoolStatus::Migrating;
+        pool_info.replaced_by = Some(new_pool_address.clone());
+        Self::set_pool_status(&env, &pool_info);
+
+        Ok(new_pool_address)
+    }
+
+    // Move up to `limit` depositor balances of an asset from a migrating pool to its replacement,
+    // returning how many remain
+    pub fn migrate_pool_depositors(
+        env: Env,
+        pool_address: Address,
+        asset_address: Address,
+        limit: u32,
+    ) -> Result<u32, Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        let (_, new_pool_address) = Self::migrating_pool(&env, &pool_address)?;
+        let pool_client = LiquidityPoolClient::new(&env, &pool_address);
+        let new_pool_client = LiquidityPoolClient::new(&env, &new_pool_address);
+
+        let records =
+            Self::pool_result(pool_client.try_migrate_depositors(&asset_address, &limit))?;
+        Self::pool_result(new_pool_client.try_import_depositors(&asset_address, &records))?;
+
+        Ok(pool_client.get_depositor_count(&asset_address))
+    }
+
+    // Move a migrating pool's tokens to its replacement once every depositor has moved over
+    pub fn complete_pool_migration(env: Env, pool_address: Address) -> Result<Address, Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        let (mut pool_info, new_pool_address) = Self::migrating_pool(&env, &pool_address)?;
+        let pool_client = LiquidityPoolClient::new(&env, &pool_address);
+        let new_pool_client = LiquidityPoolClient::new(&env, &new_pool_address);
+
+        // The old pool refuses while any depositor balance is still recorded there; the reward
+        // reserve moves with the tokens to pay the interest depositors carried over
+        for asset in pool_info.assets.iter() {
+            let (moved, reward_reserve) = Self::pool_result(
+                pool_client.try_migrate_liquidity(&asset.token, &new_pool_address),
+            )?;
+            Self::pool_result(new_pool_client.try_receive_transfer(
+                &asset.token,
+                &moved,
+                &reward_reserve,
+            ))?;
+        }
+
+        pool_info.status = PoolStatus::Migrated;
+        Self::set_pool_status(&env, &pool_info);
+
+        // Emit migration event
+        env.events().publish(
+            (Symbol::new(&env, "migrate_pool"), pool_address),
+            new_pool_address.clone(),
+        );
+
+        Ok(new_pool_address)
+    }
+
+    // Get the total balance of each asset held across all pools
+    pub fn get_total_value_by_asset(env: Env) -> Vec<AssetTotal> {
+        let mut totals: Map<Address, i128> = Map::new(&env);
+        for pool_info in Self::live_pools(&env).iter() {
+            for asset in pool_info.assets.iter() {
+                let token_client = token::Client::new(&env, &asset.token);
+                let balance = token_client.balance(&pool_info.pool_address);
+                let total = totals.get(asset.token.clone()).unwrap_or(0);
+                totals.set(asset.token, total + balance);
+            }
+        }
+
+        let mut asset_totals = Vec::new(&env);
+        for (token, total_balance) in totals.iter() {
+            asset_totals.push_back(AssetTotal {
+                token,
+                total_balance,
+            });
+        }
+        asset_totals
+    }
+
+    // Get a depositor's balances across every pool and asset
+    pub fn get_depositor_positions(
+        env: Env,
+        depositor: Address,
+    ) -> Result<Vec<DepositorPosition>, Error> {
+        let mut positions = Vec::new(&env);
+        for pool_info in Self::live_pools(&env).iter() {
+            let pool = LiquidityPoolClient::new(&env, &pool_info.pool_address);
+            for asset in pool_info.assets.iter() {
+                let result = pool.try_get_depositor_balance_amount(&asset.token, &depositor);
+                if let Err(Ok(Error::NoDepositorBalance)) = result {
+                    continue;
+                }
+
+                positions.push_back(DepositorPosition {
+                    pool_address: pool_info.pool_address.clone(),
+                    token: asset.token,
+                    amount: Self::pool_result(result)?,
+                });
+            }
+        }
+        Ok(positions)
+    }
+
+    // Get the pools where any asset is at or below its alert threshold
+    pub fn get_pools_in_alert(env: Env) -> Result<Vec<Address>, Error> {
+        let mut alerting_pools = Vec::new(&env);
+        for pool_info in Self::live_pools(&env).iter() {
+            let pool = LiquidityPoolClient::new(&env, &pool_info.pool_address);
+            if Self::pool_result(pool.try_check_liquidity_levels())? {
+                alerting_pools.push_back(pool_info.pool_address);
+            }
+        }
+        Ok(alerting_pools)
+    }
+
+    // Get depositor balance from a pool
+    pub fn get_depositor_balance(
+        env: Env,
+        pool_address: Address,
+        asset_address: Address,
+        depositor: Address,
+    ) -> Result<i128, Error> {
+        // Call the pool contract directly
+        let result: i128 = Self::pool_result(
+            LiquidityPoolClient::new(&env, &pool_address)
+                .try_get_depositor_balance_amount(&asset_address, &depositor),
+        )?;
+
+        Ok(result)
+    }
+
+    // Grant a role on the factory and on every pool it manages
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), Error> {
+        // Verify caller is admin
+        let admin = Self::verify_admin(&env)?;
+
+        // The threshold-setter and the withdrawer must be different accounts
+        let mut risk_manager = Self::get_role(env.clone(), Role::RiskManager);
+        let mut treasurer = Self::get_role(env.clone(), Role::Treasurer);
+        match role {
+            Role::RiskManager => risk_manager = Some(account.clone()),
+            Role::Treasurer => treasurer = Some(account.clone()),
+            Role::FeeManager | Role::Guardian => {}
+        }
+        Self::verify_duties_separated(&admin, &risk_manager, &treasurer)?;
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::Role(role.clone()), &account);
+
+        // Forward the grant to the pools
+        for pool_info in Self::live_pools(&env).iter() {
+            Self::pool_result(
+                LiquidityPoolClient::new(&env, &pool_info.pool_address)
+                    .try_grant_role(&role, &account),
+            )?;
+        }
+
+        // Emit event for the grant
+        env.events()
+            .publish((Symbol::new(&env, "role_granted"), role), account);
+
+        Ok(())
+    }
+
+    // Revoke a role on the factory and on every pool it manages
+    pub fn revoke_role(env: Env, role: Role) -> Result<(), Error> {
+        // Verify caller is admin
+        let admin = Self::verify_admin(&env)?;
+
+        // Handing risk duties back to the admin must not make it the withdrawer too
+        let account = Self::get_role(env.clone(), role.clone()).ok_or(Error::Unauthorized)?;
+        if role == Role::RiskManager {
+            let treasurer = Self::get_role(env.clone(), Role::Treasurer);
+            Self::verify_duties_separated(&admin, &None, &treasurer)?;
+        }
+
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Role(role.clone()));
+
+        // Forward the revocation to the pools still holding the role
+        for pool_info in Self::live_pools(&env).iter() {
+            let pool_client = LiquidityPoolClient::new(&env, &pool_info.pool_address);
+            if pool_client.get_role(&role).is_some() {
+                Self::pool_result(pool_client.try_revoke_role(&role))?;
+            }
+        }
+
+        // Emit event for the revocation
+        env.events()
+            .publish((Symbol::new(&env, "role_revoked"), role), account);
+
+        Ok(())
+    }
+
+    // Get the account explicitly granted a role
+    pub fn get_role(env: Env, role: Role) -> Option<Address> {
+        env.storage().persistent().get(&DataKey::Role(role))
+    }
+
+    // Get the current admin
+    pub fn get_admin(env: Env) -> Result<Address, Error> {
+        env.storage()
+            .persistent()
+            .get::<_, Address>(&DataKey::Admin)
+            .ok_or(Error::Unauthorized)
+    }
+
+    // Propose a new admin for the factory and its pools
+    pub fn propose_admin(env: Env, new_admin: Address) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::PendingAdmin, &new_admin);
+
+        // Forward the proposal to the pools
+        for pool_info in Self::live_pools(&env).iter() {
+            Self::pool_result(
+                LiquidityPoolClient::new(&env, &pool_info.pool_address)
+                    .try_propose_admin(&new_admin),
+            )?;
+        }
+
+        // Emit event for the proposal
+        env.events()
+            .publish((Symbol::new(&env, "admin_proposed"),), new_admin);
+
+        Ok(())
+    }
+
+    // Accept a pending admin handover on the factory and its pools
+    pub fn accept_admin(env: Env) -> Result<(), Error> {
+        let new_admin = env
+            .storage()
+            .persistent()
+            .get::<_, Address>(&DataKey::PendingAdmin)
+            .ok_or(Error::NoPendingAdmin)?;
+        new_admin.require_auth();
+
+        // The new admin inherits unassigned risk duties, so it must not be the treasurer
+        let risk_manager = Self::get_role(env.clone(), Role::RiskManager);
+        let treasurer = Self::get_role(env.clone(), Role::Treasurer);
+        Self::verify_duties_separated(&new_admin, &risk_manager, &treasurer)?;
+
+        let old_admin = Self::get_admin(env.clone())?;
+        env.storage().persistent().set(&DataKey::Admin, &new_admin);
+        env.storage().persistent().remove(&DataKey::PendingAdmin);
+
+        // Complete the handover on the pools
+        for pool_info in Self::live_pools(&env).iter() {
+            Self::pool_result(
+                LiquidityPoolClient::new(&env, &pool_info.pool_address).try_accept_admin(),
+            )?;
+        }
+
+        // Emit event for the handover
+        env.events().publish(
+            (Symbol::new(&env, "admin_accepted"),),
+            (old_admin, new_admin),
+        );
+
+        Ok(())
+    }
+
+    // Change the account that receives fees on the factory and every pool it manages
+    pub fn set_fee_collector(env: Env, fee_collector: Address) -> Result<(), Error> {
+        // Verify caller is the fee manager
+        Self::verify_role(&env, Role::FeeManager)?;
+
+        env.storage()
+            .persistent()
+            .set(&DataKey::FeeCollector, &fee_collector);
+
+        // Forward the change to the pools
+        for pool_info in Self::live_pools(&env).iter() {
+            Self::pool_result(
+                LiquidityPoolClient::new(&env, &pool_info.pool_address)
+                    .try_set_fee_collector(&fee_collector),
+            )?;
+        }
+
+        // Emit event for the fee collector update
+        env.events()
+            .publish((Symbol::new(&env, "set_fee_collector"),), fee_collector);
+
+        Ok(())
+    }
+
+    // Get the account that receives fees
+    pub fn get_fee_collector(env: Env) -> Result<Address, Error> {
+        env.storage()
+            .persistent()
+            .get::<_, Address>(&DataKey::FeeCollector)
+            .ok_or(Error::Unauthorized)
+    }
+
+    // Admin verification function
+    fn verify_admin(env: &Env) -> Result<Address, Error> {
+        let admin: Address = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .ok_or(Error::Unauthorized)?;
+        admin.require_auth();
+        Ok(admin)
+    }
+
+    // Role verification function; risk and fee duties default to the admin, withdrawals do not
+    fn verify_role(env: &Env, role: Role) -> Result<Address, Error> {
+        let holder = match Self::get_role(env.clone(), role.clone()) {
+            Some(holder) => holder,
+            None if role == Role::Treasurer => return Err(Error::Unauthorized),
+            None => Self::get_admin(env.clone())?,
+        };
+        holder.require_auth();
+        Ok(holder)
+    }
+
+    // Check that the effective risk manager is not also the treasurer
+    fn verify_duties_separated(
+        admin: &Address,
+        risk_manager: &Option<Address>,
+        treasurer: &Option<Address>,
+    ) -> Result<(), Error> {
+        let risk_manager = risk_manager.as_ref().unwrap_or(admin);
+        if treasurer.as_ref() == Some(risk_manager) {
+            return Err(Error::RoleConflict);
+        }
+        Ok(())
+    }
+
+    // Refresh the stored pool info with the pool's current asset list
+    fn sync_pool_assets(env: &Env, pool_address: &Address) -> Result<(), Error> {
+        let mut pool_info = Self::get_pool_info(env.clone(), pool_address.clone())?;
+
+        pool_info.assets =
+            Self::pool_result(LiquidityPoolClient::new(env, pool_address).try_get_assets())?;
+        Self::write_pool_info(env, &pool_info);
+
+        // Emit event for the asset list change
+        env.events().publish(
+            (Symbol::new(env, "pool_assets"), pool_address.clone()),
+            pool_info.assets,
+        );
+
+        Ok(())
+    }
+
+    // Sum of allocations across all managed pools
+    fn total_allocation(env: &Env) -> u32 {
+        let pool_addresses = env
+            .storage()
+            .persistent()
+            .get::<DataKey, Vec<Address>>(&DataKey::Pools)
+            .unwrap_or_else(|| Vec::new(env));
+
+        let mut total = 0;
+        for pool_address in pool_addresses.iter() {
+            if let Some(pool_info) = Self::read_pool_info(env, &pool_address) {
+                total += pool_info.allocation_percentage;
+            }
+        }
+        total
+    }
+
+    // Active pools that hold an asset, accept deposits of it and have a non-zero allocation
+    fn allocated_pools_for_asset(env: &Env, asset_address: &Address) -> Vec<PoolInfo> {
+        let pool_addresses = env
+            .storage()
+            .persistent()
+            .get::<DataKey, Vec<Address>>(&DataKey::Pools)
+            .unwrap_or_else(|| Vec::new(env));
+
+        let mut pools = Vec::new(env);
+        for pool_address in pool_addresses.iter() {
+            if let Some(pool_info) = Self::read_pool_info(env, &pool_address) {
+                let holds_asset = pool_info
+                    .assets
+                    .iter()
+                    .any(|asset| asset.token == *asset_address);
+                if !holds_asset
+                    || pool_info.allocation_percentage == 0
+                    || pool_info.status != PoolStatus::Active
+                {
+                    continue;
+                }
+
+                // The pool may have been paused or the asset delisted on the pool itself
+                let pool_client = LiquidityPoolClient::new(env, &pool_address);
+                if !pool_client.is_paused() && !pool_client.is_asset_delisted(asset_address) {
+                    pools.push_back(pool_info);
+                }
+            }
+        }
+        pools
+    }
+
+    // Record this factory as the manager of a pool that has none, as pools initialized before
+    // the manager was kept do not
+    fn claim_pool(env: &Env, pool_client: &LiquidityPoolClient) -> Result<(), Error> {
+        match pool_client.try_get_manager() {
+            Ok(Ok(manager)) if manager == env.current_contract_address() => Ok(()),
+            Ok(Ok(_)) => Err(Error::Unauthorized),
+            _ => Self::pool_result(pool_client.try_set_manager(&env.current_contract_address())),
+        }
+    }
+
+    // Sum of allocations of the given pools, used to normalise their shares
+    fn total_weight(pools: &Vec<PoolInfo>) -> i128 {
+        pools
+            .iter()
+            .map(|pool_info| pool_info.allocation_percentage as i128)
+            .sum()
+    }
+
+    // Validate the tokens and allocation, then deploy the pool
+    fn create_pool(
+        env: &Env,
+        token_addresses: Vec<Address>,
+        allocation_percentage: u32,
+        label: Option<String>,
+    ) -> Result<Address, Error> {
+        // Verify caller is admin
+        Self::verify_admin(env)?;
+
+        // Labels must be unique
+        if let Some(label) = &label {
+            if env
+                .storage()
+                .persistent()
+                .has(&DataKey::PoolLabel(label.clone()))
+            {
+                return Err(Error::PoolAlreadyExists);
+            }
+        }
+
+        // Verify allocations across all pools stay within 10000 basis points (100%)
+        if Self::total_allocation(env) + allocation_percentage > MAX_TOTAL_ALLOCATION {
+            return Err(Error::InvalidAllocation);
+        }
+
+        // Initialize an empty assets vector
+        let mut assets = Vec::new(env);
+
+        // Validate that all tokens exist and retrieve their details
+        for token_address in token_addresses.iter() {
+            // Create token client to verify the token exists and to get token details
+            let token_client = token::Client::new(env, &token_address);
+
+            let token_decimals = token_client.decimals();
+            let symbol = token_client.symbol();
+
+            // Create the asset with details from the token contract
+            let asset = Asset {
+                token: token_address.clone(),
+                symbol,
+                decimals: token_decimals,
+            };
+
+            // Add it to our assets vector
+            assets.push_back(asset);
+        }
+
+        Self::deploy_pool(env, assets, allocation_percentage, label)
+    }
+
+    // Deploy a pool contract from the stored WASM and register it with the factory
+    fn deploy_pool(
+        env: &Env,
+        assets: Vec<Asset>,
+        allocation_percentage: u32,
+        label: Option<String>,
+    ) -> Result<Address, Error> {
+        // Get admin and fee collector
+        let admin = env
+            .storage()
+            .persistent()
+            .get::<_, Address>(&DataKey::Admin)
+            .ok_or(Error::Unauthorized)?;
+
+        let fee_collector = env
+            .storage()
+            .persistent()
+            .get::<_, Address>(&DataKey::FeeCollector)
+            .ok_or(Error::Unauthorized)?;
+
+        // Get the pool contract WASM hash
+        let pool_wasm_hash = env
+            .storage()
+            .persistent()
+            .get::<_, BytesN<32>>(&DataKey::PoolContractWasm)
+            .ok_or(Error::DeploymentFailed)?;
+
+        // Derive a collision-free salt from the label or the pool nonce
+        let salt = Self::pool_salt(env, &label);
+        if label.is_none() {
+            env.storage()
+                .persistent()
+                .set(&DataKey::PoolNonce, &(Self::pool_nonce(env) + 1));
+        }
+
+        // Deploy new pool contract
+        let pool_address = env.deployer().with_current_contract(salt).deploy_v2(
+            pool_wasm_hash,
+            (
+                admin.clone(),
+                fee_collector.clone(),
+                assets.clone(),
+                allocation_percentage,
+                env.current_contract_address(),
+            ),
+        );
+
+        // Mirror the factory's roles and any pending admin handover on the new pool
+        let pool_client = LiquidityPoolClient::new(env, &pool_address);
+        for role in [
+            Role::RiskManager,
+            Role::Treasurer,
+            Role::FeeManager,
+            Role::Guardian,
+        ] {
+            if let Some(account) = Self::get_role(env.clone(), role.clone()) {
+                Self::pool_result(pool_client.try_grant_role(&role, &account))?;
+            }
+        }
+        if let Some(pending_admin) = env
+            .storage()
+            .persistent()
+            .get::<_, Address>(&DataKey::PendingAdmin)
+        {
+            Self::pool_result(pool_client.try_propose_admin(&pending_admin))?;
+        }
+
+        // Store pool information
+        let pool_info = PoolInfo {
+            pool_address: pool_address.clone(),
+            assets: assets.clone(),
+            allocation_percentage,
+            status: PoolStatus::Active,
+            replaced_by: None,
+            label: label.clone(),
+        };
+
+        // Point the label at the new pool
+        if let Some(label) = label {
+            env.storage()
+                .persistent()
+                .set(&DataKey::PoolLabel(label), &pool_address);
+        }
+
+        // Add the pool address to the list of pools in persistent storage
+        let mut pool_addresses = env
+            .storage()
+            .persistent()
+            .get::<DataKey, Vec<Address>>(&DataKey::Pools)
+            .unwrap_or_else(|| Vec::new(env));
+
+        pool_addresses.push_back(pool_address.clone());
+        env.storage()
+            .persistent()
+            .set(&DataKey::Pools, &pool_addresses);
+
+        // Store the pool info
+        Self::write_pool_info(env, &pool_info);
+
+        // Emit deployment event
+        env.events().publish(
+            (Symbol::new(env, "deploy_pool"),),
+            (pool_address.clone(), pool_info.clone()),
+        );
+
+        Ok(pool_address)
+    }
+
+    // Read the nonce the next unlabeled pool will be deployed with
+    fn pool_nonce(env: &Env) -> u64 {
+        env.storage()
+            .persistent()
+            .get::<_, u64>(&DataKey::PoolNonce)
+            .unwrap_or(0)
+    }
+
+    // Derive a deployment salt from a pool label, or from the pool nonce when unlabeled
+    fn pool_salt(env: &Env, label: &Option<String>) -> BytesN<32> {
+        let mut salt = Bytes::new(env);
+        match label {
+            Some(label) => {
+                salt.append(&Bytes::from_slice(env, b"label"));
+                salt.append(&label.clone().to_xdr(env));
+            }
+            None => {
+                salt.append(&Bytes::from_slice(env, b"nonce"));
+                salt.append(&Self::pool_nonce(env).to_xdr(env));
+            }
+        }
+        env.crypto().sha256(&salt).into()
+    }
+
+    // Store a pool's updated info and announce its status
+    fn set_pool_status(env: &Env, pool_info: &PoolInfo) {
+        Self::write_pool_info(env, pool_info);
+
+        // Emit status event
+        env.events().publish(
+            (
+                Symbol::new(env, "pool_status"),
+                pool_info.pool_address.clone(),
+            ),
+            pool_info.status.clone(),
+        );
+    }
+
+    // Read a pool's info, upgrading entries stored in the PoolInfoV1 layout
+    fn read_pool_info(env: &Env, pool_address: &Address) -> Option<PoolInfo> {
+        if let Some(pool_info) = env
+            .storage()
+            .persistent()
+            .get::<_, PoolInfo>(&DataKey::PoolInfoV2(pool_address.clone()))
+        {
+            return Some(pool_info);
+        }
+
+        env.storage()
+            .persistent()
+            .get::<_, PoolInfoV1>(&DataKey::PoolInfo(pool_address.clone()))
+            .map(|pool_info| PoolInfo {
+                pool_address: pool_info.pool_address,
+                assets: pool_info.assets,
+                allocation_percentage: pool_info.allocation_percentage,
+                status: PoolStatus::Active,
+                replaced_by: None,
+                label: None,
+            })
+    }
+
+    // Store a pool's info in the current layout, dropping any PoolInfoV1 entry it replaces
+    fn write_pool_info(env: &Env, pool_info: &PoolInfo) {
+        env.storage().persistent().set(
+            &DataKey::PoolInfoV2(pool_info.pool_address.clone()),
+            pool_info,
+        );
+        env.storage()
+            .persistent()
+            .remove(&DataKey::PoolInfo(pool_info.pool_address.clone()));
+    }
+
+    // Info of a pool being migrated, along with its replacement's address
+    fn migrating_pool(env: &Env, pool_address: &Address) -> Result<(PoolInfo, Address), Error> {
+        let pool_info = Self::get_pool_info(env.clone(), pool_address.clone())?;
+        match (&pool_info.status, &pool_info.replaced_by) {
+            (PoolStatus::Migrating, Some(new_pool_address)) => {
+                let new_pool_address = new_pool_address.clone();
+                Ok((pool_info, new_pool_address))
+            }
+            _ => Err(Error::PoolNotActive),
+        }
+    }
+
+    // Propagate a pool call's error, keeping the pool's own error code where it has one
+    fn pool_result<T, C>(
+        result: Result<Result<T, C>, Result<Error, InvokeError>>,
+    ) -> Result<T, Error> {
+        match result {
+            Ok(Ok(value)) => Ok(value),
+            Err(Ok(error)) => Err(error),
+            Ok(Err(_)) | Err(Err(_)) => Err(Error::OperationFailed),
+        }
+    }
+
+    // Pools that still hold liquidity, i.e. everything not yet migrated
+    fn live_pools(env: &Env) -> Vec<PoolInfo> {
+        let pool_addresses = env
+            .storage()
+            .persistent()
+            .get::<DataKey, Vec<Address>>(&DataKey::Pools)
+            .unwrap_or_else(|| Vec::new(env));
+
+        let mut pools = Vec::new(env);
+        for pool_address in pool_addresses.iter() {
+            if let Some(pool_info) = Self::read_pool_info(env, &pool_address) {
+                if pool_info.status != PoolStatus::Migrated {
+                    pools.push_back(pool_info);
+                }
+            }
+        }
+        pools
+    }
 }
@@ -1,6 +1,8 @@
 #![no_std]
 
+mod constants;
 mod contract;
+mod pool_client;
 mod types;
 
 pub use contract::*;
@@ -0,0 +1,120 @@
+use crate::types::{Asset, DepositorRecord, Error, Role, Thresholds};
+use soroban_sdk::{contractclient, Address, BytesN, Env, Vec};
+
+// Interface of the LiquidityPool contract, used to generate a typed client for
+// the pools this factory deploys. Pool errors share their codes with `Error`.
+#[allow(dead_code)]
+#[contractclient(name = "LiquidityPoolClient")]
+pub trait LiquidityPoolInterface {
+    fn set_thresholds(
+        env: Env,
+        asset_address: Address,
+        min_threshold: i128,
+        alert_threshold: i128,
+    ) -> Result<(), Error>;
+
+    fn add_liquidity(
+        env: Env,
+        asset_address: Address,
+        amount: i128,
+        depositor: Address,
+    ) -> Result<(), Error>;
+
+    fn withdraw_deposited_tokens(
+        env: Env,
+        asset_address: Address,
+        amount: i128,
+        depositor: Address,
+    ) -> Result<(), Error>;
+
+    fn get_depositor_balance_amount(
+        env: Env,
+        asset_address: Address,
+        depositor: Address,
+    ) -> Result<i128, Error>;
+
+    fn check_liquidity_levels(env: Env) -> Result<bool, Error>;
+
+    fn get_liquidity_status(env: Env, asset_address: Address) -> Result<(i128, Thresholds), Error>;
+
+    fn get_assets(env: Env) -> Result<Vec<Asset>, Error>;
+
+    fn set_allocation_percentage(env: Env, allocation_percentage: u32) -> Result<(), Error>;
+
+    fn add_asset(env: Env, asset_address: Address) -> Result<Asset, Error>;
+
+    fn delist_asset(env: Env, asset_address: Address) -> Result<(), Error>;
+
+    fn remove_asset(env: Env, asset_address: Address) -> Result<(), Error>;
+
+    fn is_asset_delisted(env: Env, asset_address: Address) -> bool;
+
+    fn get_surplus(env: Env, asset_address: Address) -> i128;
+
+    fn rebalance_transfer(
+        env: Env,
+        asset_address: Address,
+        amount: i128,
+        destination: Address,
+    ) -> Result<(), Error>;
+
+    fn pause(env: Env) -> Result<(), Error>;
+
+    fn unpause(env: Env) -> Result<(), Error>;
+
+    fn is_paused(env: Env) -> bool;
+
+    fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), Error>;
+
+    fn set_manager(env: Env, manager: Address) -> Result<(), Error>;
+
+    fn get_manager(env: Env) -> Result<Address, Error>;
+
+    fn get_depositor_count(env: Env, asset_address: Address) -> u32;
+
+    fn is_depositor_index_complete(env: Env, asset_address: Address) -> bool;
+
+    fn migrate_depositors(
+        env: Env,
+        asset_address: Address,
+        limit: u32,
+    ) -> Result<Vec<DepositorRecord>, Error>;
+
+    fn migrate_liquidity(
+        env: Env,
+        asset_address: Address,
+        destination: Address,
+    ) -> Result<(i128, i128), Error>;
+
+    fn import_depositors(
+        env: Env,
+        asset_address: Address,
+        records: Vec<DepositorRecord>,
+    ) -> Result<(), Error>;
+
+    fn receive_transfer(
+        env: Env,
+        asset_address: Address,
+        amount: i128,
+        reward_reserve: i128,
+    ) -> Result<(), Error>;
+
+    fn set_yield_rate(env: Env, asset_address: Address, apr_basis_points: u32)
+        -> Result<(), Error>;
+
+    fn get_yield_rate(env: Env, asset_address: Address) -> u32;
+
+    fn grant_role(env: Env, role: Role, account: Address) -> Result<(), Error>;
+
+    fn revoke_role(env: Env, role: Role) -> Result<(), Error>;
+
+    fn get_role(env: Env, role: Role) -> Option<Address>;
+
+    fn get_admin(env: Env) -> Result<Address, Error>;
+
+    fn propose_admin(env: Env, new_admin: Address) -> Result<(), Error>;
+
+    fn accept_admin(env: Env) -> Result<(), Error>;
+
+    fn set_fee_collector(env: Env, fee_collector: Address) -> Result<(), Error>;
+}
@@ -1,7 +1,11 @@
 use super::*;
 use crate::testutils::{check_balance, create_token_contract, install_pool_wasm, mint_tokens};
 use soroban_sdk::testutils::Address as _;
-use soroban_sdk::{testutils::AuthorizedFunction, token::TokenClient, Address, Env, Symbol, Vec};
+use soroban_sdk::{
+    testutils::AuthorizedFunction,
+    token::{StellarAssetClient as TokenAdmin, TokenClient},
+    Address, Env, String, Symbol, Vec,
+};
 
 #[cfg(test)]
 mod test_setup {
@@ -77,6 +81,7 @@ mod test_admin {
 
 mod test_pool_creation {
     use super::*;
+    use crate::testutils::pool_contract;
 
     #[test]
     fn test_create_pool() {
@@ -110,6 +115,10 @@ mod test_pool_creation {
         assert_eq!(pool_info.pool_address, pool_address);
         assert_eq!(pool_info.assets.len(), 1);
         assert_eq!(pool_info.allocation_percentage, allocation_percentage);
+
+        // The pool is managed by this factory from the start
+        let pool = pool_contract::Client::new(&env, &pool_address);
+        assert_eq!(pool.get_manager(), contract.address);
     }
 
     #[test]
@@ -154,4 +163,763 @@ mod test_pool_creation {
         let pool_balance = check_balance(&env, &token_address, &pool_address);
         assert_eq!(pool_balance, 5000);
     }
+
+    #[test]
+    fn test_create_pools_in_same_ledger() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+
+        let pool_wasm_hash = install_pool_wasm(&env);
+        contract.set_pool_contract_wasm(&admin, &pool_wasm_hash);
+
+        let (token_address, _) = create_token_contract(&env, &admin);
+        let token_addresses = Vec::from_array(&env, [token_address]);
+
+        // Each unlabeled pool takes the next nonce, so the salts never collide
+        let first = contract.create_liquidity_pool(&token_addresses, &3000);
+        let second = contract.create_liquidity_pool(&token_addresses, &3000);
+
+        assert_ne!(first, second);
+        assert_eq!(contract.list_pools().len(), 2);
+        assert_eq!(contract.get_pool_nonce(), 2);
+    }
+
+    #[test]
+    fn test_predict_pool_address() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+
+        let pool_wasm_hash = install_pool_wasm(&env);
+        contract.set_pool_contract_wasm(&admin, &pool_wasm_hash);
+
+        let (token_address, _) = create_token_contract(&env, &admin);
+        let token_addresses = Vec::from_array(&env, [token_address]);
+
+        // Unlabeled pools deploy to the predicted nonce address
+        let predicted = contract.predict_pool_address(&None);
+        let pool_address = contract.create_liquidity_pool(&token_addresses, &3000);
+        assert_eq!(pool_address, predicted);
+        assert_ne!(contract.predict_pool_address(&None), predicted);
+
+        // Labeled pools deploy to the predicted label address
+        let label = String::from_str(&env, "usdc-main");
+        let predicted = contract.predict_pool_address(&Some(label.clone()));
+        let labeled_address =
+            contract.create_labeled_liquidity_pool(&token_addresses, &3000, &label);
+        assert_eq!(labeled_address, predicted);
+    }
+
+    #[test]
+    fn test_get_pool_by_label() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+
+        let pool_wasm_hash = install_pool_wasm(&env);
+        contract.set_pool_contract_wasm(&admin, &pool_wasm_hash);
+
+        let (token_address, _) = create_token_contract(&env, &admin);
+        let token_addresses = Vec::from_array(&env, [token_address]);
+
+        let label = String::from_str(&env, "usdc-main");
+        let pool_address = contract.create_labeled_liquidity_pool(&token_addresses, &3000, &label);
+
+        assert_eq!(contract.get_pool_by_label(&label), pool_address);
+        assert_eq!(contract.get_pool_info(&pool_address).label, Some(label));
+
+        // Labeled pools do not consume the nonce
+        assert_eq!(contract.get_pool_nonce(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #3)")]
+    fn test_create_pool_duplicate_label() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+
+        let pool_wasm_hash = install_pool_wasm(&env);
+        contract.set_pool_contract_wasm(&admin, &pool_wasm_hash);
+
+        let (token_address, _) = create_token_contract(&env, &admin);
+        let token_addresses = Vec::from_array(&env, [token_address]);
+
+        let label = String::from_str(&env, "usdc-main");
+        contract.create_labeled_liquidity_pool(&token_addresses, &3000, &label);
+
+        // Try to reuse the label (should fail with PoolAlreadyExists error)
+        contract.create_labeled_liquidity_pool(&token_addresses, &3000, &label);
+    }
+}
+
+mod test_pool_assets {
+    use super::*;
+
+    #[test]
+    fn test_add_and_remove_pool_asset() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+
+        env.mock_all_auths();
+
+        // Install and set pool WASM hash
+        let pool_wasm_hash = install_pool_wasm(&env);
+        contract.set_pool_contract_wasm(&admin, &pool_wasm_hash);
+
+        // Create a pool with a single token
+        let (token1_address, _) = create_token_contract(&env, &admin);
+        let token_addresses = Vec::from_array(&env, [token1_address.clone()]);
+        let pool_address = contract.create_liquidity_pool(&token_addresses, &5000);
+
+        // Add a second token and check the pool info follows
+        let (token2_address, _) = create_token_contract(&env, &admin);
+        contract.add_pool_asset(&pool_address, &token2_address);
+
+        let pool_info = contract.get_pool_info(&pool_address);
+        assert_eq!(pool_info.assets.len(), 2);
+        assert_eq!(pool_info.assets.get_unchecked(1).token, token2_address);
+
+        // Delist and remove the first token
+        contract.delist_pool_asset(&pool_address, &token1_address);
+        contract.remove_pool_asset(&pool_address, &token1_address);
+
+        let pool_info = contract.get_pool_info(&pool_address);
+        assert_eq!(pool_info.assets.len(), 1);
+        assert_eq!(pool_info.assets.get_unchecked(0).token, token2_address);
+    }
+}
+
+mod test_allocations {
+    use super::*;
+
+    fn setup_pools(
+        env: &Env,
+        contract: &LiquidityManagerClient,
+        admin: &Address,
+    ) -> (Address, TokenAdmin<'static>, Address, Address) {
+        env.mock_all_auths();
+
+        // Install and set pool WASM hash
+        let pool_wasm_hash = install_pool_wasm(env);
+        contract.set_pool_contract_wasm(admin, &pool_wasm_hash);
+
+        // Create two pools for the same token, split 60/40
+        let (token_address, token_admin) = create_token_contract(env, admin);
+        let token_addresses = Vec::from_array(env, [token_address.clone()]);
+        let pool1 = contract.create_liquidity_pool(&token_addresses, &6000);
+        let pool2 = contract.create_liquidity_pool(&token_addresses, &4000);
+
+        (token_address, token_admin, pool1, pool2)
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #5)")]
+    fn test_total_allocation_capped() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let (token_address, _, _, _) = setup_pools(&env, &contract, &admin);
+        assert_eq!(contract.get_total_allocation(), 10000);
+
+        // A third pool would push the total over 100%
+        let token_addresses = Vec::from_array(&env, [token_address]);
+        contract.create_liquidity_pool(&token_addresses, &1);
+    }
+
+    #[test]
+    fn test_set_pool_allocation() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let (_, _, pool1, pool2) = setup_pools(&env, &contract, &admin);
+
+        // Shrink one pool first to make room in the other
+        contract.set_pool_allocation(&pool2, &3000);
+        contract.set_pool_allocation(&pool1, &7000);
+
+        assert_eq!(contract.get_pool_info(&pool1).allocation_percentage, 7000);
+        assert_eq!(contract.get_pool_info(&pool2).allocation_percentage, 3000);
+        assert_eq!(contract.get_total_allocation(), 10000);
+
+        // Over-allocating is rejected
+        let result = contract.try_set_pool_allocation(&pool2, &3001);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_route_deposit() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let (token_address, token_admin, pool1, pool2) = setup_pools(&env, &contract, &admin);
+
+        let depositor = Address::generate(&env);
+        mint_tokens(&token_admin, &depositor, &1001);
+
+        let routes = contract.route_deposit(&token_address, &1001, &depositor);
+        assert_eq!(routes.len(), 2);
+
+        // 60% to the first pool, the rest (including rounding) to the second
+        assert_eq!(check_balance(&env, &token_address, &pool1), 600);
+        assert_eq!(check_balance(&env, &token_address, &pool2), 401);
+        assert_eq!(
+            contract.get_depositor_balance(&pool1, &token_address, &depositor),
+            600
+        );
+    }
+
+    #[test]
+    fn test_route_deposit_skips_delisted_pool() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let (token_address, token_admin, pool1, pool2) = setup_pools(&env, &contract, &admin);
+
+        // The first pool no longer accepts the asset, so everything goes to the second
+        contract.delist_pool_asset(&pool1, &token_address);
+
+        let depositor = Address::generate(&env);
+        mint_tokens(&token_admin, &depositor, &1000);
+
+        let routes = contract.route_deposit(&token_address, &1000, &depositor);
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes.get_unchecked(0).pool_address, pool2);
+        assert_eq!(check_balance(&env, &token_address, &pool1), 0);
+        assert_eq!(check_balance(&env, &token_address, &pool2), 1000);
+    }
+
+    #[test]
+    fn test_allocation_report() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let (token_address, token_admin, pool1, pool2) = setup_pools(&env, &contract, &admin);
+
+        // Deposit everything into the first pool
+        let depositor = Address::generate(&env);
+        mint_tokens(&token_admin, &depositor, &1000);
+        contract.add_liquidity(&pool1, &token_address, &1000, &depositor);
+
+        let report = contract.get_allocation_report(&token_address);
+        assert_eq!(report.len(), 2);
+
+        let first = report.get_unchecked(0);
+        assert_eq!(first.pool_address, pool1);
+        assert_eq!(first.target_percentage, 6000);
+        assert_eq!(first.actual_percentage, 10000);
+        assert_eq!(first.target_balance, 600);
+        assert_eq!(first.drift, 400);
+
+        let second = report.get_unchecked(1);
+        assert_eq!(second.pool_address, pool2);
+        assert_eq!(second.actual_percentage, 0);
+        assert_eq!(second.drift, -400);
+    }
+}
+
+mod test_rebalance {
+    use super::*;
+
+    fn setup_unbalanced_pools(
+        env: &Env,
+        contract: &LiquidityManagerClient,
+        admin: &Address,
+    ) -> (Address, Address, Address) {
+        env.mock_all_auths();
+
+        // Install and set pool WASM hash
+        let pool_wasm_hash = install_pool_wasm(env);
+        contract.set_pool_contract_wasm(admin, &pool_wasm_hash);
+
+        // Two pools split 50/50, with everything held by the first: 1000 deposited
+        // and 1000 the pool owes nobody
+        let (token_address, token_admin) = create_token_contract(env, admin);
+        let token_addresses = Vec::from_array(env, [token_address.clone()]);
+        let pool1 = contract.create_liquidity_pool(&token_addresses, &5000);
+        let pool2 = contract.create_liquidity_pool(&token_addresses, &5000);
+
+        let depositor = Address::generate(env);
+        mint_tokens(&token_admin, &depositor, &1000);
+        contract.add_liquidity(&pool1, &token_address, &1000, &depositor);
+        mint_tokens(&token_admin, &pool1, &1000);
+
+        (token_address, pool1, pool2)
+    }
+
+    #[test]
+    fn test_rebalance_to_target() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let (token_address, pool1, pool2) = setup_unbalanced_pools(&env, &contract, &admin);
+
+        contract.set_rebalance_limit(&token_address, &10000);
+        let transfers = contract.rebalance(&admin, &token_address);
+
+        assert_eq!(transfers.len(), 1);
+        let transfer = transfers.get_unchecked(0);
+        assert_eq!(transfer.from_pool, pool1);
+        assert_eq!(transfer.to_pool, pool2);
+        assert_eq!(transfer.amount, 1000);
+
+        assert_eq!(check_balance(&env, &token_address, &pool1), 1000);
+        assert_eq!(check_balance(&env, &token_address, &pool2), 1000);
+    }
+
+    #[test]
+    fn test_rebalance_keeps_deposits() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let (token_address, pool1, pool2) = setup_unbalanced_pools(&env, &contract, &admin);
+
+        // Another 1000 deposited raises the first pool's drift but not its surplus
+        let token_admin = TokenAdmin::new(&env, &token_address);
+        let depositor = Address::generate(&env);
+        mint_tokens(&token_admin, &depositor, &1000);
+        contract.add_liquidity(&pool1, &token_address, &1000, &depositor);
+
+        contract.set_rebalance_limit(&token_address, &10000);
+        contract.rebalance(&admin, &token_address);
+
+        assert_eq!(check_balance(&env, &token_address, &pool1), 2000);
+        assert_eq!(check_balance(&env, &token_address, &pool2), 1000);
+    }
+
+    #[test]
+    fn test_rebalance_by_keeper_within_limit() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let (token_address, pool1, pool2) = setup_unbalanced_pools(&env, &contract, &admin);
+
+        let keeper = Address::generate(&env);
+        contract.set_keeper(&keeper, &true);
+        assert!(contract.is_keeper(&keeper));
+
+        // Only 200 may move per rebalance
+        contract.set_rebalance_limit(&token_address, &200);
+        contract.rebalance(&keeper, &token_address);
+
+        assert_eq!(check_balance(&env, &token_address, &pool1), 1800);
+        assert_eq!(check_balance(&env, &token_address, &pool2), 200);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_rebalance_unauthorized() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let (token_address, _, _) = setup_unbalanced_pools(&env, &contract, &admin);
+
+        contract.set_rebalance_limit(&token_address, &10000);
+        contract.rebalance(&Address::generate(&env), &token_address);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #19)")]
+    fn test_rebalance_without_limit() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let (token_address, _, _) = setup_unbalanced_pools(&env, &contract, &admin);
+
+        contract.rebalance(&admin, &token_address);
+    }
+}
+
+mod test_lifecycle {
+    use super::*;
+    use crate::testutils::pool_contract;
+    use crate::types::{DataKey, Error, PoolInfoV1, PoolStatus};
+    use soroban_sdk::testutils::Ledger;
+
+    fn setup_funded_pool(
+        env: &Env,
+        contract: &LiquidityManagerClient,
+        admin: &Address,
+    ) -> (Address, Address, Address) {
+        env.mock_all_auths();
+
+        // Install and set pool WASM hash
+        let pool_wasm_hash = install_pool_wasm(env);
+        contract.set_pool_contract_wasm(admin, &pool_wasm_hash);
+
+        let (token_address, token_admin) = create_token_contract(env, admin);
+        let token_addresses = Vec::from_array(env, [token_address.clone()]);
+        let pool_address = contract.create_liquidity_pool(&token_addresses, &5000);
+
+        let depositor = Address::generate(env);
+        mint_tokens(&token_admin, &depositor, &1000);
+        contract.add_liquidity(&pool_address, &token_address, &1000, &depositor);
+
+        (token_address, pool_address, depositor)
+    }
+
+    #[test]
+    fn test_pause_and_unpause_pool() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let (token_address, pool_address, depositor) = setup_funded_pool(&env, &contract, &admin);
+
+        contract.pause_pool(&pool_address);
+        assert_eq!(
+            contract.get_pool_info(&pool_address).status,
+            PoolStatus::Paused
+        );
+
+        // Withdrawals are blocked while paused
+        let result =
+            contract.try_withdraw_deposited_tokens(&pool_address, &token_address, &100, &depositor);
+        assert!(result.is_err());
+
+        contract.unpause_pool(&pool_address);
+        assert_eq!(
+            contract.get_pool_info(&pool_address).status,
+            PoolStatus::Active
+        );
+        contract.withdraw_deposited_tokens(&pool_address, &token_address, &100, &depositor);
+    }
+
+    #[test]
+    fn test_deprecate_pool() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let (_, pool_address, _) = setup_funded_pool(&env, &contract, &admin);
+
+        contract.deprecate_pool(&pool_address);
+
+        let pool_info = contract.get_pool_info(&pool_address);
+        assert_eq!(pool_info.status, PoolStatus::Deprecated);
+        assert_eq!(pool_info.allocation_percentage, 0);
+        assert_eq!(contract.get_total_allocation(), 0);
+    }
+
+    #[test]
+    fn test_migrate_pool() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let (token_address, pool_address, depositor) = setup_funded_pool(&env, &contract, &admin);
+
+        // The pool pays 10% interest out of a reserve of 300
+        let old_pool = pool_contract::Client::new(&env, &pool_address);
+        let token_admin = TokenAdmin::new(&env, &token_address);
+        mint_tokens(&token_admin, &admin, &300);
+        old_pool.set_yield_rate(&token_address, &1000);
+        old_pool.fund_reward_reserve(&token_address, &300);
+        env.ledger().with_mut(|li| li.timestamp += 31_536_000);
+
+        let new_pool_address = contract.migrate_pool(&pool_address);
+
+        // The old pool hands its allocation to the new one, in the factory and in the pool
+        let old_info = contract.get_pool_info(&pool_address);
+        assert_eq!(old_info.status, PoolStatus::Migrating);
+        assert_eq!(old_info.replaced_by, Some(new_pool_address.clone()));
+        assert_eq!(old_info.allocation_percentage, 0);
+        assert_eq!(old_pool.get_allocation_percentage(), 0);
+
+        let new_info = contract.get_pool_info(&new_pool_address);
+        assert_eq!(new_info.status, PoolStatus::Active);
+        assert_eq!(new_info.allocation_percentage, 5000);
+        assert_eq!(contract.get_total_allocation(), 5000);
+
+        // Tokens stay until every depositor has moved over
+        let result = contract.try_complete_pool_migration(&pool_address);
+        assert_eq!(result, Err(Ok(Error::DepositorsRemaining)));
+
+        let remaining = contract.migrate_pool_depositors(&pool_address, &token_address, &10);
+        assert_eq!(remaining, 0);
+        contract.complete_pool_migration(&pool_address);
+        assert_eq!(
+            contract.get_pool_info(&pool_address).status,
+            PoolStatus::Migrated
+        );
+
+        // Liquidity and the depositor's balance moved across
+        assert_eq!(check_balance(&env, &token_address, &pool_address), 0);
+        assert_eq!(check_balance(&env, &token_address, &new_pool_address), 1300);
+        assert_eq!(
+            contract.get_depositor_balance(&new_pool_address, &token_address, &depositor),
+            1000
+        );
+
+        // So did the unpaid interest, the reserve paying it and the rate
+        let new_pool = pool_contract::Client::new(&env, &new_pool_address);
+        assert_eq!(
+            new_pool.get_accrued_interest(&token_address, &depositor),
+            100
+        );
+        assert_eq!(new_pool.get_pool_totals(&token_address).reward_reserve, 300);
+        assert_eq!(new_pool.get_yield_rate(&token_address), 1000);
+    }
+
+    #[test]
+    fn test_migrate_pool_without_recorded_manager() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let (token_address, pool_address, depositor) = setup_funded_pool(&env, &contract, &admin);
+
+        // A pool initialized before pools recorded their manager
+        env.as_contract(&pool_address, || {
+            env.storage()
+                .persistent()
+                .remove(&pool_contract::DataKey::Manager);
+        });
+
+        let new_pool_address = contract.migrate_pool(&pool_address);
+        let old_pool = pool_contract::Client::new(&env, &pool_address);
+        assert_eq!(old_pool.get_manager(), contract.address);
+
+        contract.migrate_pool_depositors(&pool_address, &token_address, &10);
+        contract.complete_pool_migration(&pool_address);
+        assert_eq!(
+            contract.get_pool_info(&pool_address).status,
+            PoolStatus::Migrated
+        );
+        assert_eq!(check_balance(&env, &token_address, &new_pool_address), 1000);
+        assert_eq!(
+            contract.get_depositor_balance(&new_pool_address, &token_address, &depositor),
+            1000
+        );
+    }
+
+    #[test]
+    fn test_upgrade_pool_records_manager() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let (_, pool_address, _) = setup_funded_pool(&env, &contract, &admin);
+
+        env.as_contract(&pool_address, || {
+            env.storage()
+                .persistent()
+                .remove(&pool_contract::DataKey::Manager);
+        });
+
+        contract.upgrade_pool(&pool_address, &install_pool_wasm(&env));
+
+        let pool = pool_contract::Client::new(&env, &pool_address);
+        assert_eq!(pool.get_manager(), contract.address);
+    }
+
+    #[test]
+    fn test_migrate_labeled_pool() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        env.mock_all_auths();
+
+        let pool_wasm_hash = install_pool_wasm(&env);
+        contract.set_pool_contract_wasm(&admin, &pool_wasm_hash);
+
+        let (token_address, _) = create_token_contract(&env, &admin);
+        let token_addresses = Vec::from_array(&env, [token_address]);
+        let label = String::from_str(&env, "usdc-main");
+        let pool_address = contract.create_labeled_liquidity_pool(&token_addresses, &5000, &label);
+
+        let new_pool_address = contract.migrate_pool(&pool_address);
+
+        // The label follows the pool to its replacement
+        assert_eq!(contract.get_pool_by_label(&label), new_pool_address);
+        assert_eq!(contract.get_pool_info(&new_pool_address).label, Some(label));
+    }
+
+    #[test]
+    fn test_read_pool_info_stored_before_status() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let (token_address, pool_address, _) = setup_funded_pool(&env, &contract, &admin);
+
+        // Rewrite the pool's info in the layout earlier versions stored
+        let pool_info = contract.get_pool_info(&pool_address);
+        env.as_contract(&contract.address, || {
+            env.storage()
+                .persistent()
+                .remove(&DataKey::PoolInfoV2(pool_address.clone()));
+            env.storage().persistent().set(
+                &DataKey::PoolInfo(pool_address.clone()),
+                &PoolInfoV1 {
+                    pool_address: pool_address.clone(),
+                    assets: pool_info.assets.clone(),
+                    allocation_percentage: pool_info.allocation_percentage,
+                },
+            );
+        });
+
+        // It reads as an active pool and is rewritten in the current layout on change
+        assert_eq!(contract.get_pool_info(&pool_address), pool_info);
+        assert_eq!(contract.get_total_allocation(), 5000);
+        contract.pause_pool(&pool_address);
+        assert_eq!(
+            contract.get_pool_info(&pool_address).status,
+            PoolStatus::Paused
+        );
+        env.as_contract(&contract.address, || {
+            assert!(!env
+                .storage()
+                .persistent()
+                .has(&DataKey::PoolInfo(pool_address.clone())));
+        });
+        assert_eq!(
+            contract.get_total_value_by_asset().get_unchecked(0).token,
+            token_address
+        );
+    }
+}
+
+mod test_aggregated_views {
+    use super::*;
+    use crate::types::Error;
+
+    #[test]
+    fn test_pool_errors_propagate() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        env.mock_all_auths();
+
+        let pool_wasm_hash = install_pool_wasm(&env);
+        contract.set_pool_contract_wasm(&admin, &pool_wasm_hash);
+        let (token_address, _) = create_token_contract(&env, &admin);
+        let token_addresses = Vec::from_array(&env, [token_address.clone()]);
+        let pool_address = contract.create_liquidity_pool(&token_addresses, &5000);
+
+        // The pool's own error comes back to the manager's caller
+        let result = contract.try_set_thresholds(&pool_address, &token_address, &5000, &1000);
+        assert_eq!(result, Err(Ok(Error::InvalidThreshold)));
+    }
+
+    #[test]
+    fn test_totals_positions_and_alerts() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        env.mock_all_auths();
+
+        let pool_wasm_hash = install_pool_wasm(&env);
+        contract.set_pool_contract_wasm(&admin, &pool_wasm_hash);
+
+        // Two pools holding the same token
+        let (token_address, token_admin) = create_token_contract(&env, &admin);
+        let token_addresses = Vec::from_array(&env, [token_address.clone()]);
+        let pool1 = contract.create_liquidity_pool(&token_addresses, &5000);
+        let pool2 = contract.create_liquidity_pool(&token_addresses, &5000);
+
+        let depositor = Address::generate(&env);
+        mint_tokens(&token_admin, &depositor, &1500);
+        contract.add_liquidity(&pool1, &token_address, &1000, &depositor);
+        contract.add_liquidity(&pool2, &token_address, &500, &depositor);
+
+        // Total value per asset sums both pools
+        let totals = contract.get_total_value_by_asset();
+        assert_eq!(totals.len(), 1);
+        assert_eq!(totals.get_unchecked(0).token, token_address);
+        assert_eq!(totals.get_unchecked(0).total_balance, 1500);
+
+        // The depositor has a position in each pool
+        let positions = contract.get_depositor_positions(&depositor);
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions.get_unchecked(0).pool_address, pool1);
+        assert_eq!(positions.get_unchecked(0).amount, 1000);
+        assert_eq!(positions.get_unchecked(1).pool_address, pool2);
+        assert_eq!(positions.get_unchecked(1).amount, 500);
+
+        // Someone with no deposits has no positions
+        let positions = contract.get_depositor_positions(&Address::generate(&env));
+        assert_eq!(positions.len(), 0);
+
+        // Only the second pool is at or below its alert threshold
+        contract.set_thresholds(&pool1, &token_address, &100, &800);
+        contract.set_thresholds(&pool2, &token_address, &100, &800);
+        let alerting = contract.get_pools_in_alert();
+        assert_eq!(alerting, Vec::from_array(&env, [pool2]));
+    }
+}
+
+mod test_roles {
+    use super::*;
+    use crate::pool_client::LiquidityPoolClient;
+    use crate::types::Role;
+
+    fn setup_pool(env: &Env, contract: &LiquidityManagerClient, admin: &Address) -> Address {
+        env.mock_all_auths();
+
+        let pool_wasm_hash = install_pool_wasm(env);
+        contract.set_pool_contract_wasm(admin, &pool_wasm_hash);
+
+        let (token_address, _) = create_token_contract(env, admin);
+        let token_addresses = Vec::from_array(env, [token_address]);
+        contract.create_liquidity_pool(&token_addresses, &3000)
+    }
+
+    #[test]
+    fn test_grant_role_reaches_pools() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let pool_address = setup_pool(&env, &contract, &admin);
+
+        let risk_manager = Address::generate(&env);
+        contract.grant_role(&Role::RiskManager, &risk_manager);
+        assert_eq!(
+            contract.get_role(&Role::RiskManager),
+            Some(risk_manager.clone())
+        );
+
+        // Existing pools receive the grant, and new pools start with it
+        let pool_client = LiquidityPoolClient::new(&env, &pool_address);
+        assert_eq!(
+            pool_client.get_role(&Role::RiskManager),
+            Some(risk_manager.clone())
+        );
+        let (token_address, _) = create_token_contract(&env, &admin);
+        let new_pool =
+            contract.create_liquidity_pool(&Vec::from_array(&env, [token_address]), &3000);
+        assert_eq!(
+            LiquidityPoolClient::new(&env, &new_pool).get_role(&Role::RiskManager),
+            Some(risk_manager)
+        );
+
+        contract.revoke_role(&Role::RiskManager);
+        assert_eq!(pool_client.get_role(&Role::RiskManager), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #22)")]
+    fn test_treasurer_cannot_be_risk_manager() {
+        let env = Env::default();
+        let (contract, _, _) = test_setup::setup_contract(&env);
+        env.mock_all_auths();
+
+        let account = Address::generate(&env);
+        contract.grant_role(&Role::Treasurer, &account);
+
+        // Try to make the withdrawer the threshold-setter (should fail with RoleConflict error)
+        contract.grant_role(&Role::RiskManager, &account);
+    }
+
+    #[test]
+    fn test_two_step_admin_handover() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        let pool_address = setup_pool(&env, &contract, &admin);
+
+        let new_admin = Address::generate(&env);
+        contract.propose_admin(&new_admin);
+        assert_eq!(contract.get_admin(), admin);
+
+        // A pool created during the handover is handed over too
+        let (token_address, _) = create_token_contract(&env, &admin);
+        let new_pool =
+            contract.create_liquidity_pool(&Vec::from_array(&env, [token_address]), &3000);
+
+        contract.accept_admin();
+        assert_eq!(contract.get_admin(), new_admin);
+        assert_eq!(
+            LiquidityPoolClient::new(&env, &pool_address).get_admin(),
+            new_admin
+        );
+        assert_eq!(
+            LiquidityPoolClient::new(&env, &new_pool).get_admin(),
+            new_admin
+        );
+    }
+
+    #[test]
+    fn test_set_fee_collector() {
+        let env = Env::default();
+        let (contract, admin, _) = test_setup::setup_contract(&env);
+        setup_pool(&env, &contract, &admin);
+
+        let fee_manager = Address::generate(&env);
+        let new_collector = Address::generate(&env);
+        contract.grant_role(&Role::FeeManager, &fee_manager);
+        contract.set_fee_collector(&new_collector);
+
+        assert_eq!(contract.get_fee_collector(), new_collector);
+    }
 }
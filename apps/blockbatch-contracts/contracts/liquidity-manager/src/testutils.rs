@@ -7,7 +7,7 @@ use soroban_sdk::{
 };
 
 // Import the WASM file for the liquidity pool contract
-mod pool_contract {
+pub(crate) mod pool_contract {
     soroban_sdk::contractimport!(
         file = "../../target/wasm32-unknown-unknown/release/liquidity_pool.wasm"
     );
@@ -9,12 +9,106 @@ pub struct Asset {
     pub decimals: u32,
 }
 
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Role {
+    RiskManager = 1, // Sets thresholds, alerts and rebalance limits
+    Treasurer = 2,   // Performs admin withdrawals from pools
+    FeeManager = 3,  // Manages the fee collector
+    Guardian = 4,    // Cancels queued admin withdrawals on pools
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PoolStatus {
+    Active = 1,
+    Paused = 2,
+    Deprecated = 3,
+    Migrated = 4,
+    Migrating = 5,
+}
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct PoolInfo {
     pub pool_address: Address,
     pub assets: Vec<Asset>,
     pub allocation_percentage: u32,
+    pub status: PoolStatus,
+    pub replaced_by: Option<Address>, // Replacement pool once migrated
+    pub label: Option<String>,        // Label the pool's address was derived from
+}
+
+// Pool info as stored before pools had a status, replacement or label
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolInfoV1 {
+    pub pool_address: Address,
+    pub assets: Vec<Asset>,
+    pub allocation_percentage: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DepositorBalance {
+    pub amount: i128,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DepositorRecord {
+    pub depositor: Address,
+    pub balance: DepositorBalance,
+    pub accrued_interest: i128, // Interest settled but not yet paid
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Thresholds {
+    pub min_threshold: i128,
+    pub alert_threshold: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RouteAllocation {
+    pub pool_address: Address,
+    pub amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AllocationDrift {
+    pub pool_address: Address,
+    pub target_percentage: u32, // Target share of the asset in basis points
+    pub actual_percentage: u32, // Current share of the asset in basis points
+    pub balance: i128,          // Current balance of the asset in the pool
+    pub target_balance: i128,   // Balance the pool would hold at its target share
+    pub drift: i128,            // balance - target_balance
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RebalanceTransfer {
+    pub from_pool: Address,
+    pub to_pool: Address,
+    pub amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AssetTotal {
+    pub token: Address,
+    pub total_balance: i128, // Balance held across all pools
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DepositorPosition {
+    pub pool_address: Address,
+    pub token: Address,
+    pub amount: i128,
 }
 
 #[contracttype]
@@ -39,15 +133,43 @@ pub enum Error {
     InvalidAsset = 10,
     NoDepositorBalance = 11,
     DeploymentFailed = 12,
+    ConsumerNotAllowed = 13,
+    SpendingCapExceeded = 14,
+    InvalidAmount = 15,
+    AssetAlreadyExists = 16,
+    AssetDelisted = 17,
+    AssetNotEmpty = 18,
+    RebalanceLimitNotSet = 19,
+    PoolPaused = 20,
+    PoolNotActive = 21,
+    RoleConflict = 22,
+    NoPendingAdmin = 23,
+    WithdrawalTimelocked = 24,
+    OutflowCapExceeded = 25,
+    WithdrawalNotFound = 26,
+    RewardReserveExhausted = 27,
+    CheckpointNotFound = 28,
+    InsufficientSurplus = 29,
+    DepositorIndexIncomplete = 30,
+    DepositorsRemaining = 31,
+    PolicyChangeTimelocked = 32,
+    PolicyChangeNotFound = 33,
 }
 
 // Helper enum for storage keys - Factory Contract
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum DataKey {
-    Admin,             // Admin address
-    FeeCollector,      // Fee collector address
-    Pools,             // List of all pool addresses
-    PoolInfo(Address), // Pool info for a specific address
-    PoolContractWasm,  // WASM hash for the pool contract
+    Admin,                   // Admin address
+    FeeCollector,            // Fee collector address
+    Pools,                   // List of all pool addresses
+    PoolInfo(Address),       // Pool info stored in the PoolInfoV1 layout
+    PoolContractWasm,        // WASM hash for the pool contract
+    Keeper(Address),         // Whether an address may trigger rebalances
+    RebalanceLimit(Address), // Maximum amount of an asset moved per rebalance
+    PoolNonce,               // Nonce for the next unlabeled pool's salt
+    PoolLabel(String),       // Pool deployed under a label
+    Role(Role),              // Account holding a role
+    PendingAdmin,            // Admin proposed but not yet accepted
+    PoolInfoV2(Address),     // Pool info for a specific address
 }
@@ -1,3 +1,11 @@
 // Define fee constants
 pub const WITHDRAWAL_FEE_BASIS_POINTS: i128 = 10; // 0.1% (10 basis points)
 pub const BASIS_POINTS_DENOMINATOR: i128 = 10000; // 100% = 10000 basis points
+
+// Define interest constants
+pub const SECONDS_PER_YEAR: i128 = 31_536_000; // 365 days, the period a yield rate applies to
+
+// Admin withdrawal policy every asset starts with: nothing can be withdrawn until the admin
+// loosens it, and loosening waits out the timelock
+pub const DEFAULT_WITHDRAWAL_TIMELOCK: u64 = 172_800; // 2 days
+pub const DEFAULT_OUTFLOW_PERIOD: u64 = 86_400; // 1 day
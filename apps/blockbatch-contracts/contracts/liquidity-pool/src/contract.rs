@@ -1,6 +1,11 @@
 use crate::constants::*;
-use crate::types::{AlertType, Asset, DataKey as PoolDataKey, DepositorBalance, Error, Thresholds};
-use soroban_sdk::{contract, contractimpl, token, Address, Env, Symbol, Vec};
+use crate::types::{
+    AlertType, Asset, AssetSnapshot, BalanceSnapshot, Checkpoint, ConsumerAllowance,
+    DataKey as PoolDataKey, DepositorBalance, DepositorRecord, Error, InterestIndex, PolicyChange,
+    PoolTotals, QueuedPolicyChange, QueuedWithdrawal, ReconciliationReport, Role, Thresholds,
+    WithdrawalPolicy,
+};
+use soroban_sdk::{contract, contractimpl, token, Address, BytesN, Env, String, Symbol, Vec};
 
 #[contract]
 pub struct LiquidityPool;
@@ -13,6 +18,7 @@ impl LiquidityPool {
         fee_collector: Address,
         pool_assets: Vec<Asset>,
         allocation_percentage: u32,
+        manager: Address,
     ) -> Result<(), Error> {
         Self::initialize(
             env,
@@ -20,16 +26,18 @@ impl LiquidityPool {
             fee_collector,
             pool_assets,
             allocation_percentage,
+            manager,
         )
     }
 
-    // Initialize the pool with given parameters
+    // Initialize the pool with given parameters; the manager cannot be changed afterwards
     pub fn initialize(
         env: Env,
         admin: Address,
         fee_collector: Address,
         pool_assets: Vec<Asset>,
         allocation_percentage: u32,
+        manager: Address,
     ) -> Result<(), Error> {
         // Ensure pool is not already initialized
         if env.storage().persistent().has(&PoolDataKey::Admin) {
@@ -44,20 +52,30 @@ impl LiquidityPool {
             .persistent()
             .set(&PoolDataKey::FeeCollector, &fee_collector);
 
-        // Store pool assets
+        // Store pool assets; a new pool has no depositors missing from its indexes
         env.storage()
             .persistent()
             .set(&PoolDataKey::Assets, &pool_assets);
+        for asset in pool_assets.iter() {
+            env.storage()
+                .persistent()
+                .set(&PoolDataKey::DepositorsIndexed(asset.token), &true);
+        }
 
         // Store allocation percentage
         env.storage()
             .persistent()
             .set(&PoolDataKey::AllocationPercentage, &allocation_percentage);
 
+        // Record the factory contract that may move liquidity between its pools
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::Manager, &manager);
+
         // Emit initialization event
         env.events().publish(
             (Symbol::new(&env, "initialize"),),
-            (admin, fee_collector, allocation_percentage),
+            (admin, fee_collector, allocation_percentage, manager),
         );
 
         Ok(())
@@ -70,8 +88,8 @@ impl LiquidityPool {
         min_threshold: i128,
         alert_threshold: i128,
     ) -> Result<(), Error> {
-        // Verify caller is admin
-        Self::verify_admin(&env)?;
+        // Verify caller is the risk manager
+        Self::verify_role(&env, Role::RiskManager)?;
 
         // Verify thresholds are valid
         if min_threshold < 0 || alert_threshold < 0 || min_threshold > alert_threshold {
@@ -111,6 +129,9 @@ impl LiquidityPool {
         // Require depositor authorization
         depositor.require_auth();
 
+        // Paused pools accept no deposits or withdrawals
+        Self::verify_not_paused(&env)?;
+
         // Verify asset exists in the pool
         let assets = env
             .storage()
@@ -123,12 +144,21 @@ impl LiquidityPool {
             return Err(Error::AssetNotFound);
         }
 
+        // Delisted assets accept withdrawals only
+        if Self::is_asset_delisted(env.clone(), asset_address.clone()) {
+            return Err(Error::AssetDelisted);
+        }
+
         // Create token client
         let token_client = token::Client::new(&env, &asset_address);
 
         // Transfer tokens from depositor to the contract
         token_client.transfer(&depositor, &env.current_contract_address(), &amount);
 
+        // Settle interest and keep the checkpointed balance before it changes
+        Self::settle_interest(&env, &asset_address, &depositor);
+        Self::record_balance_history(&env, &asset_address, &depositor);
+
         // Record depositor's balance
         let depositor_key = PoolDataKey::DepositorBalance(asset_address.clone(), depositor.clone());
 
@@ -148,11 +178,20 @@ impl LiquidityPool {
             timestamp: env.ledger().timestamp(),
         };
 
+        // Index depositors so their balances can be migrated
+        Self::track_depositor(&env, &asset_address, &depositor);
+
         // Save to storage
         env.storage()
             .persistent()
             .set(&depositor_key, &new_depositor_balance);
 
+        // Track the deposit in the pool totals
+        Self::update_totals(&env, &asset_address, |totals| {
+            totals.total_deposits += amount;
+            totals.tracked_balance += amount;
+        });
+
         // Emit event for balance update
         env.events().publish(
             (
@@ -176,6 +215,9 @@ impl LiquidityPool {
         // Require depositor authorization
         depositor.require_auth();
 
+        // Paused pools accept no deposits or withdrawals
+        Self::verify_not_paused(&env)?;
+
         // Verify asset exists in the pool
         let assets = env
             .storage()
@@ -188,6 +230,11 @@ impl LiquidityPool {
             return Err(Error::AssetNotFound);
         }
 
+        // Settle interest and keep the checkpointed balance before it changes
+        Self::settle_interest(&env, &asset_address, &depositor);
+        Self::record_balance_history(&env, &asset_address, &depositor);
+        Self::track_depositor(&env, &asset_address, &depositor);
+
         // Get depositor's balance
         let depositor_key = PoolDataKey::DepositorBalance(asset_address.clone(), depositor.clone());
         let depositor_balance = env
@@ -263,12 +310,20 @@ impl LiquidityPool {
         // If balance is zero, remove the entry, otherwise update it
         if updated_depositor_balance.amount <= 0 {
             env.storage().persistent().remove(&depositor_key);
+            Self::release_depositor(&env, &asset_address, &depositor);
         } else {
             env.storage()
                 .persistent()
                 .set(&depositor_key, &updated_depositor_balance);
         }
 
+        // Track the withdrawal and its fee in the pool totals
+        Self::update_totals(&env, &asset_address, |totals| {
+            totals.total_deposits -= amount;
+            totals.total_fees += fee;
+            totals.tracked_balance -= amount;
+        });
+
         // Then transfer tokens
         token_client.transfer(
             &env.current_contract_address(),
@@ -322,92 +377,324 @@ impl LiquidityPool {
         Ok(balance.amount)
     }
 
-    // Admin-only withdraw function
+    // Treasurer-only withdraw function for amounts below the asset's timelock threshold
     pub fn admin_withdraw_funds(
         env: Env,
         asset_address: Address,
         amount: i128,
+    ) -> Result<(), Error> {
+        // Verify caller is the treasurer
+        let treasurer = Self::verify_role(&env, Role::Treasurer)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        // Large withdrawals must wait out the timelock
+        let policy = Self::get_withdrawal_policy(env.clone(), asset_address.clone());
+        if amount >= policy.timelock_threshold {
+            return Err(Error::WithdrawalTimelocked);
+        }
+
+        Self::pay_admin_withdrawal(&env, &asset_address, amount, &treasurer)
+    }
+
+    // Tighten the timelock and per-period outflow cap for admin withdrawals of an asset;
+    // loosening them has to go through queue_withdrawal_policy
+    pub fn set_withdrawal_policy(
+        env: Env,
+        asset_address: Address,
+        timelock_threshold: i128,
+        timelock: u64,
+        period_cap: i128,
+        period_length: u64,
     ) -> Result<(), Error> {
         // Verify caller is admin
         Self::verify_admin(&env)?;
 
-        let admin: Address = env
+        // Verify threshold, cap and period are valid
+        if timelock_threshold <= 0 || period_cap <= 0 || period_length == 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        // Only changes that make every limit at least as strict take effect at once
+        let current = Self::get_withdrawal_policy(env.clone(), asset_address.clone());
+        if timelock_threshold > current.timelock_threshold
+            || timelock < current.timelock
+            || period_cap > current.period_cap
+            || period_length < current.period_length
+        {
+            return Err(Error::PolicyChangeTimelocked);
+        }
+
+        Self::apply_withdrawal_policy(
+            &env,
+            &asset_address,
+            timelock_threshold,
+            timelock,
+            period_cap,
+            period_length,
+        );
+
+        Ok(())
+    }
+
+    // Queue a withdrawal policy change for an asset behind the asset's current timelock
+    pub fn queue_withdrawal_policy(
+        env: Env,
+        asset_address: Address,
+        timelock_threshold: i128,
+        timelock: u64,
+        period_cap: i128,
+        period_length: u64,
+    ) -> Result<u64, Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        // Verify threshold, cap and period are valid
+        if timelock_threshold <= 0 || period_cap <= 0 || period_length == 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        Ok(Self::queue_policy_change(
+            &env,
+            &asset_address,
+            PolicyChange::WithdrawalPolicy(timelock_threshold, timelock, period_cap, period_length),
+        ))
+    }
+
+    // Get the admin withdrawal policy for an asset, or the restrictive default if none was set
+    pub fn get_withdrawal_policy(env: Env, asset_address: Address) -> WithdrawalPolicy {
+        env.storage()
+            .persistent()
+            .get(&PoolDataKey::WithdrawalPolicy(asset_address))
+            .unwrap_or(WithdrawalPolicy {
+                timelock_threshold: 0,
+                timelock: DEFAULT_WITHDRAWAL_TIMELOCK,
+                period_cap: 0,
+                period_length: DEFAULT_OUTFLOW_PERIOD,
+                period_start: 0,
+                withdrawn_in_period: 0,
+            })
+    }
+
+    // Apply a queued allowance or withdrawal policy change once its timelock has passed
+    pub fn execute_policy_change(env: Env, id: u64) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        let queued = env
             .storage()
             .persistent()
-            .get(&PoolDataKey::Admin)
-            .ok_or(Error::Unauthorized)?;
+            .get::<PoolDataKey, QueuedPolicyChange>(&PoolDataKey::QueuedPolicyChange(id))
+            .ok_or(Error::PolicyChangeNotFound)?;
 
-        // Verify asset exists in the pool
-        let assets = env
+        if env.ledger().timestamp() < queued.execute_after {
+            return Err(Error::PolicyChangeTimelocked);
+        }
+
+        // The asset may have been removed while the change waited
+        Self::verify_asset(&env, &queued.asset)?;
+
+        Self::dequeue_policy_change(&env, id);
+        match queued.change.clone() {
+            PolicyChange::ConsumerAllowance(consumer, spending_cap, period_length) => {
+                Self::apply_consumer_allowance(
+                    &env,
+                    &consumer,
+                    &queued.asset,
+                    spending_cap,
+                    period_length,
+                );
+            }
+            PolicyChange::WithdrawalPolicy(
+                timelock_threshold,
+                timelock,
+                period_cap,
+                period_length,
+            ) => {
+                Self::apply_withdrawal_policy(
+                    &env,
+                    &queued.asset,
+                    timelock_threshold,
+                    timelock,
+                    period_cap,
+                    period_length,
+                );
+            }
+        }
+
+        // Emit event for the executed change
+        env.events().publish(
+            (Symbol::new(&env, "policy_change_executed"), id),
+            (queued.asset, queued.change),
+        );
+
+        Ok(())
+    }
+
+    // Cancel a queued allowance or withdrawal policy change
+    pub fn cancel_policy_change(env: Env, id: u64) -> Result<(), Error> {
+        // Verify caller is the guardian
+        Self::verify_role(&env, Role::Guardian)?;
+
+        let queued = env
             .storage()
             .persistent()
-            .get::<PoolDataKey, Vec<Asset>>(&PoolDataKey::Assets)
-            .ok_or(Error::AssetNotFound)?;
+            .get::<PoolDataKey, QueuedPolicyChange>(&PoolDataKey::QueuedPolicyChange(id))
+            .ok_or(Error::PolicyChangeNotFound)?;
 
-        let asset_found = assets.iter().any(|asset| asset.token == asset_address);
-        if !asset_found {
-            return Err(Error::AssetNotFound);
+        Self::dequeue_policy_change(&env, id);
+
+        // Emit event for the cancelled change
+        env.events().publish(
+            (Symbol::new(&env, "policy_change_cancelled"), id),
+            (queued.asset, queued.change),
+        );
+
+        Ok(())
+    }
+
+    // List allowance and withdrawal policy changes waiting to execute
+    pub fn get_queued_policy_changes(env: Env) -> Vec<QueuedPolicyChange> {
+        let mut changes = Vec::new(&env);
+        for id in Self::policy_change_queue(&env).iter() {
+            if let Some(change) = env
+                .storage()
+                .persistent()
+                .get::<PoolDataKey, QueuedPolicyChange>(&PoolDataKey::QueuedPolicyChange(id))
+            {
+                changes.push_back(change);
+            }
         }
+        changes
+    }
 
-        // Check current token balance using token client
-        let token_client = token::Client::new(&env, &asset_address);
-        let current_balance = token_client.balance(&env.current_contract_address());
+    // Queue an admin withdrawal behind the asset's timelock
+    pub fn queue_admin_withdrawal(
+        env: Env,
+        asset_address: Address,
+        amount: i128,
+    ) -> Result<u64, Error> {
+        // Verify caller is the treasurer
+        Self::verify_role(&env, Role::Treasurer)?;
 
-        // Check if there's enough balance
-        if current_balance < amount {
-            return Err(Error::InsufficientBalance);
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
         }
 
-        // Get thresholds for this asset
-        let threshold_key = PoolDataKey::Thresholds(asset_address.clone());
-        let thresholds = env
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        let timelock = Self::get_withdrawal_policy(env.clone(), asset_address.clone()).timelock;
+
+        // Take the next withdrawal id
+        let id = env
             .storage()
             .persistent()
-            .get::<PoolDataKey, Thresholds>(&threshold_key)
-            .unwrap_or_else(|| Thresholds {
-                min_threshold: 0,
-                alert_threshold: 0,
-            });
+            .get::<PoolDataKey, u64>(&PoolDataKey::NextWithdrawalId)
+            .unwrap_or(0);
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::NextWithdrawalId, &(id + 1));
+
+        // Store the withdrawal and add it to the public queue
+        let withdrawal = QueuedWithdrawal {
+            id,
+            asset: asset_address.clone(),
+            amount,
+            execute_after: env.ledger().timestamp() + timelock,
+        };
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::QueuedWithdrawal(id), &withdrawal);
 
-        // Check if withdrawal would go below minimum threshold
-        if (current_balance - amount) < thresholds.min_threshold {
-            return Err(Error::BelowMinThreshold);
+        let mut queue = Self::withdrawal_queue(&env);
+        queue.push_back(id);
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::WithdrawalQueue, &queue);
+
+        // Emit event so depositors can see the pending outflow
+        env.events().publish(
+            (Symbol::new(&env, "withdrawal_queued"), id),
+            (asset_address, amount, withdrawal.execute_after),
+        );
+
+        Ok(id)
+    }
+
+    // Execute a queued admin withdrawal once its timelock has passed
+    pub fn execute_admin_withdrawal(env: Env, id: u64) -> Result<(), Error> {
+        // Verify caller is the treasurer
+        let treasurer = Self::verify_role(&env, Role::Treasurer)?;
+
+        let withdrawal = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, QueuedWithdrawal>(&PoolDataKey::QueuedWithdrawal(id))
+            .ok_or(Error::WithdrawalNotFound)?;
+
+        if env.ledger().timestamp() < withdrawal.execute_after {
+            return Err(Error::WithdrawalTimelocked);
         }
 
-        // Calculate fee using global fee constant
-        let fee = amount * WITHDRAWAL_FEE_BASIS_POINTS / BASIS_POINTS_DENOMINATOR;
-        let withdrawal_amount = amount - fee;
+        Self::dequeue_withdrawal(&env, id);
+        Self::pay_admin_withdrawal(&env, &withdrawal.asset, withdrawal.amount, &treasurer)?;
 
-        // Get fee collector address
-        let fee_collector = env
+        // Emit event for the executed withdrawal
+        env.events().publish(
+            (Symbol::new(&env, "withdrawal_executed"), id),
+            (withdrawal.asset, withdrawal.amount),
+        );
+
+        Ok(())
+    }
+
+    // Cancel a queued admin withdrawal
+    pub fn cancel_admin_withdrawal(env: Env, id: u64) -> Result<(), Error> {
+        // Verify caller is the guardian
+        Self::verify_role(&env, Role::Guardian)?;
+
+        let withdrawal = env
             .storage()
             .persistent()
-            .get::<PoolDataKey, Address>(&PoolDataKey::FeeCollector)
-            .ok_or(Error::Unauthorized)?;
+            .get::<PoolDataKey, QueuedWithdrawal>(&PoolDataKey::QueuedWithdrawal(id))
+            .ok_or(Error::WithdrawalNotFound)?;
 
-        // Transfer the withdrawal amount to the stored admin (retrieved above)
-        token_client.transfer(&env.current_contract_address(), &admin, &withdrawal_amount);
+        Self::dequeue_withdrawal(&env, id);
 
-        // Transfer the fee to fee collector
-        if fee > 0 {
-            token_client.transfer(&env.current_contract_address(), &fee_collector, &fee);
+        // Emit event for the cancelled withdrawal
+        env.events().publish(
+            (Symbol::new(&env, "withdrawal_cancelled"), id),
+            (withdrawal.asset, withdrawal.amount),
+        );
 
-            // Update fee balance record (for tracking purposes)
-            let fee_balance_key = PoolDataKey::FeeBalance(asset_address.clone());
-            let current_fee_balance = env
-                .storage()
-                .persistent()
-                .get::<PoolDataKey, i128>(&fee_balance_key)
-                .unwrap_or(0);
+        Ok(())
+    }
 
-            // Update fee balance
-            let new_fee_balance = current_fee_balance + fee;
-            env.storage()
+    // List admin withdrawals waiting to execute
+    pub fn get_queued_withdrawals(env: Env) -> Vec<QueuedWithdrawal> {
+        let mut withdrawals = Vec::new(&env);
+        for id in Self::withdrawal_queue(&env).iter() {
+            if let Some(withdrawal) = env
+                .storage()
                 .persistent()
-                .set(&fee_balance_key, &new_fee_balance);
+                .get::<PoolDataKey, QueuedWithdrawal>(&PoolDataKey::QueuedWithdrawal(id))
+            {
+                withdrawals.push_back(withdrawal);
+            }
         }
-
-        Ok(())
+        withdrawals
     }
 
     // Check liquidity levels and return true if any alert threshold is crossed
@@ -454,8 +741,8 @@ impl LiquidityPool {
         asset_address: Address,
         alert_type: AlertType,
     ) -> Result<(), Error> {
-        // Verify caller is admin
-        Self::verify_admin(&env)?;
+        // Verify caller is the risk manager
+        Self::verify_role(&env, Role::RiskManager)?;
 
         // Verify asset exists in the pool
         let assets = env
@@ -564,14 +851,1996 @@ impl LiquidityPool {
         Ok(allocation)
     }
 
-    // Admin verification function
-    fn verify_admin(env: &Env) -> Result<(), Error> {
-        let admin: Address = env
-            .storage()
+    // Pause deposits, withdrawals and drawdowns
+    pub fn pause(env: Env) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        env.storage().persistent().set(&PoolDataKey::Paused, &true);
+
+        // Emit event for pause
+        env.events().publish((Symbol::new(&env, "paused"),), ());
+
+        Ok(())
+    }
+
+    // Resume deposits, withdrawals and drawdowns
+    pub fn unpause(env: Env) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        env.storage().persistent().remove(&PoolDataKey::Paused);
+
+        // Emit event for unpause
+        env.events().publish((Symbol::new(&env, "unpaused"),), ());
+
+        Ok(())
+    }
+
+    // Check whether the pool is paused
+    pub fn is_paused(env: Env) -> bool {
+        env.storage()
             .persistent()
-            .get(&PoolDataKey::Admin)
-            .ok_or(Error::Unauthorized)?;
-        admin.require_auth();
+            .get::<PoolDataKey, bool>(&PoolDataKey::Paused)
+            .unwrap_or(false)
+    }
+
+    // Upgrade the pool contract code in place, keeping all storage
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        env.deployer()
+            .update_current_contract_wasm(new_wasm_hash.clone());
+
+        // Emit event for upgrade
+        env.events()
+            .publish((Symbol::new(&env, "upgraded"),), new_wasm_hash);
+
+        Ok(())
+    }
+
+    // Get a page of the depositors holding a balance of an asset
+    pub fn get_depositors(
+        env: Env,
+        asset_address: Address,
+        start: u32,
+        limit: u32,
+    ) -> Vec<Address> {
+        let end = Self::get_depositor_count(env.clone(), asset_address.clone())
+            .min(start.saturating_add(limit));
+        let mut depositors = Vec::new(&env);
+        for position in start..end {
+            if let Some(depositor) =
+                env.storage()
+                    .persistent()
+                    .get::<PoolDataKey, Address>(&PoolDataKey::DepositorAt(
+                        asset_address.clone(),
+                        position,
+                    ))
+            {
+                depositors.push_back(depositor);
+            }
+        }
+        depositors
+    }
+
+    // Get the number of depositors in an asset's index
+    pub fn get_depositor_count(env: Env, asset_address: Address) -> u32 {
+        env.storage()
+            .persistent()
+            .get::<PoolDataKey, u32>(&PoolDataKey::DepositorCount(asset_address))
+            .unwrap_or(0)
+    }
+
+    // Check whether every depositor of an asset is in its index
+    pub fn is_depositor_index_complete(env: Env, asset_address: Address) -> bool {
+        env.storage()
+            .persistent()
+            .get::<PoolDataKey, bool>(&PoolDataKey::DepositorsIndexed(asset_address))
+            .unwrap_or(false)
+    }
+
+    // Add depositors from before the index existed, as found in past balance events
+    pub fn index_depositors(
+        env: Env,
+        asset_address: Address,
+        depositors: Vec<Address>,
+    ) -> Result<u32, Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        // Only addresses actually holding a balance are indexed
+        let mut indexed = 0;
+        for depositor in depositors.iter() {
+            if env
+                .storage()
+                .persistent()
+                .has(&PoolDataKey::DepositorBalance(
+                    asset_address.clone(),
+                    depositor.clone(),
+                ))
+                && Self::track_depositor(&env, &asset_address, &depositor)
+            {
+                indexed += 1;
+            }
+        }
+
+        // Emit event for the backfill
+        env.events().publish(
+            (Symbol::new(&env, "depositors_indexed"), asset_address),
+            indexed,
+        );
+
+        Ok(indexed)
+    }
+
+    // Confirm every depositor of an asset has been indexed, allowing its liquidity to migrate
+    pub fn complete_depositor_index(env: Env, asset_address: Address) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        env.storage().persistent().set(
+            &PoolDataKey::DepositorsIndexed(asset_address.clone()),
+            &true,
+        );
+
+        // Emit event for the completed index
+        let count = Self::get_depositor_count(env.clone(), asset_address.clone());
+        env.events().publish(
+            (Symbol::new(&env, "depositor_index_complete"), asset_address),
+            count,
+        );
+
         Ok(())
     }
+
+    // Hand over up to `limit` depositor balances of an asset to a replacement pool
+    pub fn migrate_depositors(
+        env: Env,
+        asset_address: Address,
+        limit: u32,
+    ) -> Result<Vec<DepositorRecord>, Error> {
+        // Only the managing factory may migrate
+        let manager = Self::get_manager(env.clone())?;
+        manager.require_auth();
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        // Depositors missing from the index would be left behind
+        if !Self::is_depositor_index_complete(env.clone(), asset_address.clone()) {
+            return Err(Error::DepositorIndexIncomplete);
+        }
+
+        // Take depositors from the end of the index so positions stay stable
+        let count = Self::get_depositor_count(env.clone(), asset_address.clone());
+        let start = count.saturating_sub(limit);
+        let mut records = Vec::new(&env);
+        for depositor in
+            Self::get_depositors(env.clone(), asset_address.clone(), start, limit).iter()
+        {
+            // Settle interest so the unpaid part moves along with the balance
+            Self::settle_interest(&env, &asset_address, &depositor);
+            Self::record_balance_history(&env, &asset_address, &depositor);
+
+            let depositor_key =
+                PoolDataKey::DepositorBalance(asset_address.clone(), depositor.clone());
+            let accrued_key =
+                PoolDataKey::AccruedInterest(asset_address.clone(), depositor.clone());
+            let balance = env
+                .storage()
+                .persistent()
+                .get::<PoolDataKey, DepositorBalance>(&depositor_key)
+                .unwrap_or(DepositorBalance {
+                    amount: 0,
+                    timestamp: env.ledger().timestamp(),
+                });
+            let accrued_interest = env
+                .storage()
+                .persistent()
+                .get::<PoolDataKey, i128>(&accrued_key)
+                .unwrap_or(0);
+            records.push_back(DepositorRecord {
+                depositor: depositor.clone(),
+                balance,
+                accrued_interest,
+            });
+
+            env.storage().persistent().remove(&depositor_key);
+            env.storage().persistent().remove(&accrued_key);
+            env.storage()
+                .persistent()
+                .remove(&PoolDataKey::InterestSnapshot(
+                    asset_address.clone(),
+                    depositor.clone(),
+                ));
+            Self::unindex_depositor(&env, &asset_address, &depositor);
+        }
+
+        // The deposits now belong to the destination pool
+        let migrated_deposits: i128 = records.iter().map(|record| record.balance.amount).sum();
+        Self::update_totals(&env, &asset_address, |totals| {
+            totals.total_deposits -= migrated_deposits;
+        });
+
+        // Emit migration event
+        env.events().publish(
+            (Symbol::new(&env, "depositors_out"), asset_address),
+            (records.len(), migrated_deposits),
+        );
+
+        Ok(records)
+    }
+
+    // Move all tokens of an asset to a replacement pool once its depositors have been handed over,
+    // returning the amount moved and the part of it that was the reward reserve
+    pub fn migrate_liquidity(
+        env: Env,
+        asset_address: Address,
+        destination: Address,
+    ) -> Result<(i128, i128), Error> {
+        // Only the managing factory may migrate
+        let manager = Self::get_manager(env.clone())?;
+        manager.require_auth();
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        // Tokens backing balances still recorded here must stay
+        if !Self::is_depositor_index_complete(env.clone(), asset_address.clone()) {
+            return Err(Error::DepositorIndexIncomplete);
+        }
+        if Self::get_depositor_count(env.clone(), asset_address.clone()) > 0 {
+            return Err(Error::DepositorsRemaining);
+        }
+
+        // Transfer the pool's whole balance of the asset
+        let token_client = token::Client::new(&env, &asset_address);
+        let balance = token_client.balance(&env.current_contract_address());
+        if balance > 0 {
+            token_client.transfer(&env.current_contract_address(), &destination, &balance);
+        }
+
+        // The tokens, including the reserve paying migrated depositors' interest, now belong to
+        // the destination pool. Donations on top of the tracked balance go along with them but
+        // were never tracked, so they are not taken off it.
+        let totals = Self::get_pool_totals(env.clone(), asset_address.clone());
+        let reward_reserve = totals.reward_reserve.min(balance).max(0);
+        let tracked = totals.tracked_balance.min(balance).max(0);
+        Self::update_totals(&env, &asset_address, |totals| {
+            totals.tracked_balance -= tracked;
+            totals.reward_reserve -= reward_reserve;
+        });
+
+        // Emit migration event
+        env.events().publish(
+            (Symbol::new(&env, "migrate_out"), asset_address),
+            (destination, balance, reward_reserve),
+        );
+
+        Ok((balance, reward_reserve))
+    }
+
+    // Take over depositor balances from a pool being migrated into this one
+    pub fn import_depositors(
+        env: Env,
+        asset_address: Address,
+        records: Vec<DepositorRecord>,
+    ) -> Result<(), Error> {
+        // Only the managing factory may migrate
+        let manager = Self::get_manager(env.clone())?;
+        manager.require_auth();
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        for record in records.iter() {
+            let depositor_key =
+                PoolDataKey::DepositorBalance(asset_address.clone(), record.depositor.clone());
+
+            // Merge with any balance the depositor already holds here, settling its interest first
+            Self::settle_interest(&env, &asset_address, &record.depositor);
+            Self::record_balance_history(&env, &asset_address, &record.depositor);
+            Self::track_depositor(&env, &asset_address, &record.depositor);
+            Self::add_accrued_interest(
+                &env,
+                &asset_address,
+                &record.depositor,
+                record.accrued_interest,
+            );
+            if record.balance.amount <= 0 {
+                continue;
+            }
+
+            let imported_balance = match env
+                .storage()
+                .persistent()
+                .get::<PoolDataKey, DepositorBalance>(&depositor_key)
+            {
+                Some(existing) => DepositorBalance {
+                    amount: existing.amount + record.balance.amount,
+                    timestamp: env.ledger().timestamp(),
+                },
+                None => record.balance.clone(),
+            };
+            env.storage()
+                .persistent()
+                .set(&depositor_key, &imported_balance);
+
+            // Emit event for balance update
+            env.events().publish(
+                (
+                    Symbol::new(&env, "DepositorBalanceUpdated"),
+                    asset_address.clone(),
+                    record.depositor.clone(),
+                ),
+                imported_balance,
+            );
+        }
+
+        // Track the imported deposits in the pool totals
+        let imported_deposits: i128 = records.iter().map(|record| record.balance.amount).sum();
+        Self::update_totals(&env, &asset_address, |totals| {
+            totals.total_deposits += imported_deposits;
+        });
+
+        Ok(())
+    }
+
+    // Account for liquidity the managing factory moved into this pool from another pool, part of
+    // which may be reward reserve carried over by a migration
+    pub fn receive_transfer(
+        env: Env,
+        asset_address: Address,
+        amount: i128,
+        reward_reserve: i128,
+    ) -> Result<(), Error> {
+        // Only the managing factory moves liquidity between pools
+        let manager = Self::get_manager(env.clone())?;
+        manager.require_auth();
+
+        if amount < 0 || reward_reserve < 0 || reward_reserve > amount {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        Self::update_totals(&env, &asset_address, |totals| {
+            totals.tracked_balance += amount;
+            totals.reward_reserve += reward_reserve;
+        });
+
+        // Emit event for the incoming transfer
+        env.events().publish(
+            (Symbol::new(&env, "transfer_in"), asset_address),
+            (amount, reward_reserve),
+        );
+
+        Ok(())
+    }
+
+    // Get the tracked accounting totals for an asset
+    pub fn get_pool_totals(env: Env, asset_address: Address) -> PoolTotals {
+        env.storage()
+            .persistent()
+            .get::<PoolDataKey, PoolTotals>(&PoolDataKey::Totals(asset_address))
+            .unwrap_or_default()
+    }
+
+    // Compare the pool's tracked totals for an asset with its actual token balance
+    pub fn reconcile(env: Env, asset_address: Address) -> Result<ReconciliationReport, Error> {
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        let totals = Self::get_pool_totals(env.clone(), asset_address.clone());
+        let token_client = token::Client::new(&env, &asset_address);
+        let actual_balance = token_client.balance(&env.current_contract_address());
+
+        Ok(ReconciliationReport {
+            asset: asset_address,
+            actual_balance,
+            tracked_balance: totals.tracked_balance,
+            total_deposits: totals.total_deposits,
+            unattributed: actual_balance - totals.tracked_balance,
+            deposit_shortfall: (totals.total_deposits - actual_balance).max(0),
+        })
+    }
+
+    // Sweep tokens sent to the pool outside its own flows
+    pub fn sweep_surplus(
+        env: Env,
+        asset_address: Address,
+        recipient: Address,
+    ) -> Result<i128, Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        // Deposits from before the totals were tracked are only counted once indexed
+        if !Self::is_depositor_index_complete(env.clone(), asset_address.clone()) {
+            return Err(Error::DepositorIndexIncomplete);
+        }
+
+        // Only the unattributed part of the balance can be swept, and never what the pool owes
+        let report = Self::reconcile(env.clone(), asset_address.clone())?;
+        let amount = report
+            .unattributed
+            .min(report.actual_balance - Self::liabilities(&env, &asset_address));
+        if amount <= 0 {
+            return Err(Error::InsufficientBalance);
+        }
+
+        Self::update_totals(&env, &asset_address, |totals| {
+            totals.swept_donations += amount;
+        });
+
+        let token_client = token::Client::new(&env, &asset_address);
+        token_client.transfer(&env.current_contract_address(), &recipient, &amount);
+
+        // Emit event for the sweep
+        env.events().publish(
+            (Symbol::new(&env, "sweep_surplus"), asset_address),
+            (recipient, amount),
+        );
+
+        Ok(amount)
+    }
+
+    // Set the interest APR, in basis points, paid on deposits of an asset; zero disables interest
+    pub fn set_yield_rate(
+        env: Env,
+        asset_address: Address,
+        apr_basis_points: u32,
+    ) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        // Bring the interest index up to date at the old rate so the new rate only applies from now
+        env.storage().persistent().set(
+            &PoolDataKey::InterestIndex(asset_address.clone()),
+            &InterestIndex {
+                value: Self::interest_index(&env, &asset_address),
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+
+        env.storage().persistent().set(
+            &PoolDataKey::YieldRate(asset_address.clone()),
+            &apr_basis_points,
+        );
+
+        // Emit event for the rate change
+        env.events().publish(
+            (Symbol::new(&env, "set_yield_rate"), asset_address),
+            apr_basis_points,
+        );
+
+        Ok(())
+    }
+
+    // Get the interest APR, in basis points, paid on deposits of an asset
+    pub fn get_yield_rate(env: Env, asset_address: Address) -> u32 {
+        env.storage()
+            .persistent()
+            .get::<PoolDataKey, u32>(&PoolDataKey::YieldRate(asset_address))
+            .unwrap_or(0)
+    }
+
+    // Top up the reserve that pays depositor interest for an asset
+    pub fn fund_reward_reserve(
+        env: Env,
+        asset_address: Address,
+        amount: i128,
+    ) -> Result<(), Error> {
+        // Verify caller is admin
+        let admin = Self::verify_admin(&env)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        // Transfer the reward funds from the admin
+        let token_client = token::Client::new(&env, &asset_address);
+        token_client.transfer(&admin, &env.current_contract_address(), &amount);
+
+        Self::update_totals(&env, &asset_address, |totals| {
+            totals.reward_reserve += amount;
+            totals.tracked_balance += amount;
+        });
+
+        // Emit event for the top-up
+        env.events()
+            .publish((Symbol::new(&env, "fund_rewards"), asset_address), amount);
+
+        Ok(())
+    }
+
+    // Get the interest a depositor has accrued on an asset and not yet claimed
+    pub fn get_accrued_interest(env: Env, asset_address: Address, depositor: Address) -> i128 {
+        let accrued = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, i128>(&PoolDataKey::AccruedInterest(
+                asset_address.clone(),
+                depositor.clone(),
+            ))
+            .unwrap_or(0);
+
+        match env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, DepositorBalance>(&PoolDataKey::DepositorBalance(
+                asset_address.clone(),
+                depositor.clone(),
+            )) {
+            Some(balance) => {
+                accrued + Self::pending_interest(&env, &asset_address, &depositor, &balance)
+            }
+            None => accrued,
+        }
+    }
+
+    // Pay a depositor's accrued interest out of the reward reserve
+    pub fn claim_interest(
+        env: Env,
+        asset_address: Address,
+        depositor: Address,
+    ) -> Result<i128, Error> {
+        // Require depositor authorization
+        depositor.require_auth();
+
+        // Paused pools make no payments
+        Self::verify_not_paused(&env)?;
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        let interest = Self::take_interest(&env, &asset_address, &depositor)?;
+        Self::update_totals(&env, &asset_address, |totals| {
+            totals.tracked_balance -= interest;
+        });
+
+        // Transfer the interest to the depositor
+        let token_client = token::Client::new(&env, &asset_address);
+        token_client.transfer(&env.current_contract_address(), &depositor, &interest);
+        Self::release_depositor(&env, &asset_address, &depositor);
+
+        // Emit event for the claim
+        env.events().publish(
+            (
+                Symbol::new(&env, "interest_claimed"),
+                asset_address,
+                depositor,
+            ),
+            interest,
+        );
+
+        Ok(interest)
+    }
+
+    // Add a depositor's accrued interest to their balance
+    pub fn compound_interest(
+        env: Env,
+        asset_address: Address,
+        depositor: Address,
+    ) -> Result<i128, Error> {
+        // Require depositor authorization
+        depositor.require_auth();
+
+        // Paused pools accept no deposits or withdrawals
+        Self::verify_not_paused(&env)?;
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        let interest = Self::take_interest(&env, &asset_address, &depositor)?;
+        Self::update_totals(&env, &asset_address, |totals| {
+            totals.total_deposits += interest;
+        });
+
+        // Credit the interest to the depositor's balance
+        Self::record_balance_history(&env, &asset_address, &depositor);
+        let depositor_key = PoolDataKey::DepositorBalance(asset_address.clone(), depositor.clone());
+        let existing_balance = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, DepositorBalance>(&depositor_key);
+        Self::track_depositor(&env, &asset_address, &depositor);
+        let new_depositor_balance = DepositorBalance {
+            amount: existing_balance.map(|balance| balance.amount).unwrap_or(0) + interest,
+            timestamp: env.ledger().timestamp(),
+        };
+        env.storage()
+            .persistent()
+            .set(&depositor_key, &new_depositor_balance);
+
+        // Emit event for balance update
+        env.events().publish(
+            (
+                Symbol::new(&env, "DepositorBalanceUpdated"),
+                asset_address.clone(),
+                depositor.clone(),
+            ),
+            new_depositor_balance,
+        );
+
+        // Emit event for the compounding
+        env.events().publish(
+            (
+                Symbol::new(&env, "interest_compounded"),
+                asset_address,
+                depositor,
+            ),
+            interest,
+        );
+
+        Ok(interest)
+    }
+
+    // Take a checkpoint of per-asset totals; depositor balances are kept as they next change
+    pub fn create_checkpoint(env: Env) -> Result<u64, Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        let assets = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, Vec<Asset>>(&PoolDataKey::Assets)
+            .ok_or(Error::AssetNotFound)?;
+
+        // Capture the totals of every asset
+        let mut snapshots = Vec::new(&env);
+        for asset in assets.iter() {
+            let totals = Self::get_pool_totals(env.clone(), asset.token.clone());
+            let token_client = token::Client::new(&env, &asset.token);
+            snapshots.push_back(AssetSnapshot {
+                asset: asset.token,
+                total_deposits: totals.total_deposits,
+                tracked_balance: totals.tracked_balance,
+                actual_balance: token_client.balance(&env.current_contract_address()),
+            });
+        }
+
+        let id = Self::get_latest_checkpoint_id(env.clone()) + 1;
+        let checkpoint = Checkpoint {
+            id,
+            timestamp: env.ledger().timestamp(),
+            assets: snapshots,
+        };
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::Checkpoint(id), &checkpoint);
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::CheckpointCount, &id);
+
+        // Emit checkpoint event
+        env.events()
+            .publish((Symbol::new(&env, "checkpoint"), id), checkpoint.timestamp);
+
+        Ok(id)
+    }
+
+    // Get the id of the latest checkpoint, or zero if none has been taken
+    pub fn get_latest_checkpoint_id(env: Env) -> u64 {
+        env.storage()
+            .persistent()
+            .get::<PoolDataKey, u64>(&PoolDataKey::CheckpointCount)
+            .unwrap_or(0)
+    }
+
+    // Get a checkpoint by id
+    pub fn get_checkpoint(env: Env, checkpoint_id: u64) -> Result<Checkpoint, Error> {
+        env.storage()
+            .persistent()
+            .get::<PoolDataKey, Checkpoint>(&PoolDataKey::Checkpoint(checkpoint_id))
+            .ok_or(Error::CheckpointNotFound)
+    }
+
+    // Get the latest checkpoint taken at or before a timestamp
+    pub fn get_checkpoint_at(env: Env, timestamp: u64) -> Result<Checkpoint, Error> {
+        // Checkpoint timestamps only increase, so search the ids
+        let mut low = 1;
+        let mut high = Self::get_latest_checkpoint_id(env.clone());
+        let mut found = None;
+        while low <= high {
+            let middle = low + (high - low) / 2;
+            let checkpoint = Self::get_checkpoint(env.clone(), middle)?;
+            if checkpoint.timestamp <= timestamp {
+                found = Some(checkpoint);
+                low = middle + 1;
+            } else {
+                high = middle - 1;
+            }
+        }
+        found.ok_or(Error::CheckpointNotFound)
+    }
+
+    // Get a depositor's balance of an asset at a checkpoint
+    pub fn get_balance_at(
+        env: Env,
+        asset_address: Address,
+        depositor: Address,
+        checkpoint_id: u64,
+    ) -> Result<i128, Error> {
+        if checkpoint_id == 0 || checkpoint_id > Self::get_latest_checkpoint_id(env.clone()) {
+            return Err(Error::CheckpointNotFound);
+        }
+
+        // The first entry at or after the checkpoint holds the balance from that time
+        let history = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, Vec<BalanceSnapshot>>(&PoolDataKey::BalanceHistory(
+                asset_address.clone(),
+                depositor.clone(),
+            ))
+            .unwrap_or(Vec::new(&env));
+        for snapshot in history.iter() {
+            if snapshot.checkpoint_id >= checkpoint_id {
+                return Ok(snapshot.amount);
+            }
+        }
+
+        // Unchanged since the checkpoint
+        Ok(Self::get_depositor_balance_amount(env, asset_address, depositor).unwrap_or(0))
+    }
+
+    // Get a depositor's balance of an asset at the latest checkpoint taken at or before a timestamp
+    pub fn get_balance_at_time(
+        env: Env,
+        asset_address: Address,
+        depositor: Address,
+        timestamp: u64,
+    ) -> Result<i128, Error> {
+        let checkpoint = Self::get_checkpoint_at(env.clone(), timestamp)?;
+        Self::get_balance_at(env, asset_address, depositor, checkpoint.id)
+    }
+
+    // Update the pool allocation percentage
+    pub fn set_allocation_percentage(env: Env, allocation_percentage: u32) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        // Verify allocation percentage is valid (0-10000 basis points, representing 0-100%)
+        if allocation_percentage as i128 > BASIS_POINTS_DENOMINATOR {
+            return Err(Error::InvalidAllocation);
+        }
+
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::AllocationPercentage, &allocation_percentage);
+
+        Ok(())
+    }
+
+    // Record the factory managing a pool initialized before pools kept one; once set it is fixed
+    pub fn set_manager(env: Env, manager: Address) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        if env.storage().persistent().has(&PoolDataKey::Manager) {
+            return Err(Error::Unauthorized);
+        }
+
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::Manager, &manager);
+
+        // Emit event for the manager
+        env.events()
+            .publish((Symbol::new(&env, "set_manager"),), manager);
+
+        Ok(())
+    }
+
+    // Get the factory contract managing this pool
+    pub fn get_manager(env: Env) -> Result<Address, Error> {
+        env.storage()
+            .persistent()
+            .get::<PoolDataKey, Address>(&PoolDataKey::Manager)
+            .ok_or(Error::Unauthorized)
+    }
+
+    // Move liquidity to another pool as part of a manager rebalance
+    pub fn rebalance_transfer(
+        env: Env,
+        asset_address: Address,
+        amount: i128,
+        destination: Address,
+    ) -> Result<(), Error> {
+        // Only the managing factory may rebalance
+        let manager = Self::get_manager(env.clone())?;
+        manager.require_auth();
+
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        // Check current token balance using token client
+        let token_client = token::Client::new(&env, &asset_address);
+        let current_balance = token_client.balance(&env.current_contract_address());
+
+        // Check if there's enough balance
+        if current_balance < amount {
+            return Err(Error::InsufficientBalance);
+        }
+
+        // Get thresholds for this asset
+        let threshold_key = PoolDataKey::Thresholds(asset_address.clone());
+        let thresholds = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, Thresholds>(&threshold_key)
+            .unwrap_or(Thresholds {
+                min_threshold: 0,
+                alert_threshold: 0,
+            });
+
+        // Check if transfer would go below minimum threshold
+        if (current_balance - amount) < thresholds.min_threshold {
+            return Err(Error::BelowMinThreshold);
+        }
+
+        // Depositor claims stay here, so only tokens above what the pool owes may leave
+        if amount > Self::get_surplus(env.clone(), asset_address.clone()) {
+            return Err(Error::InsufficientSurplus);
+        }
+
+        // The destination pool takes over tracking of the transferred amount
+        Self::update_totals(&env, &asset_address, |totals| {
+            totals.tracked_balance -= amount;
+        });
+
+        // Transfer tokens to the destination pool
+        token_client.transfer(&env.current_contract_address(), &destination, &amount);
+
+        // Emit rebalance event
+        env.events().publish(
+            (Symbol::new(&env, "rebalance_out"), asset_address),
+            (destination, amount),
+        );
+
+        Ok(())
+    }
+
+    // Get the part of an asset's balance above deposits and the reward reserve; nothing
+    // counts as surplus until every depositor from before the totals is indexed
+    pub fn get_surplus(env: Env, asset_address: Address) -> i128 {
+        if !Self::is_depositor_index_complete(env.clone(), asset_address.clone()) {
+            return 0;
+        }
+
+        let token_client = token::Client::new(&env, &asset_address);
+        let balance = token_client.balance(&env.current_contract_address());
+        (balance - Self::liabilities(&env, &asset_address)).max(0)
+    }
+
+    // Add a new SEP-41 token to the pool's assets
+    pub fn add_asset(env: Env, asset_address: Address) -> Result<Asset, Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        let mut assets = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, Vec<Asset>>(&PoolDataKey::Assets)
+            .unwrap_or_else(|| Vec::new(&env));
+
+        if assets.iter().any(|asset| asset.token == asset_address) {
+            return Err(Error::AssetAlreadyExists);
+        }
+
+        // Create token client to verify the token exists and to get token details
+        let token_client = token::Client::new(&env, &asset_address);
+        let asset = Asset {
+            token: asset_address.clone(),
+            symbol: token_client.symbol(),
+            decimals: token_client.decimals(),
+        };
+
+        assets.push_back(asset.clone());
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::Assets, &assets);
+        env.storage().persistent().set(
+            &PoolDataKey::DepositorsIndexed(asset_address.clone()),
+            &true,
+        );
+
+        // Emit event for the new asset
+        env.events().publish(
+            (Symbol::new(&env, "asset_added"), asset_address),
+            asset.clone(),
+        );
+
+        Ok(asset)
+    }
+
+    // Delist an asset so that it only accepts withdrawals
+    pub fn delist_asset(env: Env, asset_address: Address) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::Delisted(asset_address.clone()), &true);
+
+        // Emit event for the delisting
+        env.events()
+            .publish((Symbol::new(&env, "asset_delisted"), asset_address), ());
+
+        Ok(())
+    }
+
+    // Remove a delisted asset once nothing is owed in it, sending leftover dust to the fee collector
+    pub fn remove_asset(env: Env, asset_address: Address) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        // Only delisted assets can be removed, so depositors get a chance to withdraw
+        if !Self::is_asset_delisted(env.clone(), asset_address.clone()) {
+            return Err(Error::InvalidAsset);
+        }
+
+        // Deposits and the reward reserve must be paid out first; dust does not block removal
+        if !Self::is_depositor_index_complete(env.clone(), asset_address.clone()) {
+            return Err(Error::DepositorIndexIncomplete);
+        }
+        let totals = Self::get_pool_totals(env.clone(), asset_address.clone());
+        if totals.total_deposits != 0
+            || totals.reward_reserve != 0
+            || Self::get_depositor_count(env.clone(), asset_address.clone()) > 0
+        {
+            return Err(Error::AssetNotEmpty);
+        }
+
+        // Send whatever is left to the fee collector so nothing is stranded
+        let token_client = token::Client::new(&env, &asset_address);
+        let dust = token_client.balance(&env.current_contract_address());
+        if dust > 0 {
+            let fee_collector = env
+                .storage()
+                .persistent()
+                .get::<PoolDataKey, Address>(&PoolDataKey::FeeCollector)
+                .ok_or(Error::Unauthorized)?;
+            token_client.transfer(&env.current_contract_address(), &fee_collector, &dust);
+        }
+
+        // Drop consumer allowances so they do not revive if the asset is added again
+        for consumer in Self::get_consumers(env.clone(), asset_address.clone()).iter() {
+            env.storage()
+                .persistent()
+                .remove(&PoolDataKey::ConsumerAllowance(
+                    consumer,
+                    asset_address.clone(),
+                ));
+        }
+        env.storage()
+            .persistent()
+            .remove(&PoolDataKey::Consumers(asset_address.clone()));
+
+        // Drop queued withdrawals and policy changes so they cannot execute against a re-added asset
+        for id in Self::withdrawal_queue(&env).iter() {
+            let withdrawal = env
+                .storage()
+                .persistent()
+                .get::<PoolDataKey, QueuedWithdrawal>(&PoolDataKey::QueuedWithdrawal(id));
+            if withdrawal.is_some_and(|withdrawal| withdrawal.asset == asset_address) {
+                Self::dequeue_withdrawal(&env, id);
+            }
+        }
+        for id in Self::policy_change_queue(&env).iter() {
+            let queued = env
+                .storage()
+                .persistent()
+                .get::<PoolDataKey, QueuedPolicyChange>(&PoolDataKey::QueuedPolicyChange(id));
+            if queued.is_some_and(|queued| queued.asset == asset_address) {
+                Self::dequeue_policy_change(&env, id);
+            }
+        }
+
+        // Reset the asset's policy, rate, interest index and running totals; balance history
+        // stays, since it records what depositors held at checkpoints taken before the removal
+        env.storage()
+            .persistent()
+            .remove(&PoolDataKey::WithdrawalPolicy(asset_address.clone()));
+        env.storage()
+            .persistent()
+            .remove(&PoolDataKey::YieldRate(asset_address.clone()));
+        env.storage()
+            .persistent()
+            .remove(&PoolDataKey::InterestIndex(asset_address.clone()));
+        env.storage()
+            .persistent()
+            .remove(&PoolDataKey::FeeBalance(asset_address.clone()));
+        env.storage()
+            .persistent()
+            .remove(&PoolDataKey::Totals(asset_address.clone()));
+
+        let assets = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, Vec<Asset>>(&PoolDataKey::Assets)
+            .ok_or(Error::AssetNotFound)?;
+
+        let mut remaining_assets = Vec::new(&env);
+        for asset in assets.iter() {
+            if asset.token != asset_address {
+                remaining_assets.push_back(asset);
+            }
+        }
+
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::Assets, &remaining_assets);
+        env.storage()
+            .persistent()
+            .remove(&PoolDataKey::Delisted(asset_address.clone()));
+        env.storage()
+            .persistent()
+            .remove(&PoolDataKey::Thresholds(asset_address.clone()));
+        env.storage()
+            .persistent()
+            .remove(&PoolDataKey::DepositorsIndexed(asset_address.clone()));
+
+        // Emit event for the removal
+        env.events()
+            .publish((Symbol::new(&env, "asset_removed"), asset_address), dust);
+
+        Ok(())
+    }
+
+    // Check whether an asset has been delisted
+    pub fn is_asset_delisted(env: Env, asset_address: Address) -> bool {
+        env.storage()
+            .persistent()
+            .get::<PoolDataKey, bool>(&PoolDataKey::Delisted(asset_address))
+            .unwrap_or(false)
+    }
+
+    // Lower a consumer's drawdown cap or lengthen its period; new consumers and higher
+    // allowances have to go through queue_consumer_allowance
+    pub fn set_consumer_allowance(
+        env: Env,
+        consumer: Address,
+        asset_address: Address,
+        spending_cap: i128,
+        period_length: u64,
+    ) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        // Verify cap and period are valid
+        if spending_cap <= 0 || period_length == 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        // Only changes that let the consumer draw no more than before take effect at once
+        let existing =
+            Self::get_consumer_allowance(env.clone(), consumer.clone(), asset_address.clone())
+                .map_err(|_| Error::PolicyChangeTimelocked)?;
+        if spending_cap > existing.spending_cap || period_length < existing.period_length {
+            return Err(Error::PolicyChangeTimelocked);
+        }
+
+        Self::apply_consumer_allowance(
+            &env,
+            &consumer,
+            &asset_address,
+            spending_cap,
+            period_length,
+        );
+
+        Ok(())
+    }
+
+    // Queue a consumer allowance for an asset behind the asset's withdrawal timelock
+    pub fn queue_consumer_allowance(
+        env: Env,
+        consumer: Address,
+        asset_address: Address,
+        spending_cap: i128,
+        period_length: u64,
+    ) -> Result<u64, Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        // Verify cap and period are valid
+        if spending_cap <= 0 || period_length == 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        Ok(Self::queue_policy_change(
+            &env,
+            &asset_address,
+            PolicyChange::ConsumerAllowance(consumer, spending_cap, period_length),
+        ))
+    }
+
+    // Remove a consumer contract from the drawdown allowlist
+    pub fn remove_consumer(
+        env: Env,
+        consumer: Address,
+        asset_address: Address,
+    ) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        let allowance_key = PoolDataKey::ConsumerAllowance(consumer.clone(), asset_address.clone());
+        if !env.storage().persistent().has(&allowance_key) {
+            return Err(Error::ConsumerNotAllowed);
+        }
+        env.storage().persistent().remove(&allowance_key);
+
+        let mut consumers = Self::get_consumers(env.clone(), asset_address.clone());
+        if let Some(index) = consumers.first_index_of(&consumer) {
+            consumers.remove(index);
+            env.storage()
+                .persistent()
+                .set(&PoolDataKey::Consumers(asset_address.clone()), &consumers);
+        }
+
+        // Emit event for allowance removal
+        env.events().publish(
+            (
+                Symbol::new(&env, "consumer_removed"),
+                consumer,
+                asset_address,
+            ),
+            (),
+        );
+
+        Ok(())
+    }
+
+    // Get a consumer's drawdown allowance for an asset
+    pub fn get_consumer_allowance(
+        env: Env,
+        consumer: Address,
+        asset_address: Address,
+    ) -> Result<ConsumerAllowance, Error> {
+        env.storage()
+            .persistent()
+            .get::<PoolDataKey, ConsumerAllowance>(&PoolDataKey::ConsumerAllowance(
+                consumer,
+                asset_address,
+            ))
+            .ok_or(Error::ConsumerNotAllowed)
+    }
+
+    // Get the consumers holding a drawdown allowance for an asset
+    pub fn get_consumers(env: Env, asset_address: Address) -> Vec<Address> {
+        env.storage()
+            .persistent()
+            .get::<PoolDataKey, Vec<Address>>(&PoolDataKey::Consumers(asset_address))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    // Draw liquidity directly to a payee on behalf of an allowlisted consumer contract
+    pub fn draw_for_payment(
+        env: Env,
+        consumer: Address,
+        asset_address: Address,
+        amount: i128,
+        payee: Address,
+        payment_reference: String,
+    ) -> Result<(), Error> {
+        // Require consumer authorization
+        consumer.require_auth();
+
+        // Paused pools make no payments
+        Self::verify_not_paused(&env)?;
+
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Verify asset exists in the pool
+        Self::verify_asset(&env, &asset_address)?;
+
+        // Get the consumer's allowance
+        let allowance_key = PoolDataKey::ConsumerAllowance(consumer.clone(), asset_address.clone());
+        let mut allowance = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, ConsumerAllowance>(&allowance_key)
+            .ok_or(Error::ConsumerNotAllowed)?;
+
+        // Start a new period once the current one has elapsed; a period that would
+        // end past the largest timestamp never does
+        let now = env.ledger().timestamp();
+        let period_end = allowance.period_start.checked_add(allowance.period_length);
+        if period_end.is_some_and(|period_end| now >= period_end) {
+            allowance.period_start = now;
+            allowance.spent_in_period = 0;
+        }
+
+        // Check the draw fits within the spending cap for this period. The cap limits
+        // what is drawn, so deposits made during the period don't add to it.
+        let spent_in_period = allowance
+            .spent_in_period
+            .checked_add(amount)
+            .ok_or(Error::SpendingCapExceeded)?;
+        if spent_in_period > allowance.spending_cap {
+            return Err(Error::SpendingCapExceeded);
+        }
+
+        // Check current token balance using token client
+        let token_client = token::Client::new(&env, &asset_address);
+        let current_balance = token_client.balance(&env.current_contract_address());
+
+        // The reward reserve is owed to depositors as interest, so it is not available
+        let reward_reserve =
+            Self::get_pool_totals(env.clone(), asset_address.clone()).reward_reserve;
+        let available_balance = current_balance - reward_reserve;
+
+        // Check if there's enough balance
+        if available_balance < amount {
+            return Err(Error::InsufficientBalance);
+        }
+
+        // Get thresholds for this asset
+        let threshold_key = PoolDataKey::Thresholds(asset_address.clone());
+        let thresholds = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, Thresholds>(&threshold_key)
+            .unwrap_or(Thresholds {
+                min_threshold: 0,
+                alert_threshold: 0,
+            });
+
+        // Check if draw would go below minimum threshold
+        if (available_balance - amount) < thresholds.min_threshold {
+            return Err(Error::BelowMinThreshold);
+        }
+
+        // Record the draw before transferring
+        allowance.spent_in_period = spent_in_period;
+        env.storage().persistent().set(&allowance_key, &allowance);
+
+        // Track the draw in the pool totals
+        Self::update_totals(&env, &asset_address, |totals| {
+            totals.drawn_for_payments += amount;
+            totals.tracked_balance -= amount;
+        });
+
+        // Transfer tokens straight to the payee
+        token_client.transfer(&env.current_contract_address(), &payee, &amount);
+
+        // Emit drawdown event tied to the consumer's payment reference
+        env.events().publish(
+            (Symbol::new(&env, "draw"), consumer, asset_address),
+            (payment_reference, payee, amount),
+        );
+
+        Ok(())
+    }
+
+    // Grant a role to an account, replacing any previous holder
+    pub fn grant_role(env: Env, role: Role, account: Address) -> Result<(), Error> {
+        // Verify caller is admin
+        let admin = Self::verify_admin(&env)?;
+
+        // The threshold-setter and the withdrawer must be different accounts
+        let mut risk_manager = Self::get_role(env.clone(), Role::RiskManager);
+        let mut treasurer = Self::get_role(env.clone(), Role::Treasurer);
+        match role {
+            Role::RiskManager => risk_manager = Some(account.clone()),
+            Role::Treasurer => treasurer = Some(account.clone()),
+            Role::FeeManager | Role::Guardian => {}
+        }
+        Self::verify_duties_separated(&admin, &risk_manager, &treasurer)?;
+
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::Role(role.clone()), &account);
+
+        // Emit event for the grant
+        env.events()
+            .publish((Symbol::new(&env, "role_granted"), role), account);
+
+        Ok(())
+    }
+
+    // Revoke a role; risk and fee duties fall back to the admin
+    pub fn revoke_role(env: Env, role: Role) -> Result<(), Error> {
+        // Verify caller is admin
+        let admin = Self::verify_admin(&env)?;
+
+        // Handing risk duties back to the admin must not make it the withdrawer too
+        let account = Self::get_role(env.clone(), role.clone()).ok_or(Error::Unauthorized)?;
+        if role == Role::RiskManager {
+            let treasurer = Self::get_role(env.clone(), Role::Treasurer);
+            Self::verify_duties_separated(&admin, &None, &treasurer)?;
+        }
+
+        env.storage()
+            .persistent()
+            .remove(&PoolDataKey::Role(role.clone()));
+
+        // Emit event for the revocation
+        env.events()
+            .publish((Symbol::new(&env, "role_revoked"), role), account);
+
+        Ok(())
+    }
+
+    // Get the account explicitly granted a role
+    pub fn get_role(env: Env, role: Role) -> Option<Address> {
+        env.storage().persistent().get(&PoolDataKey::Role(role))
+    }
+
+    // Get the current admin
+    pub fn get_admin(env: Env) -> Result<Address, Error> {
+        env.storage()
+            .persistent()
+            .get::<PoolDataKey, Address>(&PoolDataKey::Admin)
+            .ok_or(Error::Unauthorized)
+    }
+
+    // Propose a new admin, who takes over once they accept
+    pub fn propose_admin(env: Env, new_admin: Address) -> Result<(), Error> {
+        // Verify caller is admin
+        Self::verify_admin(&env)?;
+
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::PendingAdmin, &new_admin);
+
+        // Emit event for the proposal
+        env.events()
+            .publish((Symbol::new(&env, "admin_proposed"),), new_admin);
+
+        Ok(())
+    }
+
+    // Accept a pending admin handover
+    pub fn accept_admin(env: Env) -> Result<(), Error> {
+        let new_admin = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, Address>(&PoolDataKey::PendingAdmin)
+            .ok_or(Error::NoPendingAdmin)?;
+        new_admin.require_auth();
+
+        // The new admin inherits unassigned risk duties, so it must not be the treasurer
+        let risk_manager = Self::get_role(env.clone(), Role::RiskManager);
+        let treasurer = Self::get_role(env.clone(), Role::Treasurer);
+        Self::verify_duties_separated(&new_admin, &risk_manager, &treasurer)?;
+
+        let old_admin = Self::get_admin(env.clone())?;
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::Admin, &new_admin);
+        env.storage()
+            .persistent()
+            .remove(&PoolDataKey::PendingAdmin);
+
+        // Emit event for the handover
+        env.events().publish(
+            (Symbol::new(&env, "admin_accepted"),),
+            (old_admin, new_admin),
+        );
+
+        Ok(())
+    }
+
+    // Change the account that receives withdrawal fees
+    pub fn set_fee_collector(env: Env, fee_collector: Address) -> Result<(), Error> {
+        // Verify caller is the fee manager
+        Self::verify_role(&env, Role::FeeManager)?;
+
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::FeeCollector, &fee_collector);
+
+        // Emit event for the fee collector update
+        env.events()
+            .publish((Symbol::new(&env, "set_fee_collector"),), fee_collector);
+
+        Ok(())
+    }
+
+    // Admin verification function
+    fn verify_admin(env: &Env) -> Result<Address, Error> {
+        let admin: Address = env
+            .storage()
+            .persistent()
+            .get(&PoolDataKey::Admin)
+            .ok_or(Error::Unauthorized)?;
+        admin.require_auth();
+        Ok(admin)
+    }
+
+    // Role verification function; risk and fee duties default to the admin, withdrawals do not
+    fn verify_role(env: &Env, role: Role) -> Result<Address, Error> {
+        let holder = match Self::get_role(env.clone(), role.clone()) {
+            Some(holder) => holder,
+            None if role == Role::Treasurer => return Err(Error::Unauthorized),
+            None => Self::get_admin(env.clone())?,
+        };
+        holder.require_auth();
+        Ok(holder)
+    }
+
+    // Check that the effective risk manager is not also the treasurer
+    fn verify_duties_separated(
+        admin: &Address,
+        risk_manager: &Option<Address>,
+        treasurer: &Option<Address>,
+    ) -> Result<(), Error> {
+        let risk_manager = risk_manager.as_ref().unwrap_or(admin);
+        if treasurer.as_ref() == Some(risk_manager) {
+            return Err(Error::RoleConflict);
+        }
+        Ok(())
+    }
+
+    // Asset verification function
+    fn verify_asset(env: &Env, asset_address: &Address) -> Result<(), Error> {
+        let assets = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, Vec<Asset>>(&PoolDataKey::Assets)
+            .ok_or(Error::AssetNotFound)?;
+
+        if !assets.iter().any(|asset| asset.token == *asset_address) {
+            return Err(Error::AssetNotFound);
+        }
+        Ok(())
+    }
+
+    // Pay out an admin withdrawal within the asset's outflow cap and minimum threshold
+    fn pay_admin_withdrawal(
+        env: &Env,
+        asset_address: &Address,
+        amount: i128,
+        recipient: &Address,
+    ) -> Result<(), Error> {
+        if amount <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // A queued withdrawal may outlive its asset being delisted or removed
+        Self::verify_asset(env, asset_address)?;
+        if Self::is_asset_delisted(env.clone(), asset_address.clone()) {
+            return Err(Error::InvalidAsset);
+        }
+
+        // Start a new outflow period once the current one has elapsed, then check the cap; a
+        // period that would end past the largest timestamp never does
+        let mut policy = Self::get_withdrawal_policy(env.clone(), asset_address.clone());
+        let now = env.ledger().timestamp();
+        let period_end = policy.period_start.checked_add(policy.period_length);
+        if period_end.is_some_and(|period_end| now >= period_end) {
+            policy.period_start = now;
+            policy.withdrawn_in_period = 0;
+        }
+        let withdrawn_in_period = policy
+            .withdrawn_in_period
+            .checked_add(amount)
+            .ok_or(Error::OutflowCapExceeded)?;
+        if withdrawn_in_period > policy.period_cap {
+            return Err(Error::OutflowCapExceeded);
+        }
+        policy.withdrawn_in_period = withdrawn_in_period;
+        env.storage().persistent().set(
+            &PoolDataKey::WithdrawalPolicy(asset_address.clone()),
+            &policy,
+        );
+
+        // Check current token balance using token client
+        let token_client = token::Client::new(env, asset_address);
+        let current_balance = token_client.balance(&env.current_contract_address());
+
+        // The reward reserve is owed to depositors as interest, so it is not available
+        let reward_reserve =
+            Self::get_pool_totals(env.clone(), asset_address.clone()).reward_reserve;
+        let available_balance = current_balance - reward_reserve;
+
+        // Check if there's enough balance
+        if available_balance < amount {
+            return Err(Error::InsufficientBalance);
+        }
+
+        // Get thresholds for this asset
+        let threshold_key = PoolDataKey::Thresholds(asset_address.clone());
+        let thresholds = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, Thresholds>(&threshold_key)
+            .unwrap_or_else(|| Thresholds {
+                min_threshold: 0,
+                alert_threshold: 0,
+            });
+
+        // Check if withdrawal would go below minimum threshold
+        if (available_balance - amount) < thresholds.min_threshold {
+            return Err(Error::BelowMinThreshold);
+        }
+
+        // Calculate fee using global fee constant
+        let fee = amount * WITHDRAWAL_FEE_BASIS_POINTS / BASIS_POINTS_DENOMINATOR;
+        let withdrawal_amount = amount - fee;
+
+        // Get fee collector address
+        let fee_collector = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, Address>(&PoolDataKey::FeeCollector)
+            .ok_or(Error::Unauthorized)?;
+
+        // Track the withdrawal and its fee in the pool totals
+        Self::update_totals(env, asset_address, |totals| {
+            totals.admin_withdrawals += amount;
+            totals.total_fees += fee;
+            totals.tracked_balance -= amount;
+        });
+
+        // Transfer the withdrawal amount to the recipient
+        token_client.transfer(
+            &env.current_contract_address(),
+            recipient,
+            &withdrawal_amount,
+        );
+
+        // Transfer the fee to fee collector
+        if fee > 0 {
+            token_client.transfer(&env.current_contract_address(), &fee_collector, &fee);
+
+            // Update fee balance record (for tracking purposes)
+            let fee_balance_key = PoolDataKey::FeeBalance(asset_address.clone());
+            let current_fee_balance = env
+                .storage()
+                .persistent()
+                .get::<PoolDataKey, i128>(&fee_balance_key)
+                .unwrap_or(0);
+
+            // Update fee balance
+            let new_fee_balance = current_fee_balance + fee;
+            env.storage()
+                .persistent()
+                .set(&fee_balance_key, &new_fee_balance);
+        }
+
+        Ok(())
+    }
+
+    // Store an asset's withdrawal policy, keeping the amount already withdrawn in the current period
+    fn apply_withdrawal_policy(
+        env: &Env,
+        asset_address: &Address,
+        timelock_threshold: i128,
+        timelock: u64,
+        period_cap: i128,
+        period_length: u64,
+    ) {
+        let policy = WithdrawalPolicy {
+            timelock_threshold,
+            timelock,
+            period_cap,
+            period_length,
+            ..Self::get_withdrawal_policy(env.clone(), asset_address.clone())
+        };
+        env.storage().persistent().set(
+            &PoolDataKey::WithdrawalPolicy(asset_address.clone()),
+            &policy,
+        );
+
+        // Emit event for policy update
+        env.events().publish(
+            (Symbol::new(env, "withdrawal_policy"), asset_address.clone()),
+            (timelock_threshold, timelock, period_cap, period_length),
+        );
+    }
+
+    // Store a consumer's allowance, keeping the amount already drawn in the current period
+    fn apply_consumer_allowance(
+        env: &Env,
+        consumer: &Address,
+        asset_address: &Address,
+        spending_cap: i128,
+        period_length: u64,
+    ) {
+        let allowance_key = PoolDataKey::ConsumerAllowance(consumer.clone(), asset_address.clone());
+        let allowance = match env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, ConsumerAllowance>(&allowance_key)
+        {
+            Some(existing) => ConsumerAllowance {
+                spending_cap,
+                period_length,
+                ..existing
+            },
+            None => {
+                let mut consumers = Self::get_consumers(env.clone(), asset_address.clone());
+                consumers.push_back(consumer.clone());
+                env.storage()
+                    .persistent()
+                    .set(&PoolDataKey::Consumers(asset_address.clone()), &consumers);
+
+                ConsumerAllowance {
+                    spending_cap,
+                    period_length,
+                    period_start: env.ledger().timestamp(),
+                    spent_in_period: 0,
+                }
+            }
+        };
+        env.storage().persistent().set(&allowance_key, &allowance);
+
+        // Emit event for allowance update
+        env.events().publish(
+            (
+                Symbol::new(env, "consumer_set"),
+                consumer.clone(),
+                asset_address.clone(),
+            ),
+            (spending_cap, period_length),
+        );
+    }
+
+    // Queue a policy change behind the asset's withdrawal timelock
+    fn queue_policy_change(env: &Env, asset_address: &Address, change: PolicyChange) -> u64 {
+        let timelock = Self::get_withdrawal_policy(env.clone(), asset_address.clone()).timelock;
+
+        // Take the next change id
+        let id = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, u64>(&PoolDataKey::NextPolicyChangeId)
+            .unwrap_or(0);
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::NextPolicyChangeId, &(id + 1));
+
+        // Store the change and add it to the public queue
+        let queued = QueuedPolicyChange {
+            id,
+            asset: asset_address.clone(),
+            change,
+            execute_after: env.ledger().timestamp() + timelock,
+        };
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::QueuedPolicyChange(id), &queued);
+
+        let mut queue = Self::policy_change_queue(env);
+        queue.push_back(id);
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::PolicyChangeQueue, &queue);
+
+        // Emit event so depositors can see the pending change
+        env.events().publish(
+            (Symbol::new(env, "policy_change_queued"), id),
+            (queued.asset, queued.change, queued.execute_after),
+        );
+
+        id
+    }
+
+    // Ids of queued policy changes
+    fn policy_change_queue(env: &Env) -> Vec<u64> {
+        env.storage()
+            .persistent()
+            .get::<PoolDataKey, Vec<u64>>(&PoolDataKey::PolicyChangeQueue)
+            .unwrap_or(Vec::new(env))
+    }
+
+    // Remove a policy change from the queue
+    fn dequeue_policy_change(env: &Env, id: u64) {
+        env.storage()
+            .persistent()
+            .remove(&PoolDataKey::QueuedPolicyChange(id));
+
+        let mut queue = Self::policy_change_queue(env);
+        if let Some(index) = queue.first_index_of(id) {
+            queue.remove(index);
+            env.storage()
+                .persistent()
+                .set(&PoolDataKey::PolicyChangeQueue, &queue);
+        }
+    }
+
+    // Current value of an asset's interest index; assets whose rate was never set have none
+    fn interest_index(env: &Env, asset_address: &Address) -> i128 {
+        let rate = Self::get_yield_rate(env.clone(), asset_address.clone()) as i128;
+        match env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, InterestIndex>(&PoolDataKey::InterestIndex(asset_address.clone()))
+        {
+            Some(index) => {
+                index.value + rate * (env.ledger().timestamp() - index.timestamp) as i128
+            }
+            None => 0,
+        }
+    }
+
+    // Interest accrued on a balance since the depositor last settled
+    fn pending_interest(
+        env: &Env,
+        asset_address: &Address,
+        depositor: &Address,
+        balance: &DepositorBalance,
+    ) -> i128 {
+        let snapshot = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, i128>(&PoolDataKey::InterestSnapshot(
+                asset_address.clone(),
+                depositor.clone(),
+            ))
+            .unwrap_or(0);
+        let growth = Self::interest_index(env, asset_address) - snapshot;
+        balance.amount * growth / (BASIS_POINTS_DENOMINATOR * SECONDS_PER_YEAR)
+    }
+
+    // Move a depositor's pending interest into their accrued interest and restart accrual
+    fn settle_interest(env: &Env, asset_address: &Address, depositor: &Address) {
+        if let Some(balance) = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, DepositorBalance>(&PoolDataKey::DepositorBalance(
+                asset_address.clone(),
+                depositor.clone(),
+            ))
+        {
+            let pending = Self::pending_interest(env, asset_address, depositor, &balance);
+            Self::add_accrued_interest(env, asset_address, depositor, pending);
+        }
+
+        // Later interest accrues from the index as it stands now
+        env.storage().persistent().set(
+            &PoolDataKey::InterestSnapshot(asset_address.clone(), depositor.clone()),
+            &Self::interest_index(env, asset_address),
+        );
+    }
+
+    // Add to a depositor's settled but unpaid interest
+    fn add_accrued_interest(env: &Env, asset_address: &Address, depositor: &Address, amount: i128) {
+        if amount <= 0 {
+            return;
+        }
+
+        let accrued_key = PoolDataKey::AccruedInterest(asset_address.clone(), depositor.clone());
+        let accrued = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, i128>(&accrued_key)
+            .unwrap_or(0);
+        env.storage()
+            .persistent()
+            .set(&accrued_key, &(accrued + amount));
+    }
+
+    // Settle and clear a depositor's accrued interest, drawing it from the reward reserve
+    fn take_interest(
+        env: &Env,
+        asset_address: &Address,
+        depositor: &Address,
+    ) -> Result<i128, Error> {
+        Self::settle_interest(env, asset_address, depositor);
+
+        let accrued_key = PoolDataKey::AccruedInterest(asset_address.clone(), depositor.clone());
+        let interest = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, i128>(&accrued_key)
+            .unwrap_or(0);
+        if interest <= 0 {
+            return Err(Error::InvalidAmount);
+        }
+
+        // Interest is only paid from funds the admin set aside
+        let totals = Self::get_pool_totals(env.clone(), asset_address.clone());
+        if totals.reward_reserve < interest {
+            return Err(Error::RewardReserveExhausted);
+        }
+
+        env.storage().persistent().remove(&accrued_key);
+        Self::update_totals(env, asset_address, |totals| {
+            totals.reward_reserve -= interest;
+            totals.interest_paid += interest;
+        });
+
+        Ok(interest)
+    }
+
+    // Keep a depositor's balance as of the latest checkpoint before its first change since then
+    fn record_balance_history(env: &Env, asset_address: &Address, depositor: &Address) {
+        let checkpoint_id = Self::get_latest_checkpoint_id(env.clone());
+        if checkpoint_id == 0 {
+            return;
+        }
+
+        let history_key = PoolDataKey::BalanceHistory(asset_address.clone(), depositor.clone());
+        let mut history = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, Vec<BalanceSnapshot>>(&history_key)
+            .unwrap_or(Vec::new(env));
+        if let Some(last) = history.last() {
+            if last.checkpoint_id == checkpoint_id {
+                return;
+            }
+        }
+
+        let amount = Self::get_depositor_balance_amount(
+            env.clone(),
+            asset_address.clone(),
+            depositor.clone(),
+        )
+        .unwrap_or(0);
+        history.push_back(BalanceSnapshot {
+            checkpoint_id,
+            amount,
+        });
+        env.storage().persistent().set(&history_key, &history);
+    }
+
+    // Tokens of an asset owed to depositors and set aside for their interest
+    fn liabilities(env: &Env, asset_address: &Address) -> i128 {
+        let totals = Self::get_pool_totals(env.clone(), asset_address.clone());
+        totals.total_deposits + totals.reward_reserve
+    }
+
+    // Apply a change to an asset's tracked totals
+    fn update_totals(env: &Env, asset_address: &Address, update: impl FnOnce(&mut PoolTotals)) {
+        let mut totals = Self::get_pool_totals(env.clone(), asset_address.clone());
+        update(&mut totals);
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::Totals(asset_address.clone()), &totals);
+    }
+
+    // Ids of queued admin withdrawals
+    fn withdrawal_queue(env: &Env) -> Vec<u64> {
+        env.storage()
+            .persistent()
+            .get::<PoolDataKey, Vec<u64>>(&PoolDataKey::WithdrawalQueue)
+            .unwrap_or(Vec::new(env))
+    }
+
+    // Remove a withdrawal from the queue
+    fn dequeue_withdrawal(env: &Env, id: u64) {
+        env.storage()
+            .persistent()
+            .remove(&PoolDataKey::QueuedWithdrawal(id));
+
+        let mut queue = Self::withdrawal_queue(env);
+        if let Some(index) = queue.first_index_of(id) {
+            queue.remove(index);
+            env.storage()
+                .persistent()
+                .set(&PoolDataKey::WithdrawalQueue, &queue);
+        }
+    }
+
+    // Pause verification function
+    fn verify_not_paused(env: &Env) -> Result<(), Error> {
+        if Self::is_paused(env.clone()) {
+            return Err(Error::PoolPaused);
+        }
+        Ok(())
+    }
+
+    // Add a depositor to the asset's depositor index, returning whether it was missing
+    fn index_depositor(env: &Env, asset_address: &Address, depositor: &Address) -> bool {
+        let slot_key = PoolDataKey::DepositorSlot(asset_address.clone(), depositor.clone());
+        if env.storage().persistent().has(&slot_key) {
+            return false;
+        }
+
+        let count = Self::get_depositor_count(env.clone(), asset_address.clone());
+        env.storage().persistent().set(
+            &PoolDataKey::DepositorAt(asset_address.clone(), count),
+            depositor,
+        );
+        env.storage().persistent().set(&slot_key, &count);
+        env.storage().persistent().set(
+            &PoolDataKey::DepositorCount(asset_address.clone()),
+            &(count + 1),
+        );
+        true
+    }
+
+    // Index a depositor, counting a balance from before the index into the pool totals
+    fn track_depositor(env: &Env, asset_address: &Address, depositor: &Address) -> bool {
+        let complete = Self::is_depositor_index_complete(env.clone(), asset_address.clone());
+        if !Self::index_depositor(env, asset_address, depositor) {
+            return false;
+        }
+
+        if !complete {
+            let existing = Self::get_depositor_balance_amount(
+                env.clone(),
+                asset_address.clone(),
+                depositor.clone(),
+            )
+            .unwrap_or(0);
+            Self::update_totals(env, asset_address, |totals| {
+                totals.total_deposits += existing;
+                totals.tracked_balance += existing;
+            });
+        }
+        true
+    }
+
+    // Drop a depositor from the index once they hold neither a balance nor unpaid interest
+    fn release_depositor(env: &Env, asset_address: &Address, depositor: &Address) {
+        let balance_key = PoolDataKey::DepositorBalance(asset_address.clone(), depositor.clone());
+        let accrued_key = PoolDataKey::AccruedInterest(asset_address.clone(), depositor.clone());
+        if !env.storage().persistent().has(&balance_key)
+            && !env.storage().persistent().has(&accrued_key)
+        {
+            Self::unindex_depositor(env, asset_address, depositor);
+        }
+    }
+
+    // Remove a depositor from the asset's depositor index, moving the last entry into its place
+    fn unindex_depositor(env: &Env, asset_address: &Address, depositor: &Address) {
+        let slot_key = PoolDataKey::DepositorSlot(asset_address.clone(), depositor.clone());
+        let Some(slot) = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, u32>(&slot_key)
+        else {
+            return;
+        };
+
+        let last = Self::get_depositor_count(env.clone(), asset_address.clone()) - 1;
+        let last_key = PoolDataKey::DepositorAt(asset_address.clone(), last);
+        let moved = env
+            .storage()
+            .persistent()
+            .get::<PoolDataKey, Address>(&last_key);
+        if let Some(moved) = moved.filter(|moved| moved != depositor) {
+            env.storage().persistent().set(
+                &PoolDataKey::DepositorAt(asset_address.clone(), slot),
+                &moved,
+            );
+            env.storage().persistent().set(
+                &PoolDataKey::DepositorSlot(asset_address.clone(), moved),
+                &slot,
+            );
+        }
+        env.storage().persistent().remove(&last_key);
+        env.storage().persistent().remove(&slot_key);
+        env.storage()
+            .persistent()
+            .set(&PoolDataKey::DepositorCount(asset_address.clone()), &last);
+    }
 }
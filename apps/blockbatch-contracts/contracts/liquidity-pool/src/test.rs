@@ -1,6 +1,6 @@
 use super::*;
 use crate::testutils::{calculate_fee, check_balance, create_token_contract, mint_tokens};
-use crate::types::{AlertType, Asset};
+use crate::types::{AlertType, Asset, Role};
 use soroban_sdk::testutils::Address as _;
 use soroban_sdk::{
     testutils::Events,
@@ -11,6 +11,7 @@ use soroban_sdk::{
 #[cfg(test)]
 mod test_setup {
     use super::*;
+    use soroban_sdk::testutils::Ledger;
 
     pub fn setup_contract(e: &Env) -> (LiquidityPoolClient, Address, Address, Address, Address) {
         let admin = Address::generate(e);
@@ -43,15 +44,59 @@ mod test_setup {
 
         let pool_assets = Vec::from_array(e, [asset1, asset2]);
 
-        // Register the liquidity pool contract type
+        // Register the liquidity pool contract type under a managing factory
+        let manager = Address::generate(e);
         let contract_id = e.register(
             LiquidityPool,
-            (admin.clone(), fee_collector.clone(), pool_assets, 5000u32),
+            (
+                admin.clone(),
+                fee_collector.clone(),
+                pool_assets,
+                5000u32,
+                manager,
+            ),
         );
         let client = LiquidityPoolClient::new(e, &contract_id);
 
         (client, admin, fee_collector, token1_address, token2_address)
     }
+
+    // Loosen an asset's withdrawal policy by waiting out the policy timelock
+    pub fn loosen_withdrawal_policy(
+        e: &Env,
+        client: &LiquidityPoolClient,
+        asset: &Address,
+        timelock_threshold: i128,
+        timelock: u64,
+        period_cap: i128,
+        period_length: u64,
+    ) {
+        let id = client.queue_withdrawal_policy(
+            asset,
+            &timelock_threshold,
+            &timelock,
+            &period_cap,
+            &period_length,
+        );
+        let wait = client.get_withdrawal_policy(asset).timelock;
+        e.ledger().with_mut(|li| li.timestamp += wait);
+        client.execute_policy_change(&id);
+    }
+
+    // Allowlist a consumer by waiting out the policy timelock
+    pub fn allow_consumer(
+        e: &Env,
+        client: &LiquidityPoolClient,
+        consumer: &Address,
+        asset: &Address,
+        spending_cap: i128,
+        period_length: u64,
+    ) {
+        let id = client.queue_consumer_allowance(consumer, asset, &spending_cap, &period_length);
+        let wait = client.get_withdrawal_policy(asset).timelock;
+        e.ledger().with_mut(|li| li.timestamp += wait);
+        client.execute_policy_change(&id);
+    }
 }
 
 mod test_initialization {
@@ -73,7 +118,13 @@ mod test_initialization {
         let assets = Vec::from_array(&env, [asset]);
 
         // Try to initialize again (should fail with Unauthorized error)
-        contract.initialize(&admin, &fee_collector, &assets, &5000);
+        contract.initialize(
+            &admin,
+            &fee_collector,
+            &assets,
+            &5000,
+            &Address::generate(&env),
+        );
     }
 
     #[test]
@@ -101,6 +152,19 @@ mod test_initialization {
         let allocation = contract.get_allocation_percentage();
         assert_eq!(allocation, 5000);
     }
+
+    #[test]
+    fn test_set_allocation_percentage() {
+        let env = Env::default();
+        let (contract, _, _, _, _) = test_setup::setup_contract(&env);
+
+        contract.set_allocation_percentage(&2500);
+        assert_eq!(contract.get_allocation_percentage(), 2500);
+
+        // More than 100% is rejected
+        let result = contract.try_set_allocation_percentage(&10001);
+        assert!(result.is_err());
+    }
 }
 
 mod test_thresholds {
@@ -340,7 +404,11 @@ mod test_admin_functions {
     #[test]
     fn test_admin_withdraw_funds() {
         let env = Env::default();
-        let (contract, admin, _, token1_address, _) = test_setup::setup_contract(&env);
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        // Withdrawals are made by the treasurer
+        let treasurer = Address::generate(&env);
+        contract.grant_role(&Role::Treasurer, &treasurer);
 
         // Create a depositor
         let depositor = Address::generate(&env);
@@ -357,10 +425,21 @@ mod test_admin_functions {
         // Add liquidity
         contract.add_liquidity(&token1_address, &5000, &depositor);
 
-        // Get initial admin balance
-        let initial_admin_balance = check_balance(&env, &token1_address, &admin);
+        // Get initial treasurer balance
+        let initial_treasurer_balance = check_balance(&env, &token1_address, &treasurer);
+
+        // Open the outflow cap through the policy timelock
+        test_setup::loosen_withdrawal_policy(
+            &env,
+            &contract,
+            &token1_address,
+            5000,
+            86400,
+            5000,
+            86400,
+        );
 
-        // Admin withdraws some funds
+        // Treasurer withdraws some funds
         let withdraw_amount = 2000;
         contract.admin_withdraw_funds(&token1_address, &withdraw_amount);
 
@@ -368,9 +447,12 @@ mod test_admin_functions {
         let fee = calculate_fee(withdraw_amount);
         let net_withdrawal = withdraw_amount - fee;
 
-        // Check admin balance
-        let final_admin_balance = check_balance(&env, &token1_address, &admin);
-        assert_eq!(final_admin_balance, initial_admin_balance + net_withdrawal);
+        // Check treasurer balance
+        let final_treasurer_balance = check_balance(&env, &token1_address, &treasurer);
+        assert_eq!(
+            final_treasurer_balance,
+            initial_treasurer_balance + net_withdrawal
+        );
 
         // Check contract balance
         let contract_balance = check_balance(&env, &token1_address, &contract.address);
@@ -440,3 +522,1595 @@ mod test_alerts {
         assert!(!events.is_empty());
     }
 }
+
+mod test_drawdown {
+    use super::*;
+    use crate::types::Error;
+    use soroban_sdk::testutils::Ledger;
+
+    fn fund_pool(env: &Env, contract: &LiquidityPoolClient, token_address: &Address, amount: i128) {
+        let depositor = Address::generate(env);
+        let token_admin = TokenAdmin::new(env, token_address);
+        mint_tokens(&token_admin, &depositor, &amount);
+        contract.add_liquidity(token_address, &amount, &depositor);
+    }
+
+    #[test]
+    fn test_draw_for_payment() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        fund_pool(&env, &contract, &token1_address, 10000);
+
+        // Allowlist a payroll contract with a cap of 5000 per day
+        let consumer = Address::generate(&env);
+        test_setup::allow_consumer(&env, &contract, &consumer, &token1_address, 5000, 86400);
+
+        // Draw straight to the payee
+        let payee = Address::generate(&env);
+        contract.draw_for_payment(
+            &consumer,
+            &token1_address,
+            &3000,
+            &payee,
+            &String::from_str(&env, "PAY-001"),
+        );
+
+        // Payee receives the full amount, no withdrawal fee
+        assert_eq!(check_balance(&env, &token1_address, &payee), 3000);
+        assert_eq!(
+            check_balance(&env, &token1_address, &contract.address),
+            7000
+        );
+
+        // Draw is recorded against the period's cap
+        let allowance = contract.get_consumer_allowance(&consumer, &token1_address);
+        assert_eq!(allowance.spent_in_period, 3000);
+        assert_eq!(allowance.spending_cap, 5000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #13)")]
+    fn test_draw_for_payment_not_allowlisted() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        fund_pool(&env, &contract, &token1_address, 10000);
+
+        let consumer = Address::generate(&env);
+        let payee = Address::generate(&env);
+        contract.draw_for_payment(
+            &consumer,
+            &token1_address,
+            &1000,
+            &payee,
+            &String::from_str(&env, "PAY-001"),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #14)")]
+    fn test_draw_for_payment_cap_exceeded() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        fund_pool(&env, &contract, &token1_address, 10000);
+
+        let consumer = Address::generate(&env);
+        test_setup::allow_consumer(&env, &contract, &consumer, &token1_address, 5000, 86400);
+
+        let payee = Address::generate(&env);
+        contract.draw_for_payment(
+            &consumer,
+            &token1_address,
+            &3000,
+            &payee,
+            &String::from_str(&env, "PAY-001"),
+        );
+
+        // Second draw in the same period goes over the cap
+        contract.draw_for_payment(
+            &consumer,
+            &token1_address,
+            &3000,
+            &payee,
+            &String::from_str(&env, "PAY-002"),
+        );
+    }
+
+    #[test]
+    fn test_draw_for_payment_new_period_resets_cap() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        fund_pool(&env, &contract, &token1_address, 10000);
+
+        let consumer = Address::generate(&env);
+        test_setup::allow_consumer(&env, &contract, &consumer, &token1_address, 5000, 86400);
+
+        let payee = Address::generate(&env);
+        contract.draw_for_payment(
+            &consumer,
+            &token1_address,
+            &4000,
+            &payee,
+            &String::from_str(&env, "PAY-001"),
+        );
+
+        // Move past the end of the period
+        env.ledger().with_mut(|li| li.timestamp += 86400);
+
+        contract.draw_for_payment(
+            &consumer,
+            &token1_address,
+            &4000,
+            &payee,
+            &String::from_str(&env, "PAY-002"),
+        );
+
+        assert_eq!(check_balance(&env, &token1_address, &payee), 8000);
+        let allowance = contract.get_consumer_allowance(&consumer, &token1_address);
+        assert_eq!(allowance.spent_in_period, 4000);
+    }
+
+    #[test]
+    fn test_draw_for_payment_cap_ignores_deposits_in_period() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        fund_pool(&env, &contract, &token1_address, 10000);
+
+        let consumer = Address::generate(&env);
+        test_setup::allow_consumer(&env, &contract, &consumer, &token1_address, 5000, 86400);
+
+        let payee = Address::generate(&env);
+        contract.draw_for_payment(
+            &consumer,
+            &token1_address,
+            &4000,
+            &payee,
+            &String::from_str(&env, "PAY-001"),
+        );
+
+        // New deposits in the same period leave the remaining cap at 1000
+        fund_pool(&env, &contract, &token1_address, 10000);
+        let result = contract.try_draw_for_payment(
+            &consumer,
+            &token1_address,
+            &2000,
+            &payee,
+            &String::from_str(&env, "PAY-002"),
+        );
+        assert_eq!(result, Err(Ok(Error::SpendingCapExceeded)));
+
+        contract.draw_for_payment(
+            &consumer,
+            &token1_address,
+            &1000,
+            &payee,
+            &String::from_str(&env, "PAY-003"),
+        );
+        assert_eq!(check_balance(&env, &token1_address, &payee), 5000);
+    }
+
+    #[test]
+    fn test_draw_for_payment_extreme_allowance_does_not_overflow() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        fund_pool(&env, &contract, &token1_address, 10000);
+        env.ledger().with_mut(|li| li.timestamp += 1000);
+
+        // A period that never ends and a cap at the largest amount
+        let consumer = Address::generate(&env);
+        test_setup::allow_consumer(
+            &env,
+            &contract,
+            &consumer,
+            &token1_address,
+            i128::MAX,
+            u64::MAX,
+        );
+
+        let payee = Address::generate(&env);
+        contract.draw_for_payment(
+            &consumer,
+            &token1_address,
+            &1000,
+            &payee,
+            &String::from_str(&env, "PAY-001"),
+        );
+        let allowance = contract.get_consumer_allowance(&consumer, &token1_address);
+        assert_eq!(allowance.spent_in_period, 1000);
+
+        // Going past the cap is rejected rather than overflowing
+        let result = contract.try_draw_for_payment(
+            &consumer,
+            &token1_address,
+            &i128::MAX,
+            &payee,
+            &String::from_str(&env, "PAY-002"),
+        );
+        assert_eq!(result, Err(Ok(Error::SpendingCapExceeded)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #9)")]
+    fn test_draw_for_payment_below_min_threshold() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        fund_pool(&env, &contract, &token1_address, 10000);
+        contract.set_thresholds(&token1_address, &8000, &9000);
+
+        let consumer = Address::generate(&env);
+        test_setup::allow_consumer(&env, &contract, &consumer, &token1_address, 5000, 86400);
+
+        let payee = Address::generate(&env);
+        contract.draw_for_payment(
+            &consumer,
+            &token1_address,
+            &3000,
+            &payee,
+            &String::from_str(&env, "PAY-001"),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #13)")]
+    fn test_remove_consumer() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        fund_pool(&env, &contract, &token1_address, 10000);
+
+        let consumer = Address::generate(&env);
+        test_setup::allow_consumer(&env, &contract, &consumer, &token1_address, 5000, 86400);
+        contract.remove_consumer(&consumer, &token1_address);
+
+        let payee = Address::generate(&env);
+        contract.draw_for_payment(
+            &consumer,
+            &token1_address,
+            &1000,
+            &payee,
+            &String::from_str(&env, "PAY-001"),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #7)")]
+    fn test_draw_for_payment_leaves_reward_reserve() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        fund_pool(&env, &contract, &token1_address, 1000);
+        contract.fund_reward_reserve(&token1_address, &500);
+
+        let consumer = Address::generate(&env);
+        test_setup::allow_consumer(&env, &contract, &consumer, &token1_address, 5000, 86400);
+
+        // Only the 1000 outside the reserve can be drawn (should fail with InsufficientBalance error)
+        contract.draw_for_payment(
+            &consumer,
+            &token1_address,
+            &1200,
+            &Address::generate(&env),
+            &String::from_str(&env, "PAY-001"),
+        );
+    }
+
+    #[test]
+    fn test_raising_allowance_is_timelocked() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        // A new consumer cannot be allowlisted at once
+        let consumer = Address::generate(&env);
+        let result = contract.try_set_consumer_allowance(&consumer, &token1_address, &5000, &86400);
+        assert_eq!(result, Err(Ok(Error::PolicyChangeTimelocked)));
+
+        test_setup::allow_consumer(&env, &contract, &consumer, &token1_address, 5000, 86400);
+
+        // Nor can its cap be raised, but it can be lowered
+        let result = contract.try_set_consumer_allowance(&consumer, &token1_address, &9000, &86400);
+        assert_eq!(result, Err(Ok(Error::PolicyChangeTimelocked)));
+        contract.set_consumer_allowance(&consumer, &token1_address, &2000, &86400);
+        let allowance = contract.get_consumer_allowance(&consumer, &token1_address);
+        assert_eq!(allowance.spending_cap, 2000);
+    }
+}
+
+mod test_asset_management {
+    use super::*;
+    use crate::types::PoolTotals;
+    use soroban_sdk::testutils::Ledger;
+
+    #[test]
+    fn test_add_asset() {
+        let env = Env::default();
+        let (contract, admin, _, _, _) = test_setup::setup_contract(&env);
+
+        // Create a new token and add it to the pool
+        let (token3_address, token3_admin) = create_token_contract(&env, &admin);
+        let asset = contract.add_asset(&token3_address);
+        assert_eq!(asset.token, token3_address);
+        assert_eq!(asset.decimals, 7);
+
+        let assets = contract.get_assets();
+        assert_eq!(assets.len(), 3);
+
+        // The new asset accepts deposits straight away
+        let depositor = Address::generate(&env);
+        mint_tokens(&token3_admin, &depositor, &1000);
+        contract.add_liquidity(&token3_address, &1000, &depositor);
+        assert_eq!(
+            check_balance(&env, &token3_address, &contract.address),
+            1000
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #16)")]
+    fn test_add_existing_asset() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        contract.add_asset(&token1_address);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #17)")]
+    fn test_delisted_asset_rejects_deposits() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        contract.delist_asset(&token1_address);
+        assert!(contract.is_asset_delisted(&token1_address));
+
+        let depositor = Address::generate(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &depositor, &1000);
+        contract.add_liquidity(&token1_address, &1000, &depositor);
+    }
+
+    #[test]
+    fn test_delisted_asset_allows_withdrawals() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        let depositor = Address::generate(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &depositor, &1000);
+        contract.add_liquidity(&token1_address, &1000, &depositor);
+
+        contract.delist_asset(&token1_address);
+        contract.withdraw_deposited_tokens(&token1_address, &1000, &depositor);
+
+        assert_eq!(check_balance(&env, &token1_address, &contract.address), 0);
+    }
+
+    #[test]
+    fn test_remove_asset() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, token2_address) = test_setup::setup_contract(&env);
+
+        contract.set_thresholds(&token1_address, &0, &1000);
+        contract.delist_asset(&token1_address);
+        contract.remove_asset(&token1_address);
+
+        let assets = contract.get_assets();
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets.get_unchecked(0).token, token2_address);
+        assert!(!contract.is_asset_delisted(&token1_address));
+
+        // Thresholds for the removed asset are gone with it
+        let result = contract.try_get_liquidity_status(&token1_address);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #10)")]
+    fn test_remove_listed_asset() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        contract.remove_asset(&token1_address);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #18)")]
+    fn test_remove_asset_with_balance() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        let depositor = Address::generate(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &depositor, &1000);
+        contract.add_liquidity(&token1_address, &1000, &depositor);
+
+        contract.delist_asset(&token1_address);
+        contract.remove_asset(&token1_address);
+    }
+
+    #[test]
+    fn test_remove_asset_with_dust() {
+        let env = Env::default();
+        let (contract, _, fee_collector, token1_address, _) = test_setup::setup_contract(&env);
+
+        // Tokens sent outside the pool's own flows are owed to nobody
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &contract.address, &3);
+
+        let consumer = Address::generate(&env);
+        test_setup::allow_consumer(&env, &contract, &consumer, &token1_address, 1000, 86400);
+
+        contract.delist_asset(&token1_address);
+        contract.remove_asset(&token1_address);
+
+        assert_eq!(check_balance(&env, &token1_address, &contract.address), 0);
+        assert_eq!(check_balance(&env, &token1_address, &fee_collector), 3);
+
+        // The consumer's allowance does not come back with the asset
+        contract.add_asset(&token1_address);
+        assert!(contract
+            .try_get_consumer_allowance(&consumer, &token1_address)
+            .is_err());
+        assert_eq!(contract.get_consumers(&token1_address).len(), 0);
+    }
+
+    #[test]
+    fn test_readd_removed_asset() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        contract.grant_role(&Role::Treasurer, &Address::generate(&env));
+
+        // Build up per-asset state: a rate, a loosened policy, totals and pending queues
+        contract.set_yield_rate(&token1_address, &500);
+        test_setup::loosen_withdrawal_policy(
+            &env,
+            &contract,
+            &token1_address,
+            1000,
+            3600,
+            1000,
+            86400,
+        );
+        let depositor = Address::generate(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &depositor, &1000);
+        contract.add_liquidity(&token1_address, &1000, &depositor);
+        contract.withdraw_deposited_tokens(&token1_address, &1000, &depositor);
+        mint_tokens(&token_admin, &contract.address, &5000);
+        contract.queue_admin_withdrawal(&token1_address, &2000);
+        contract.queue_withdrawal_policy(&token1_address, &5000, &0, &5000, &86400);
+
+        contract.delist_asset(&token1_address);
+        contract.remove_asset(&token1_address);
+        contract.add_asset(&token1_address);
+
+        // The re-added asset starts from scratch
+        assert_eq!(contract.get_yield_rate(&token1_address), 0);
+        let policy = contract.get_withdrawal_policy(&token1_address);
+        assert_eq!(policy.timelock_threshold, 0);
+        assert_eq!(policy.period_cap, 0);
+        assert_eq!(
+            contract.get_pool_totals(&token1_address),
+            PoolTotals::default()
+        );
+        assert_eq!(contract.get_queued_withdrawals().len(), 0);
+        assert_eq!(contract.get_queued_policy_changes().len(), 0);
+        assert_eq!(contract.reconcile(&token1_address).unattributed, 0);
+
+        // A new deposit earns nothing until a rate is set again
+        mint_tokens(&token_admin, &depositor, &1000);
+        contract.add_liquidity(&token1_address, &1000, &depositor);
+        env.ledger().with_mut(|li| li.timestamp += 86400);
+        assert_eq!(
+            contract.get_accrued_interest(&token1_address, &depositor),
+            0
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #18)")]
+    fn test_remove_asset_with_reward_reserve() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        contract.fund_reward_reserve(&token1_address, &500);
+
+        contract.delist_asset(&token1_address);
+        contract.remove_asset(&token1_address);
+    }
+}
+
+mod test_rebalance {
+    use super::*;
+
+    #[test]
+    fn test_rebalance_transfer() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        let depositor = Address::generate(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &depositor, &5000);
+        contract.add_liquidity(&token1_address, &5000, &depositor);
+
+        // Liquidity the pool holds beyond what it owes depositors
+        mint_tokens(&token_admin, &contract.address, &2000);
+        assert_eq!(contract.get_surplus(&token1_address), 2000);
+
+        // The managing factory moves the surplus to a sibling pool
+        let other_pool = Address::generate(&env);
+
+        contract.rebalance_transfer(&token1_address, &2000, &other_pool);
+
+        assert_eq!(check_balance(&env, &token1_address, &other_pool), 2000);
+        assert_eq!(
+            check_balance(&env, &token1_address, &contract.address),
+            5000
+        );
+        assert_eq!(contract.get_surplus(&token1_address), 0);
+
+        // The depositor's claim is still fully backed
+        let balance = contract.get_depositor_balance(&token1_address, &depositor);
+        assert_eq!(balance.amount, 5000);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #29)")]
+    fn test_rebalance_transfer_of_deposits() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        let depositor = Address::generate(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &depositor, &5000);
+        contract.add_liquidity(&token1_address, &5000, &depositor);
+        contract.fund_reward_reserve(&token1_address, &500);
+        mint_tokens(&token_admin, &contract.address, &1000);
+
+        // Only the 1000 above deposits and the reward reserve may move
+        contract.rebalance_transfer(&token1_address, &1500, &Address::generate(&env));
+    }
+
+    #[test]
+    fn test_rebalance_transfer_requires_manager() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &contract.address, &2000);
+
+        // Only the factory fixed at initialization may move liquidity, not the admin
+        let other_pool = Address::generate(&env);
+        contract.rebalance_transfer(&token1_address, &2000, &other_pool);
+        let auths = env.auths();
+        assert_eq!(auths.first().unwrap().0, contract.get_manager());
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #9)")]
+    fn test_rebalance_transfer_below_min_threshold() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        let depositor = Address::generate(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &depositor, &5000);
+        contract.add_liquidity(&token1_address, &5000, &depositor);
+        contract.set_thresholds(&token1_address, &4000, &4500);
+
+        contract.rebalance_transfer(&token1_address, &2000, &Address::generate(&env));
+    }
+}
+
+mod test_lifecycle {
+    use super::*;
+    use crate::types::{DataKey, DepositorBalance, Error};
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #20)")]
+    fn test_paused_pool_rejects_deposits() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        contract.pause();
+        assert!(contract.is_paused());
+
+        let depositor = Address::generate(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &depositor, &1000);
+        contract.add_liquidity(&token1_address, &1000, &depositor);
+    }
+
+    #[test]
+    fn test_unpause() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        contract.pause();
+        contract.unpause();
+        assert!(!contract.is_paused());
+
+        let depositor = Address::generate(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &depositor, &1000);
+        contract.add_liquidity(&token1_address, &1000, &depositor);
+    }
+
+    #[test]
+    fn test_depositor_index() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+
+        let depositor1 = Address::generate(&env);
+        let depositor2 = Address::generate(&env);
+        mint_tokens(&token_admin, &depositor1, &2000);
+        mint_tokens(&token_admin, &depositor2, &1000);
+
+        contract.add_liquidity(&token1_address, &1000, &depositor1);
+        contract.add_liquidity(&token1_address, &1000, &depositor1);
+        contract.add_liquidity(&token1_address, &1000, &depositor2);
+        assert_eq!(contract.get_depositor_count(&token1_address), 2);
+        assert_eq!(
+            contract.get_depositors(&token1_address, &1, &10),
+            Vec::from_array(&env, [depositor2.clone()])
+        );
+
+        // Fully withdrawn depositors leave the index; the last entry takes their place
+        contract.withdraw_deposited_tokens(&token1_address, &1000, &depositor1);
+        contract.withdraw_deposited_tokens(&token1_address, &1000, &depositor1);
+        let depositors = contract.get_depositors(&token1_address, &0, &10);
+        assert_eq!(depositors, Vec::from_array(&env, [depositor2]));
+    }
+
+    #[test]
+    fn test_index_depositors_from_before_the_index() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        // A pool upgraded from a version that kept no depositor index
+        let depositor = Address::generate(&env);
+        env.as_contract(&contract.address, || {
+            env.storage().persistent().set(
+                &DataKey::DepositorBalance(token1_address.clone(), depositor.clone()),
+                &DepositorBalance {
+                    amount: 1000,
+                    timestamp: 0,
+                },
+            );
+            env.storage()
+                .persistent()
+                .remove(&DataKey::DepositorsIndexed(token1_address.clone()));
+        });
+        let manager = contract.get_manager();
+        env.as_contract(&contract.address, || {
+            env.storage().persistent().remove(&DataKey::Manager);
+        });
+
+        // Nothing can migrate until the pool learns its manager
+        let result = contract.try_migrate_depositors(&token1_address, &10);
+        assert_eq!(result, Err(Ok(Error::Unauthorized)));
+        contract.set_manager(&manager);
+        assert_eq!(contract.get_manager(), manager);
+
+        // The manager is fixed once set
+        let result = contract.try_set_manager(&Address::generate(&env));
+        assert_eq!(result, Err(Ok(Error::Unauthorized)));
+
+        // Its liquidity cannot migrate until every depositor is indexed
+        let result = contract.try_migrate_depositors(&token1_address, &10);
+        assert_eq!(result, Err(Ok(Error::DepositorIndexIncomplete)));
+
+        // Addresses without a balance are skipped
+        let stranger = Address::generate(&env);
+        let indexed = contract.index_depositors(
+            &token1_address,
+            &Vec::from_array(&env, [depositor.clone(), stranger]),
+        );
+        assert_eq!(indexed, 1);
+        contract.complete_depositor_index(&token1_address);
+
+        let records = contract.migrate_depositors(&token1_address, &10);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records.get_unchecked(0).depositor, depositor);
+        assert_eq!(records.get_unchecked(0).balance.amount, 1000);
+    }
+
+    #[test]
+    fn test_migrate_liquidity() {
+        let env = Env::default();
+        let (contract, admin, fee_collector, token1_address, token2_address) =
+            test_setup::setup_contract(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+
+        let depositor = Address::generate(&env);
+        mint_tokens(&token_admin, &depositor, &3000);
+        contract.add_liquidity(&token1_address, &3000, &depositor);
+
+        // A replacement pool with the same assets, both managed by the same factory
+        let assets = contract.get_assets();
+        let new_pool_id = env.register(
+            LiquidityPool,
+            (
+                admin.clone(),
+                fee_collector.clone(),
+                assets,
+                5000u32,
+                contract.get_manager(),
+            ),
+        );
+        let new_pool = LiquidityPoolClient::new(&env, &new_pool_id);
+
+        // Tokens stay until every depositor has been handed over
+        let result = contract.try_migrate_liquidity(&token1_address, &new_pool_id);
+        assert_eq!(result, Err(Ok(Error::DepositorsRemaining)));
+
+        let records = contract.migrate_depositors(&token1_address, &10);
+        new_pool.import_depositors(&token1_address, &records);
+        let moved = contract.migrate_liquidity(&token1_address, &new_pool_id);
+        assert_eq!(moved, (3000, 0));
+
+        // Tokens and the depositor's balance now live in the new pool
+        assert_eq!(check_balance(&env, &token1_address, &contract.address), 0);
+        assert_eq!(check_balance(&env, &token1_address, &new_pool_id), 3000);
+        assert!(contract
+            .try_get_depositor_balance(&token1_address, &depositor)
+            .is_err());
+        let balance = new_pool.get_depositor_balance(&token1_address, &depositor);
+        assert_eq!(balance.amount, 3000);
+        assert_eq!(new_pool.get_depositor_count(&token1_address), 1);
+
+        // Other assets are untouched
+        assert_eq!(contract.get_depositor_count(&token2_address), 0);
+    }
+
+    #[test]
+    fn test_migrate_liquidity_with_donation() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+
+        let depositor = Address::generate(&env);
+        mint_tokens(&token_admin, &depositor, &3000);
+        contract.add_liquidity(&token1_address, &3000, &depositor);
+        contract.migrate_depositors(&token1_address, &10);
+
+        // Tokens sent to the pool outside its own flows move along with the rest
+        mint_tokens(&token_admin, &contract.address, &500);
+        let destination = Address::generate(&env);
+        let moved = contract.migrate_liquidity(&token1_address, &destination);
+        assert_eq!(moved, (3500, 0));
+        assert_eq!(check_balance(&env, &token1_address, &destination), 3500);
+
+        // Only what the pool tracked comes off its tracked balance
+        let totals = contract.get_pool_totals(&token1_address);
+        assert_eq!(totals.tracked_balance, 0);
+        let report = contract.reconcile(&token1_address);
+        assert_eq!(report.unattributed, 0);
+        assert_eq!(report.deposit_shortfall, 0);
+    }
+
+    #[test]
+    fn test_migrate_depositors_in_pages() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+
+        for _ in 0..5 {
+            let depositor = Address::generate(&env);
+            mint_tokens(&token_admin, &depositor, &100);
+            contract.add_liquidity(&token1_address, &100, &depositor);
+        }
+
+        assert_eq!(contract.migrate_depositors(&token1_address, &2).len(), 2);
+        assert_eq!(contract.migrate_depositors(&token1_address, &2).len(), 2);
+        assert_eq!(contract.get_depositor_count(&token1_address), 1);
+        assert_eq!(contract.migrate_depositors(&token1_address, &2).len(), 1);
+        assert_eq!(contract.get_pool_totals(&token1_address).total_deposits, 0);
+    }
+}
+
+mod test_roles {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #1)")]
+    fn test_admin_withdraw_requires_treasurer() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        // No treasurer has been granted, so the admin cannot withdraw
+        contract.admin_withdraw_funds(&token1_address, &100);
+    }
+
+    #[test]
+    fn test_grant_and_revoke_role() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        let risk_manager = Address::generate(&env);
+        contract.grant_role(&Role::RiskManager, &risk_manager);
+        assert_eq!(
+            contract.get_role(&Role::RiskManager),
+            Some(risk_manager.clone())
+        );
+
+        // Thresholds are now authorized by the risk manager
+        contract.set_thresholds(&token1_address, &1000, &5000);
+        let auths = env.auths();
+        assert_eq!(auths.first().unwrap().0, risk_manager);
+
+        contract.revoke_role(&Role::RiskManager);
+        assert_eq!(contract.get_role(&Role::RiskManager), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #22)")]
+    fn test_treasurer_cannot_be_risk_manager() {
+        let env = Env::default();
+        let (contract, _, _, _, _) = test_setup::setup_contract(&env);
+
+        let account = Address::generate(&env);
+        contract.grant_role(&Role::RiskManager, &account);
+
+        // Try to make the threshold-setter the withdrawer (should fail with RoleConflict error)
+        contract.grant_role(&Role::Treasurer, &account);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #22)")]
+    fn test_admin_cannot_be_treasurer_without_risk_manager() {
+        let env = Env::default();
+        let (contract, admin, _, _, _) = test_setup::setup_contract(&env);
+
+        // The admin sets thresholds while no risk manager is granted
+        contract.grant_role(&Role::Treasurer, &admin);
+    }
+
+    #[test]
+    fn test_two_step_admin_handover() {
+        let env = Env::default();
+        let (contract, admin, _, _, _) = test_setup::setup_contract(&env);
+
+        let new_admin = Address::generate(&env);
+        contract.propose_admin(&new_admin);
+
+        // The old admin stays in charge until the handover is accepted
+        assert_eq!(contract.get_admin(), admin);
+
+        contract.accept_admin();
+        assert_eq!(contract.get_admin(), new_admin);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #23)")]
+    fn test_accept_admin_without_proposal() {
+        let env = Env::default();
+        let (contract, _, _, _, _) = test_setup::setup_contract(&env);
+
+        contract.accept_admin();
+    }
+
+    #[test]
+    fn test_set_fee_collector() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        let fee_manager = Address::generate(&env);
+        let new_collector = Address::generate(&env);
+        contract.grant_role(&Role::FeeManager, &fee_manager);
+        contract.set_fee_collector(&new_collector);
+
+        // Withdrawal fees now go to the new collector
+        let depositor = Address::generate(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &depositor, &10000);
+        contract.add_liquidity(&token1_address, &10000, &depositor);
+        contract.withdraw_deposited_tokens(&token1_address, &10000, &depositor);
+
+        assert_eq!(
+            check_balance(&env, &token1_address, &new_collector),
+            calculate_fee(10000)
+        );
+    }
+}
+
+mod test_withdrawal_controls {
+    use super::*;
+    use crate::types::Error;
+    use soroban_sdk::testutils::Ledger;
+
+    // Fund the pool and grant a treasurer
+    fn setup_funded_pool(env: &Env) -> (LiquidityPoolClient<'_>, Address, Address) {
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(env);
+
+        let depositor = Address::generate(env);
+        let token_admin = TokenAdmin::new(env, &token1_address);
+        mint_tokens(&token_admin, &depositor, &10000);
+        contract.add_liquidity(&token1_address, &10000, &depositor);
+
+        let treasurer = Address::generate(env);
+        contract.grant_role(&Role::Treasurer, &treasurer);
+
+        (contract, token1_address, treasurer)
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #24)")]
+    fn test_large_withdrawal_must_be_queued() {
+        let env = Env::default();
+        let (contract, token1_address, _) = setup_funded_pool(&env);
+        test_setup::loosen_withdrawal_policy(
+            &env,
+            &contract,
+            &token1_address,
+            1000,
+            86400,
+            5000,
+            86400,
+        );
+
+        // Try to withdraw above the timelock threshold (should fail with WithdrawalTimelocked error)
+        contract.admin_withdraw_funds(&token1_address, &1000);
+    }
+
+    #[test]
+    fn test_queued_withdrawal_executes_after_timelock() {
+        let env = Env::default();
+        let (contract, token1_address, treasurer) = setup_funded_pool(&env);
+        test_setup::loosen_withdrawal_policy(
+            &env,
+            &contract,
+            &token1_address,
+            1000,
+            86400,
+            5000,
+            86400,
+        );
+
+        let id = contract.queue_admin_withdrawal(&token1_address, &2000);
+
+        // Depositors can see the pending withdrawal
+        let queued = contract.get_queued_withdrawals();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued.get_unchecked(0).amount, 2000);
+
+        // Too early to execute
+        assert!(contract.try_execute_admin_withdrawal(&id).is_err());
+
+        env.ledger().with_mut(|li| li.timestamp += 86400);
+        contract.execute_admin_withdrawal(&id);
+
+        assert_eq!(
+            check_balance(&env, &token1_address, &treasurer),
+            2000 - calculate_fee(2000)
+        );
+        assert_eq!(contract.get_queued_withdrawals().len(), 0);
+    }
+
+    #[test]
+    fn test_admin_withdrawal_of_nothing() {
+        let env = Env::default();
+        let (contract, token1_address, _) = setup_funded_pool(&env);
+        test_setup::loosen_withdrawal_policy(
+            &env,
+            &contract,
+            &token1_address,
+            1000,
+            86400,
+            5000,
+            86400,
+        );
+
+        let result = contract.try_admin_withdraw_funds(&token1_address, &0);
+        assert_eq!(result, Err(Ok(Error::InvalidAmount)));
+        let result = contract.try_admin_withdraw_funds(&token1_address, &-500);
+        assert_eq!(result, Err(Ok(Error::InvalidAmount)));
+        assert_eq!(
+            contract
+                .get_withdrawal_policy(&token1_address)
+                .withdrawn_in_period,
+            0
+        );
+    }
+
+    #[test]
+    fn test_queued_withdrawal_of_delisted_asset() {
+        let env = Env::default();
+        let (contract, token1_address, treasurer) = setup_funded_pool(&env);
+        test_setup::loosen_withdrawal_policy(
+            &env,
+            &contract,
+            &token1_address,
+            1000,
+            86400,
+            5000,
+            86400,
+        );
+
+        let id = contract.queue_admin_withdrawal(&token1_address, &2000);
+        contract.delist_asset(&token1_address);
+
+        // Depositors winding down a delisted asset are not raced by the queued withdrawal
+        env.ledger().with_mut(|li| li.timestamp += 86400);
+        let result = contract.try_execute_admin_withdrawal(&id);
+        assert_eq!(result, Err(Ok(Error::InvalidAsset)));
+        assert_eq!(check_balance(&env, &token1_address, &treasurer), 0);
+    }
+
+    #[test]
+    fn test_admin_withdrawal_with_extreme_period() {
+        let env = Env::default();
+        let (contract, token1_address, treasurer) = setup_funded_pool(&env);
+        test_setup::loosen_withdrawal_policy(
+            &env,
+            &contract,
+            &token1_address,
+            1000,
+            86400,
+            5000,
+            u64::MAX,
+        );
+
+        // A period that never ends keeps counting withdrawals instead of overflowing
+        contract.admin_withdraw_funds(&token1_address, &500);
+        contract.admin_withdraw_funds(&token1_address, &500);
+        assert_eq!(
+            check_balance(&env, &token1_address, &treasurer),
+            1000 - 2 * calculate_fee(500)
+        );
+        assert_eq!(
+            contract
+                .get_withdrawal_policy(&token1_address)
+                .withdrawn_in_period,
+            1000
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #26)")]
+    fn test_guardian_cancels_withdrawal() {
+        let env = Env::default();
+        let (contract, token1_address, _) = setup_funded_pool(&env);
+        test_setup::loosen_withdrawal_policy(
+            &env,
+            &contract,
+            &token1_address,
+            1000,
+            86400,
+            5000,
+            86400,
+        );
+
+        let guardian = Address::generate(&env);
+        contract.grant_role(&Role::Guardian, &guardian);
+
+        let id = contract.queue_admin_withdrawal(&token1_address, &2000);
+        contract.cancel_admin_withdrawal(&id);
+        assert_eq!(contract.get_queued_withdrawals().len(), 0);
+
+        // Try to execute the cancelled withdrawal (should fail with WithdrawalNotFound error)
+        env.ledger().with_mut(|li| li.timestamp += 86400);
+        contract.execute_admin_withdrawal(&id);
+    }
+
+    #[test]
+    fn test_outflow_cap_per_period() {
+        let env = Env::default();
+        let (contract, token1_address, _) = setup_funded_pool(&env);
+        test_setup::loosen_withdrawal_policy(
+            &env,
+            &contract,
+            &token1_address,
+            1000,
+            86400,
+            1500,
+            86400,
+        );
+
+        contract.admin_withdraw_funds(&token1_address, &900);
+
+        // A second withdrawal in the same period would pass the cap
+        let result = contract.try_admin_withdraw_funds(&token1_address, &900);
+        assert_eq!(result, Err(Ok(Error::OutflowCapExceeded)));
+
+        // The cap resets once the period has elapsed
+        env.ledger().with_mut(|li| li.timestamp += 86400);
+        contract.admin_withdraw_funds(&token1_address, &900);
+    }
+
+    #[test]
+    fn test_default_policy_blocks_withdrawals() {
+        let env = Env::default();
+        let (contract, token1_address, _) = setup_funded_pool(&env);
+
+        // Without a policy every withdrawal is timelocked and nothing fits under the cap
+        let result = contract.try_admin_withdraw_funds(&token1_address, &1);
+        assert_eq!(result, Err(Ok(Error::WithdrawalTimelocked)));
+
+        let id = contract.queue_admin_withdrawal(&token1_address, &1000);
+        env.ledger().with_mut(|li| li.timestamp += 172_800);
+        let result = contract.try_execute_admin_withdrawal(&id);
+        assert_eq!(result, Err(Ok(Error::OutflowCapExceeded)));
+    }
+
+    #[test]
+    fn test_loosening_policy_is_timelocked() {
+        let env = Env::default();
+        let (contract, token1_address, _) = setup_funded_pool(&env);
+        test_setup::loosen_withdrawal_policy(
+            &env,
+            &contract,
+            &token1_address,
+            1000,
+            86400,
+            5000,
+            86400,
+        );
+
+        // Raising the cap cannot take effect at once
+        let result =
+            contract.try_set_withdrawal_policy(&token1_address, &1000, &86400, &9000, &86400);
+        assert_eq!(result, Err(Ok(Error::PolicyChangeTimelocked)));
+
+        // Tightening it can
+        contract.set_withdrawal_policy(&token1_address, &500, &86400, &2000, &86400);
+        assert_eq!(
+            contract.get_withdrawal_policy(&token1_address).period_cap,
+            2000
+        );
+
+        // A queued loosening waits out the current timelock
+        let id = contract.queue_withdrawal_policy(&token1_address, &1000, &86400, &9000, &86400);
+        assert_eq!(contract.get_queued_policy_changes().len(), 1);
+        let result = contract.try_execute_policy_change(&id);
+        assert_eq!(result, Err(Ok(Error::PolicyChangeTimelocked)));
+
+        env.ledger().with_mut(|li| li.timestamp += 86400);
+        contract.execute_policy_change(&id);
+        assert_eq!(
+            contract.get_withdrawal_policy(&token1_address).period_cap,
+            9000
+        );
+        assert_eq!(contract.get_queued_policy_changes().len(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #33)")]
+    fn test_guardian_cancels_policy_change() {
+        let env = Env::default();
+        let (contract, token1_address, _) = setup_funded_pool(&env);
+
+        let guardian = Address::generate(&env);
+        contract.grant_role(&Role::Guardian, &guardian);
+
+        let consumer = Address::generate(&env);
+        let id = contract.queue_consumer_allowance(&consumer, &token1_address, &5000, &86400);
+        contract.cancel_policy_change(&id);
+        assert_eq!(contract.get_queued_policy_changes().len(), 0);
+
+        // Try to execute the cancelled change (should fail with PolicyChangeNotFound error)
+        env.ledger().with_mut(|li| li.timestamp += 172_800);
+        contract.execute_policy_change(&id);
+    }
+}
+
+mod test_accounting {
+    use super::*;
+    use crate::types::{DataKey, DepositorBalance, Error};
+
+    #[test]
+    fn test_totals_track_flows() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        let depositor = Address::generate(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &depositor, &10000);
+        contract.add_liquidity(&token1_address, &10000, &depositor);
+        contract.withdraw_deposited_tokens(&token1_address, &4000, &depositor);
+
+        let treasurer = Address::generate(&env);
+        contract.grant_role(&Role::Treasurer, &treasurer);
+        test_setup::loosen_withdrawal_policy(
+            &env,
+            &contract,
+            &token1_address,
+            5000,
+            86400,
+            5000,
+            86400,
+        );
+        contract.admin_withdraw_funds(&token1_address, &1000);
+
+        let totals = contract.get_pool_totals(&token1_address);
+        assert_eq!(totals.total_deposits, 6000);
+        assert_eq!(totals.total_fees, calculate_fee(4000) + calculate_fee(1000));
+        assert_eq!(totals.admin_withdrawals, 1000);
+        assert_eq!(totals.tracked_balance, 5000);
+
+        // Tracked and actual balances agree, but deposits are no longer fully backed
+        let report = contract.reconcile(&token1_address);
+        assert_eq!(report.actual_balance, 5000);
+        assert_eq!(report.unattributed, 0);
+        assert_eq!(report.deposit_shortfall, 1000);
+    }
+
+    #[test]
+    fn test_sweep_donation() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        let depositor = Address::generate(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &depositor, &10000);
+        contract.add_liquidity(&token1_address, &5000, &depositor);
+
+        // Tokens sent straight to the pool are unattributed
+        let token_client = TokenClient::new(&env, &token1_address);
+        token_client.transfer(&depositor, &contract.address, &700);
+        assert_eq!(contract.reconcile(&token1_address).unattributed, 700);
+
+        let recipient = Address::generate(&env);
+        assert_eq!(contract.sweep_surplus(&token1_address, &recipient), 700);
+
+        assert_eq!(check_balance(&env, &token1_address, &recipient), 700);
+        assert_eq!(
+            contract.get_pool_totals(&token1_address).swept_donations,
+            700
+        );
+        assert_eq!(contract.reconcile(&token1_address).unattributed, 0);
+    }
+
+    #[test]
+    fn test_sweep_on_pool_from_before_totals() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+
+        // A pool upgraded from a version that tracked neither totals nor depositors
+        let depositor1 = Address::generate(&env);
+        let depositor2 = Address::generate(&env);
+        env.as_contract(&contract.address, || {
+            for depositor in [depositor1.clone(), depositor2.clone()] {
+                env.storage().persistent().set(
+                    &DataKey::DepositorBalance(token1_address.clone(), depositor),
+                    &DepositorBalance {
+                        amount: 1000,
+                        timestamp: 0,
+                    },
+                );
+            }
+            env.storage()
+                .persistent()
+                .remove(&DataKey::DepositorsIndexed(token1_address.clone()));
+        });
+        mint_tokens(&token_admin, &contract.address, &2000);
+
+        // Its deposits look unattributed, but cannot be swept or rebalanced away
+        let recipient = Address::generate(&env);
+        let result = contract.try_sweep_surplus(&token1_address, &recipient);
+        assert_eq!(result, Err(Ok(Error::DepositorIndexIncomplete)));
+        assert_eq!(contract.get_surplus(&token1_address), 0);
+
+        // Depositors are counted into the totals as they are indexed, by a deposit or the backfill
+        mint_tokens(&token_admin, &depositor1, &500);
+        contract.add_liquidity(&token1_address, &500, &depositor1);
+        assert_eq!(
+            contract.get_pool_totals(&token1_address).total_deposits,
+            1500
+        );
+
+        contract.index_depositors(&token1_address, &Vec::from_array(&env, [depositor2]));
+        contract.complete_depositor_index(&token1_address);
+        let totals = contract.get_pool_totals(&token1_address);
+        assert_eq!(totals.total_deposits, 2500);
+        assert_eq!(totals.tracked_balance, 2500);
+
+        // Only a later donation is swept
+        mint_tokens(&token_admin, &contract.address, &300);
+        assert_eq!(contract.sweep_surplus(&token1_address, &recipient), 300);
+        assert_eq!(
+            check_balance(&env, &token1_address, &contract.address),
+            2500
+        );
+    }
+
+    #[test]
+    fn test_sweep_keeps_deposits_backed() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+
+        let depositor = Address::generate(&env);
+        mint_tokens(&token_admin, &depositor, &1000);
+        contract.add_liquidity(&token1_address, &1000, &depositor);
+        contract.fund_reward_reserve(&token1_address, &200);
+
+        // The treasurer takes out part of the deposits, then a donation arrives
+        let treasurer = Address::generate(&env);
+        contract.grant_role(&Role::Treasurer, &treasurer);
+        test_setup::loosen_withdrawal_policy(
+            &env,
+            &contract,
+            &token1_address,
+            5000,
+            86400,
+            5000,
+            86400,
+        );
+        contract.admin_withdraw_funds(&token1_address, &400);
+        mint_tokens(&token_admin, &contract.address, &600);
+        assert_eq!(contract.reconcile(&token1_address).unattributed, 600);
+
+        // Only what sits above deposits and the reward reserve can go
+        let recipient = Address::generate(&env);
+        assert_eq!(contract.sweep_surplus(&token1_address, &recipient), 200);
+        assert_eq!(
+            check_balance(&env, &token1_address, &contract.address),
+            1200
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #7)")]
+    fn test_sweep_without_surplus() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        // Nothing unattributed to sweep (should fail with InsufficientBalance error)
+        contract.sweep_surplus(&token1_address, &Address::generate(&env));
+    }
+}
+
+mod test_interest {
+    use super::*;
+    use soroban_sdk::testutils::Ledger;
+
+    const YEAR: u64 = 31_536_000;
+
+    // Deposit 10000 of token1 at a 10% APR with a funded reward reserve
+    fn setup_yielding_pool(env: &Env) -> (LiquidityPoolClient<'_>, Address, Address) {
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(env);
+
+        let depositor = Address::generate(env);
+        let token_admin = TokenAdmin::new(env, &token1_address);
+        mint_tokens(&token_admin, &depositor, &10000);
+        contract.add_liquidity(&token1_address, &10000, &depositor);
+
+        contract.set_yield_rate(&token1_address, &1000);
+        contract.fund_reward_reserve(&token1_address, &1500);
+
+        (contract, token1_address, depositor)
+    }
+
+    #[test]
+    fn test_claim_interest() {
+        let env = Env::default();
+        let (contract, token1_address, depositor) = setup_yielding_pool(&env);
+
+        env.ledger().with_mut(|li| li.timestamp += YEAR);
+        assert_eq!(
+            contract.get_accrued_interest(&token1_address, &depositor),
+            1000
+        );
+
+        assert_eq!(contract.claim_interest(&token1_address, &depositor), 1000);
+        assert_eq!(check_balance(&env, &token1_address, &depositor), 1000);
+        assert_eq!(
+            contract.get_accrued_interest(&token1_address, &depositor),
+            0
+        );
+
+        let totals = contract.get_pool_totals(&token1_address);
+        assert_eq!(totals.reward_reserve, 500);
+        assert_eq!(totals.interest_paid, 1000);
+        assert_eq!(contract.reconcile(&token1_address).unattributed, 0);
+    }
+
+    #[test]
+    fn test_compound_interest() {
+        let env = Env::default();
+        let (contract, token1_address, depositor) = setup_yielding_pool(&env);
+
+        env.ledger().with_mut(|li| li.timestamp += YEAR);
+        contract.compound_interest(&token1_address, &depositor);
+
+        assert_eq!(
+            contract.get_depositor_balance_amount(&token1_address, &depositor),
+            11000
+        );
+
+        // Interest now accrues on the compounded balance
+        env.ledger().with_mut(|li| li.timestamp += YEAR / 2);
+        assert_eq!(
+            contract.get_accrued_interest(&token1_address, &depositor),
+            550
+        );
+    }
+
+    #[test]
+    fn test_rate_change_settles_accrued_interest() {
+        let env = Env::default();
+        let (contract, token1_address, depositor) = setup_yielding_pool(&env);
+
+        env.ledger().with_mut(|li| li.timestamp += YEAR / 2);
+        contract.set_yield_rate(&token1_address, &0);
+        env.ledger().with_mut(|li| li.timestamp += YEAR / 2);
+
+        // Only the half year at 10% earned interest
+        assert_eq!(
+            contract.get_accrued_interest(&token1_address, &depositor),
+            500
+        );
+    }
+
+    #[test]
+    fn test_interest_index_across_rate_changes() {
+        let env = Env::default();
+        let (contract, token1_address, depositor) = setup_yielding_pool(&env);
+
+        env.ledger().with_mut(|li| li.timestamp += YEAR / 2);
+        contract.set_yield_rate(&token1_address, &2000);
+
+        // A depositor joining after the change only earns the new rate
+        let late_depositor = Address::generate(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &late_depositor, &10000);
+        contract.add_liquidity(&token1_address, &10000, &late_depositor);
+
+        env.ledger().with_mut(|li| li.timestamp += YEAR / 2);
+        assert_eq!(
+            contract.get_accrued_interest(&token1_address, &depositor),
+            1500
+        );
+        assert_eq!(
+            contract.get_accrued_interest(&token1_address, &late_depositor),
+            1000
+        );
+    }
+
+    #[test]
+    fn test_migration_carries_interest_and_reserve() {
+        let env = Env::default();
+        let (contract, token1_address, depositor) = setup_yielding_pool(&env);
+
+        // A second depositor leaves with interest still unclaimed
+        let leaver = Address::generate(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &leaver, &10000);
+        contract.add_liquidity(&token1_address, &10000, &leaver);
+        env.ledger().with_mut(|li| li.timestamp += YEAR / 2);
+        contract.withdraw_deposited_tokens(&token1_address, &10000, &leaver);
+        assert_eq!(contract.get_depositor_count(&token1_address), 2);
+
+        let new_pool_id = env.register(
+            LiquidityPool,
+            (
+                Address::generate(&env),
+                Address::generate(&env),
+                contract.get_assets(),
+                5000u32,
+                contract.get_manager(),
+            ),
+        );
+        let new_pool = LiquidityPoolClient::new(&env, &new_pool_id);
+
+        // Unpaid interest travels with each depositor
+        let records = contract.migrate_depositors(&token1_address, &10);
+        assert!(records.iter().all(|record| record.accrued_interest == 500));
+        new_pool.import_depositors(&token1_address, &records);
+
+        // And the reserve that pays it travels with the tokens
+        let (moved, reward_reserve) = contract.migrate_liquidity(&token1_address, &new_pool_id);
+        assert_eq!(reward_reserve, 1500);
+        new_pool.receive_transfer(&token1_address, &moved, &reward_reserve);
+        assert_eq!(contract.get_pool_totals(&token1_address).reward_reserve, 0);
+        assert_eq!(
+            new_pool.get_pool_totals(&token1_address).reward_reserve,
+            1500
+        );
+
+        assert_eq!(new_pool.claim_interest(&token1_address, &leaver), 500);
+        assert_eq!(new_pool.get_depositor_count(&token1_address), 1);
+        assert_eq!(
+            new_pool.get_accrued_interest(&token1_address, &depositor),
+            500
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #27)")]
+    fn test_claim_beyond_reserve() {
+        let env = Env::default();
+        let (contract, token1_address, depositor) = setup_yielding_pool(&env);
+
+        // Two years of interest exceeds the reserve (should fail with RewardReserveExhausted error)
+        env.ledger().with_mut(|li| li.timestamp += 2 * YEAR);
+        contract.claim_interest(&token1_address, &depositor);
+    }
+}
+
+mod test_checkpoints {
+    use super::*;
+    use soroban_sdk::testutils::Ledger;
+
+    #[test]
+    fn test_balance_at_checkpoint() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        let depositor = Address::generate(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &depositor, &10000);
+
+        contract.add_liquidity(&token1_address, &3000, &depositor);
+        let first = contract.create_checkpoint();
+
+        // Two changes after the first checkpoint, then a second checkpoint
+        contract.add_liquidity(&token1_address, &2000, &depositor);
+        contract.withdraw_deposited_tokens(&token1_address, &1000, &depositor);
+        let second = contract.create_checkpoint();
+
+        // No change after the second checkpoint yet
+        assert_eq!(
+            contract.get_balance_at(&token1_address, &depositor, &first),
+            3000
+        );
+        assert_eq!(
+            contract.get_balance_at(&token1_address, &depositor, &second),
+            4000
+        );
+
+        contract.withdraw_deposited_tokens(&token1_address, &4000, &depositor);
+        assert_eq!(
+            contract.get_balance_at(&token1_address, &depositor, &second),
+            4000
+        );
+
+        // Per-asset totals are captured with each checkpoint
+        let checkpoint = contract.get_checkpoint(&first);
+        let snapshot = checkpoint.assets.get_unchecked(0);
+        assert_eq!(snapshot.asset, token1_address);
+        assert_eq!(snapshot.total_deposits, 3000);
+        assert_eq!(snapshot.actual_balance, 3000);
+    }
+
+    #[test]
+    fn test_balance_at_time() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        let depositor = Address::generate(&env);
+        let token_admin = TokenAdmin::new(&env, &token1_address);
+        mint_tokens(&token_admin, &depositor, &10000);
+
+        env.ledger().with_mut(|li| li.timestamp = 1000);
+        contract.add_liquidity(&token1_address, &3000, &depositor);
+        contract.create_checkpoint();
+
+        env.ledger().with_mut(|li| li.timestamp = 2000);
+        contract.add_liquidity(&token1_address, &3000, &depositor);
+        contract.create_checkpoint();
+
+        // A statement date between checkpoints uses the earlier one
+        assert_eq!(
+            contract.get_balance_at_time(&token1_address, &depositor, &1500),
+            3000
+        );
+        assert_eq!(
+            contract.get_balance_at_time(&token1_address, &depositor, &2500),
+            6000
+        );
+        assert_eq!(contract.get_checkpoint_at(&1999).id, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Error(Contract, #28)")]
+    fn test_balance_before_first_checkpoint() {
+        let env = Env::default();
+        let (contract, _, _, token1_address, _) = test_setup::setup_contract(&env);
+
+        env.ledger().with_mut(|li| li.timestamp = 1000);
+        contract.create_checkpoint();
+
+        // No checkpoint exists this early (should fail with CheckpointNotFound error)
+        contract.get_balance_at_time(&token1_address, &Address::generate(&env), &500);
+    }
+}
@@ -1,4 +1,4 @@
-use soroban_sdk::{contracterror, contracttype, Address, String};
+use soroban_sdk::{contracterror, contracttype, Address, String, Vec};
 
 // Data Structures
 #[contracttype]
@@ -16,6 +16,15 @@ pub struct Thresholds {
     pub alert_threshold: i128,
 }
 
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Role {
+    RiskManager = 1, // Sets thresholds and triggers alerts
+    Treasurer = 2,   // Performs admin withdrawals
+    FeeManager = 3,  // Manages the fee collector
+    Guardian = 4,    // Cancels queued admin withdrawals
+}
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum AlertType {
@@ -30,6 +39,114 @@ pub struct DepositorBalance {
     pub timestamp: u64,
 }
 
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DepositorRecord {
+    pub depositor: Address,
+    pub balance: DepositorBalance,
+    pub accrued_interest: i128, // Interest settled but not yet paid
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InterestIndex {
+    pub value: i128, // Sum of rate in basis points times seconds elapsed, over all rates so far
+    pub timestamp: u64, // When the value was last brought up to date
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConsumerAllowance {
+    pub spending_cap: i128,    // Maximum amount drawable per period
+    pub period_length: u64,    // Period length in seconds
+    pub period_start: u64,     // Start of the current period
+    pub spent_in_period: i128, // Amount drawn in the current period
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WithdrawalPolicy {
+    pub timelock_threshold: i128, // Admin withdrawals of this size or more must be queued
+    pub timelock: u64,            // Delay in seconds before a queued withdrawal can execute
+    pub period_cap: i128,         // Maximum amount withdrawable by the admin per period
+    pub period_length: u64,       // Period length in seconds
+    pub period_start: u64,        // Start of the current period
+    pub withdrawn_in_period: i128, // Amount withdrawn in the current period
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PoolTotals {
+    pub total_deposits: i128,     // Sum of depositor balances
+    pub total_fees: i128,         // Fees collected on withdrawals
+    pub admin_withdrawals: i128,  // Gross amount taken by admin withdrawals
+    pub drawn_for_payments: i128, // Amount paid to payees on behalf of consumers
+    pub tracked_balance: i128,    // Token balance accounted for by the pool's own flows
+    pub swept_donations: i128,    // Unattributed surplus swept by the admin
+    pub reward_reserve: i128,     // Funds set aside by the admin to pay interest
+    pub interest_paid: i128,      // Interest claimed or compounded by depositors
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReconciliationReport {
+    pub asset: Address,
+    pub actual_balance: i128,    // Token balance held by the pool
+    pub tracked_balance: i128,   // Token balance accounted for by the pool's own flows
+    pub total_deposits: i128,    // Sum of depositor balances
+    pub unattributed: i128,      // Actual minus tracked; positive for donations
+    pub deposit_shortfall: i128, // Amount by which depositor balances exceed the actual balance
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AssetSnapshot {
+    pub asset: Address,
+    pub total_deposits: i128,  // Sum of depositor balances
+    pub tracked_balance: i128, // Token balance accounted for by the pool's own flows
+    pub actual_balance: i128,  // Token balance held by the pool
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Checkpoint {
+    pub id: u64,
+    pub timestamp: u64,
+    pub assets: Vec<AssetSnapshot>, // Per-asset totals when the checkpoint was taken
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BalanceSnapshot {
+    pub checkpoint_id: u64, // Checkpoint the amount was held at
+    pub amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QueuedWithdrawal {
+    pub id: u64,
+    pub asset: Address,
+    pub amount: i128,
+    pub execute_after: u64, // Earliest time the withdrawal can execute
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PolicyChange {
+    ConsumerAllowance(Address, i128, u64), // Consumer, spending cap and period length
+    WithdrawalPolicy(i128, u64, i128, u64), // Timelock threshold, timelock, period cap and period length
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QueuedPolicyChange {
+    pub id: u64,
+    pub asset: Address,
+    pub change: PolicyChange,
+    pub execute_after: u64, // Earliest time the change can execute
+}
+
 #[contracterror]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Error {
@@ -45,17 +162,64 @@ pub enum Error {
     InvalidAsset = 10,
     NoDepositorBalance = 11,
     DeploymentFailed = 12,
+    ConsumerNotAllowed = 13,
+    SpendingCapExceeded = 14,
+    InvalidAmount = 15,
+    AssetAlreadyExists = 16,
+    AssetDelisted = 17,
+    AssetNotEmpty = 18,
+    RebalanceLimitNotSet = 19,
+    PoolPaused = 20,
+    PoolNotActive = 21,
+    RoleConflict = 22,
+    NoPendingAdmin = 23,
+    WithdrawalTimelocked = 24,
+    OutflowCapExceeded = 25,
+    WithdrawalNotFound = 26,
+    RewardReserveExhausted = 27,
+    CheckpointNotFound = 28,
+    InsufficientSurplus = 29,
+    DepositorIndexIncomplete = 30,
+    DepositorsRemaining = 31,
+    PolicyChangeTimelocked = 32,
+    PolicyChangeNotFound = 33,
 }
 
 // Helper enum for storage keys - Pool Contract
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum DataKey {
-    Admin,                              // Admin address
-    FeeCollector,                       // Fee collector address
-    Assets,                             // List of all supported assets
-    AllocationPercentage,               // Allocation percentage for this pool
-    Thresholds(Address),                // Thresholds for a specific asset
-    FeeBalance(Address),                // Balance of collected fees per asset
-    DepositorBalance(Address, Address), // Balance of a depositor (token, depositor)
+    Admin,                               // Admin address
+    FeeCollector,                        // Fee collector address
+    Assets,                              // List of all supported assets
+    AllocationPercentage,                // Allocation percentage for this pool
+    Thresholds(Address),                 // Thresholds for a specific asset
+    FeeBalance(Address),                 // Balance of collected fees per asset
+    DepositorBalance(Address, Address),  // Balance of a depositor (token, depositor)
+    ConsumerAllowance(Address, Address), // Drawdown allowance of a consumer contract (consumer, token)
+    Delisted(Address),                   // Asset accepts withdrawals only
+    Manager,                             // Factory contract allowed to rebalance this pool
+    Paused,                              // Whether deposits, withdrawals and draws are paused
+    DepositorCount(Address),             // Number of depositors in a token's index
+    DepositorAt(Address, u32),           // Depositor at a position in a token's index
+    DepositorSlot(Address, Address), // Position of a depositor in a token's index (token, depositor)
+    DepositorsIndexed(Address),      // Every depositor of a token is in its index
+    Role(Role),                      // Account holding a role
+    PendingAdmin,                    // Admin proposed but not yet accepted
+    WithdrawalPolicy(Address),       // Admin withdrawal timelock and cap for a token
+    QueuedWithdrawal(u64),           // Admin withdrawal waiting out its timelock
+    WithdrawalQueue,                 // Ids of queued admin withdrawals
+    NextWithdrawalId,                // Id for the next queued admin withdrawal
+    Totals(Address),                 // Tracked accounting totals for a token
+    YieldRate(Address),              // Interest APR in basis points for a token
+    AccruedInterest(Address, Address), // Interest settled but not yet paid (token, depositor)
+    CheckpointCount,                 // Id of the latest checkpoint
+    Checkpoint(u64),                 // Checkpoint with per-asset totals
+    BalanceHistory(Address, Address), // Depositor balances at past checkpoints (token, depositor)
+    Consumers(Address),              // Consumers holding a drawdown allowance for a token
+    QueuedPolicyChange(u64),         // Allowance or policy loosening waiting out its timelock
+    PolicyChangeQueue,               // Ids of queued policy changes
+    NextPolicyChangeId,              // Id for the next queued policy change
+    InterestIndex(Address),          // Interest accrued per unit of deposit for a token
+    InterestSnapshot(Address, Address), // Interest index when a depositor last settled (token, depositor)
 }
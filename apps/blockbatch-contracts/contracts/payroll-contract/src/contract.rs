@@ -1,25 +1,51 @@
 use crate::storage::{
-    get_contract, store_contract, increment_payment_id, get_total_employees, 
-    set_total_employees, get_payment_history, store_payment_history, initialize_storage,
-    employee_exists, store_employee, deactivate_employee, get_employee
+    get_contract, store_contract, get_total_employees, set_total_employees,
+    add_payment, get_payment, get_payment_count, get_payment_id_at, get_employee_payment_count,
+    get_employee_payment_id_at, initialize_storage, is_payment_processed,
+    mark_payment_processed, employee_exists, store_employee, deactivate_employee, get_employee,
+    get_employees, get_legacy_contract, get_legacy_employee, get_legacy_payment_history,
+    remove_legacy_storage, get_storage_version, set_storage_version, STORAGE_VERSION,
+    get_tax_liability, store_tax_liability, accrue_tax_withholding, get_tax_remittances,
+    add_tax_remittance, add_tax_jurisdiction, get_tax_jurisdictions, get_tax_levies,
+    store_tax_levies, get_year_to_date, store_year_to_date, get_run_proposal,
+    store_run_proposal, remove_run_proposal, increment_proposal_id, get_failed_payments,
+    store_failed_payments, add_employee_change, get_employee_change_count, get_employee_change_at
 };
 use crate::utils::{
-    calculate_gross_payment, calculate_tax_amount, calculate_net_amount, 
-    calculate_next_payment_date, generate_payment_id, generate_transaction_hash
+    calculate_prorated_gross_payment, calculate_tax_amount, calculate_progressive_tax,
+    calculate_net_amount, calculate_benefit_line_items, sum_line_items, timestamp_to_date,
+    calculate_payment_outflow, is_provider_payment, calculate_next_payment_date,
+    calculate_previous_payment_date, calculate_vacation_payout, generate_payment_id,
+    generate_transaction_hash, hash_run_payments, matches_payment_query, anchor_pay_day_rule,
+    calculate_first_payment_date_after
 };
 use crate::validation::{
     validate_contract_creation, validate_authorization, validate_contract_active,
-    validate_employer_only, validate_employee_data, validate_payment_due,
-    validate_sufficient_balance, validate_tax_rate
+    validate_employer_only, validate_employee_data, validate_employee_active, is_employee_due,
+    validate_sufficient_balance, validate_tax_rate, validate_tax_levies,
+    validate_approval_threshold, validate_payment_amounts, validate_pay_day_rule
 };
 use crate::types::{
-    Asset, ContractStatus, Employee, Payment, PaymentStatus,
-    PayrollContract, PayrollError, TimeSchedule,
+    Asset, Benefit, BenefitCalculation, BenefitTreatment, BenefitType, ContractStatus, Employee,
+    EmployeeChange, EmployeeChangeKind, EmployeeChangePage, EmployeeStatement, EmployeeUpdate,
+    FundingMode, LegacyEmployee, LegacyPayment, PayDayRule, Payment, PaymentLineItem, PaymentPage,
+    PaymentQuery, PaymentStatus, PayrollContract, PayrollError, PayrollPreview,
+    PayrollRunProposal, SalaryChange, TaxLevy, TaxLiability, TaxRemittance, TimeSchedule,
+    YearToDate,
 };
 use soroban_sdk::{
-    contract, contractimpl, symbol_short, token::Client, Address, Env, String, Vec,
+    contract, contractimpl, symbol_short, token::Client, Address, BytesN, Env, String, Vec,
 };
 
+/// How long a payroll run proposal stays open by default (3 days)
+const DEFAULT_PROPOSAL_TTL: u64 = 3 * 24 * 60 * 60;
+
+/// Largest page of payments a history query returns
+const MAX_PAGE_SIZE: u32 = 50;
+
+/// Most payments a history query reads before returning a page
+const MAX_PAGE_SCAN: u32 = 200;
+
 #[contract]
 pub struct PayrollContractImpl;
 
@@ -43,15 +69,25 @@ impl PayrollContractImpl {
         // Validate contract creation parameters
         validate_contract_creation(&env, minimum_balance, &signers, &payment_schedule)?;
 
+        // Keep a same-day schedule on the day of its first payment
+        let mut payment_schedule = payment_schedule;
+        payment_schedule.pay_day_rule = anchor_pay_day_rule(
+            &payment_schedule.pay_day_rule,
+            payment_schedule.next_payment_date,
+        );
+
         // Create the payroll contract
         let payroll_contract = PayrollContract {
             employer_account: employer_account.clone(),
             payment_schedule,
-            employees: Vec::new(&env),
+            employee_ids: Vec::new(&env),
             tax_rates: soroban_sdk::Map::new(&env),
             payment_token,
             minimum_balance,
             signers: signers.clone(),
+            approval_threshold: 1,
+            proposal_ttl: DEFAULT_PROPOSAL_TTL,
+            funding_mode: FundingMode::AllOrNothing,
             status: ContractStatus::Active,
             contract_id: contract_id.clone(),
             created_at: env.ledger().timestamp(),
@@ -78,6 +114,7 @@ impl PayrollContractImpl {
     ) -> Result<(), PayrollError> {
         caller.require_auth();
 
+        let mut employee = employee;
         let mut contract = get_contract(&env)?;
 
         // Validate authorization and contract state
@@ -89,20 +126,49 @@ impl PayrollContractImpl {
             return Err(PayrollError::EmployeeAlreadyExists);
         }
 
+        // The pay-day rule has to be valid before any pay date is worked out from it
+        validate_pay_day_rule(&employee.pay_day_rule)?;
+
+        // Align the first pay date with the payroll schedule if none was given
+        if employee.next_payment_date == 0 {
+            employee.pay_day_rule = anchor_pay_day_rule(
+                &employee.pay_day_rule,
+                contract.payment_schedule.next_payment_date,
+            );
+            employee.next_payment_date = calculate_first_payment_date_after(
+                &employee.pay_frequency,
+                &employee.pay_day_rule,
+                contract.payment_schedule.next_payment_date,
+                employee.hire_date,
+            )
+            .ok_or(PayrollError::InvalidPaymentSchedule)?;
+        } else {
+            employee.pay_day_rule =
+                anchor_pay_day_rule(&employee.pay_day_rule, employee.next_payment_date);
+        }
+
         // Validate employee data
         validate_employee_data(&employee)?;
 
         // Store employee
         store_employee(&env, &employee);
 
-        // Add employee to contract's employee list
-        contract.employees.push_back(employee.clone());
+        // Add employee to contract's employee index
+        contract.employee_ids.push_back(employee.employee_id.clone());
         store_contract(&env, &contract);
 
         // Update total employees counter
         let total_employees = get_total_employees(&env);
         set_total_employees(&env, total_employees + 1);
 
+        Self::log_employee_change(
+            &env,
+            &employee.employee_id,
+            EmployeeChangeKind::Hired,
+            &caller,
+            employee.hire_date,
+        );
+
         // Emit event
         env.events().publish(
             (symbol_short!("emp_add"), employee.employee_id.clone()),
@@ -134,14 +200,10 @@ impl PayrollContractImpl {
         // Deactivate employee
         deactivate_employee(&env, &employee_id)?;
 
-        // Remove from contract's employee list
-        let mut new_employees = Vec::new(&env);
-        for emp in contract.employees.iter() {
-            if emp.employee_id != employee_id {
-                new_employees.push_back(emp);
-            }
+        // Remove from contract's employee index
+        if let Some(index) = contract.employee_ids.first_index_of(&employee_id) {
+            contract.employee_ids.remove(index);
         }
-        contract.employees = new_employees;
         store_contract(&env, &contract);
 
         // Update total employees counter
@@ -150,6 +212,14 @@ impl PayrollContractImpl {
             set_total_employees(&env, total_employees - 1);
         }
 
+        Self::log_employee_change(
+            &env,
+            &employee_id,
+            EmployeeChangeKind::Removed,
+            &caller,
+            env.ledger().timestamp(),
+        );
+
         // Emit event
         env.events()
             .publish((symbol_short!("emp_rem"), employee_id), 0);
@@ -157,6 +227,241 @@ impl PayrollContractImpl {
         Ok(())
     }
 
+    /// Update an employee's salary, tax jurisdiction, benefits or payout account.
+    /// A new salary accrues from `salary_effective_date`, splitting the pay period
+    /// it falls in, and replaces salary changes scheduled on or after that date.
+    /// Periods that have already been paid are not recalculated.
+    pub fn update_employee(
+        env: Env,
+        caller: Address,
+        employee_id: String,
+        update: EmployeeUpdate,
+    ) -> Result<(), PayrollError> {
+        caller.require_auth();
+
+        let contract = get_contract(&env)?;
+
+        // Validate authorization and contract state
+        validate_authorization(&contract, &caller)?;
+        validate_contract_active(&contract)?;
+
+        let mut employee = get_employee(&env, &employee_id)?;
+        validate_employee_active(&employee)?;
+
+        let current_time = env.ledger().timestamp();
+        let mut changes = Vec::new(&env);
+
+        if let Some(base_salary) = update.base_salary {
+            if base_salary <= 0 {
+                return Err(PayrollError::InvalidAmount);
+            }
+            let effective_date = update.salary_effective_date;
+
+            // Drop the scheduled changes this one replaces
+            let mut salary_changes = Vec::new(&env);
+            for salary_change in employee.salary_changes.iter() {
+                if salary_change.effective_date < effective_date {
+                    salary_changes.push_back(salary_change);
+                }
+            }
+            employee.salary_changes = salary_changes;
+            let previous_salary = Self::salary_on(&employee, effective_date);
+
+            // A change dated at the start of the current period or earlier applies
+            // to the whole period
+            let period_start = calculate_previous_payment_date(
+                &employee.pay_frequency,
+                &employee.pay_day_rule,
+                employee.next_payment_date,
+            );
+            if effective_date <= period_start {
+                employee.base_salary = base_salary;
+            } else {
+                employee.salary_changes.push_back(SalaryChange {
+                    base_salary,
+                    effective_date,
+                });
+            }
+            changes.push_back((
+                EmployeeChangeKind::SalaryChanged(previous_salary, base_salary),
+                effective_date,
+            ));
+        }
+        if let Some(tax_jurisdiction) = update.tax_jurisdiction {
+            changes.push_back((
+                EmployeeChangeKind::JurisdictionChanged(
+                    employee.tax_jurisdiction.clone(),
+                    tax_jurisdiction.clone(),
+                ),
+                current_time,
+            ));
+            employee.tax_jurisdiction = tax_jurisdiction;
+        }
+        if let Some(benefits) = update.benefits {
+            changes.push_back((
+                EmployeeChangeKind::BenefitsChanged(employee.benefits.clone(), benefits.clone()),
+                current_time,
+            ));
+            employee.benefits = benefits;
+        }
+        if let Some(account_id) = update.account_id {
+            changes.push_back((
+                EmployeeChangeKind::AccountChanged(employee.account_id.clone(), account_id.clone()),
+                current_time,
+            ));
+            employee.account_id = account_id;
+        }
+
+        // Validate employee data
+        validate_employee_data(&employee)?;
+        store_employee(&env, &employee);
+
+        for (kind, effective_date) in changes.iter() {
+            Self::log_employee_change(&env, &employee_id, kind, &caller, effective_date);
+        }
+
+        // Emit event
+        env.events()
+            .publish((symbol_short!("emp_upd"), employee_id), changes.len());
+
+        Ok(())
+    }
+
+    /// Suspend an employee. The record is kept and their pay periods keep
+    /// advancing, but periods that end while they are suspended aren't paid.
+    pub fn suspend_employee(
+        env: Env,
+        caller: Address,
+        employee_id: String,
+    ) -> Result<(), PayrollError> {
+        caller.require_auth();
+
+        let contract = get_contract(&env)?;
+
+        // Validate authorization and contract state
+        validate_authorization(&contract, &caller)?;
+        validate_contract_active(&contract)?;
+
+        let mut employee = get_employee(&env, &employee_id)?;
+        validate_employee_active(&employee)?;
+        if employee.is_suspended {
+            return Err(PayrollError::InvalidStatus);
+        }
+
+        employee.is_suspended = true;
+        store_employee(&env, &employee);
+
+        Self::log_employee_change(
+            &env,
+            &employee_id,
+            EmployeeChangeKind::Suspended,
+            &caller,
+            env.ledger().timestamp(),
+        );
+
+        // Emit event
+        env.events()
+            .publish((symbol_short!("emp_susp"), employee_id), 0);
+
+        Ok(())
+    }
+
+    /// Resume paying a suspended employee from their current pay period
+    pub fn resume_employee(
+        env: Env,
+        caller: Address,
+        employee_id: String,
+    ) -> Result<(), PayrollError> {
+        caller.require_auth();
+
+        let contract = get_contract(&env)?;
+
+        // Validate authorization and contract state
+        validate_authorization(&contract, &caller)?;
+        validate_contract_active(&contract)?;
+
+        let mut employee = get_employee(&env, &employee_id)?;
+        validate_employee_active(&employee)?;
+        if !employee.is_suspended {
+            return Err(PayrollError::InvalidStatus);
+        }
+
+        employee.is_suspended = false;
+        store_employee(&env, &employee);
+
+        Self::log_employee_change(
+            &env,
+            &employee_id,
+            EmployeeChangeKind::Resumed,
+            &caller,
+            env.ledger().timestamp(),
+        );
+
+        // Emit event
+        env.events()
+            .publish((symbol_short!("emp_resm"), employee_id), 0);
+
+        Ok(())
+    }
+
+    /// Terminate an employee, leaving their final paycheck to the next approved run: base
+    /// pay for the current pay period up to the termination date, plus a payout of unused
+    /// vacation days. Returns the final payment as it stands now, or `None` if nothing is owed.
+    pub fn terminate_employee(
+        env: Env,
+        caller: Address,
+        employee_id: String,
+        termination_date: u64,
+    ) -> Result<Option<Payment>, PayrollError> {
+        caller.require_auth();
+
+        let contract = get_contract(&env)?;
+
+        // Validate authorization and contract state
+        validate_authorization(&contract, &caller)?;
+        validate_contract_active(&contract)?;
+
+        let mut employee = get_employee(&env, &employee_id)?;
+        validate_employee_active(&employee)?;
+
+        // The final paycheck settles the current pay period, so earlier periods
+        // must already be paid and the termination can't be in the future
+        let period_start = calculate_previous_payment_date(
+            &employee.pay_frequency,
+            &employee.pay_day_rule,
+            employee.next_payment_date,
+        );
+        let current_time = env.ledger().timestamp();
+        if termination_date < period_start.max(employee.hire_date)
+            || termination_date > current_time.min(employee.next_payment_date)
+        {
+            return Err(PayrollError::InvalidPaymentSchedule);
+        }
+        employee.termination_date = Some(termination_date);
+
+        // The final paycheck is paid like any other, by a run the signers approve; the
+        // run deactivates the employee once it is paid
+        let final_payment = Self::calculate_employee_payment(&env, &contract, &employee);
+        store_employee(&env, &employee);
+
+        let final_payment_id = final_payment.as_ref().map(|payment| payment.payment_id.clone());
+        Self::log_employee_change(
+            &env,
+            &employee_id,
+            EmployeeChangeKind::Terminated(final_payment_id),
+            &caller,
+            termination_date,
+        );
+
+        // Emit event
+        env.events().publish(
+            (symbol_short!("emp_term"), employee_id),
+            final_payment.as_ref().map_or(0, |payment| payment.net_amount),
+        );
+
+        Ok(final_payment)
+    }
+
     /// Schedule the next payment based on the payment frequency
     pub fn schedule_payment(env: Env, caller: Address) -> Result<u64, PayrollError> {
         caller.require_auth();
@@ -169,6 +474,7 @@ impl PayrollContractImpl {
 
         let next_payment_date = calculate_next_payment_date(
             &contract.payment_schedule.frequency,
+            &contract.payment_schedule.pay_day_rule,
             contract.payment_schedule.next_payment_date,
         );
 
@@ -179,207 +485,1351 @@ impl PayrollContractImpl {
         // Emit event
         env.events().publish(
             (symbol_short!("scheduled"), next_payment_date),
-            contract.employees.len(),
+            contract.employee_ids.len(),
         );
 
         Ok(next_payment_date)
     }
 
-    /// Execute payments for all active employees
-    pub fn execute_payment(env: Env, caller: Address) -> Result<Vec<String>, PayrollError> {
+    /// Set how many signer approvals a payroll run needs and how long a proposal stays open (employer only)
+    pub fn set_approval_policy(
+        env: Env,
+        caller: Address,
+        approval_threshold: u32,
+        proposal_ttl: u64,
+    ) -> Result<(), PayrollError> {
         caller.require_auth();
 
         let mut contract = get_contract(&env)?;
 
-        // Validate authorization and contract state
-        validate_authorization(&contract, &caller)?;
-        validate_contract_active(&contract)?;
+        // Validate employer-only access and the new policy
+        validate_employer_only(&contract, &caller)?;
+        validate_approval_threshold(&contract, approval_threshold)?;
+        if proposal_ttl == 0 {
+            return Err(PayrollError::InvalidPaymentSchedule);
+        }
 
-        let current_time = env.ledger().timestamp();
+        contract.approval_threshold = approval_threshold;
+        contract.proposal_ttl = proposal_ttl;
+        store_contract(&env, &contract);
 
-        // Check if payment is due
-        validate_payment_due(&env, contract.payment_schedule.next_payment_date)?;
+        // Emit event
+        env.events()
+            .publish((symbol_short!("approvals"), caller), approval_threshold);
 
-        let mut payment_ids = Vec::new(&env);
-        let mut global_payment_history = get_payment_history(&env);
+        Ok(())
+    }
 
-        // Process payments for each active employee
-        for employee in contract.employees.iter() {
-            if !employee.is_active {
-                continue;
-            }
+    /// Propose a payroll run for the employees currently due. The proposer's
+    /// approval is counted, and the contract waits for the other signers.
+    pub fn propose_payroll_run(env: Env, caller: Address) -> Result<u64, PayrollError> {
+        caller.require_auth();
 
-            let payment_result = Self::process_employee_payment(&env, &employee, &contract);
-            match payment_result {
-                Ok(payment) => {
-                    payment_ids.push_back(payment.payment_id.clone());
-                    global_payment_history.push_back(payment.clone());
+        let mut contract = get_contract(&env)?;
+        let current_time = env.ledger().timestamp();
 
-                    // Update employee's payment history
-                    let mut updated_employee = employee.clone();
-                    updated_employee.payment_history.push_back(payment);
-                    store_employee(&env, &updated_employee);
-                }
-                Err(_) => {
-                    // Log failed payment but continue with others
-                    env.events().publish(
-                        (symbol_short!("pay_fail"), employee.employee_id.clone()),
-                        0,
-                    );
-                }
+        // Validate authorization and contract state; an expired proposal can be replaced
+        validate_authorization(&contract, &caller)?;
+        if let Some(proposal) = get_run_proposal(&env) {
+            if current_time <= proposal.expires_at {
+                return Err(PayrollError::InvalidStatus);
             }
         }
+        if !matches!(contract.status, ContractStatus::Active | ContractStatus::PendingApproval) {
+            return Err(PayrollError::ContractNotActive);
+        }
 
-        // Update global payment history
-        store_payment_history(&env, &global_payment_history);
+        // Work out what the run would pay
+        let employees = get_employees(&env, &contract.employee_ids)?;
+        let (total_amount, employee_count, payments_hash) =
+            Self::calculate_run_total(&env, &contract, &employees, current_time)?;
+
+        let proposal = PayrollRunProposal {
+            proposal_id: increment_proposal_id(&env),
+            proposer: caller.clone(),
+            run_date: current_time,
+            total_amount,
+            employee_count,
+            payments_hash,
+            approvals: Vec::from_array(&env, [caller.clone()]),
+            expires_at: current_time + contract.proposal_ttl,
+        };
+        store_run_proposal(&env, &proposal);
 
-        // Schedule next payment
-        contract.payment_schedule.next_payment_date = calculate_next_payment_date(
-            &contract.payment_schedule.frequency,
-            current_time,
-        );
+        contract.status = ContractStatus::PendingApproval;
         store_contract(&env, &contract);
 
         // Emit event
         env.events().publish(
-            (symbol_short!("executed"), current_time),
-            payment_ids.len(),
+            (symbol_short!("proposed"), proposal.proposal_id, caller),
+            total_amount,
         );
 
-        Ok(payment_ids)
+        Ok(proposal.proposal_id)
     }
 
-    /// Get the current status of the payroll contract
-    pub fn get_payroll_status(env: Env) -> Result<ContractStatus, PayrollError> {
-        let contract = get_contract(&env)?;
-        Ok(contract.status)
-    }
+    /// Approve a proposed payroll run, returning the number of approvals so far
+    pub fn approve_payroll_run(
+        env: Env,
+        caller: Address,
+        proposal_id: u64,
+    ) -> Result<u32, PayrollError> {
+        caller.require_auth();
 
-    /// Get all employees in the payroll
-    pub fn get_employees(env: Env) -> Result<Vec<Employee>, PayrollError> {
         let contract = get_contract(&env)?;
-        Ok(contract.employees)
-    }
 
-    /// Get payment history for all employees or a specific employee
-    pub fn get_payment_history(
-        env: Env,
-        employee_id: Option<String>,
-    ) -> Result<Vec<Payment>, PayrollError> {
-        match employee_id {
-            Some(id) => {
-                // Get payment history for specific employee
-                let employee = get_employee(&env, &id)?;
-                Ok(employee.payment_history)
-            }
-            None => {
-                // Get global payment history
-                Ok(get_payment_history(&env))
-            }
+        // Validate authorization
+        validate_authorization(&contract, &caller)?;
+
+        let mut proposal = get_run_proposal(&env)
+            .filter(|proposal| proposal.proposal_id == proposal_id)
+            .ok_or(PayrollError::ProposalNotFound)?;
+        if env.ledger().timestamp() > proposal.expires_at {
+            return Err(PayrollError::ProposalExpired);
+        }
+        if proposal.approvals.contains(&caller) {
+            return Err(PayrollError::AlreadyApproved);
         }
+
+        proposal.approvals.push_back(caller.clone());
+        store_run_proposal(&env, &proposal);
+
+        // Emit event
+        env.events().publish(
+            (symbol_short!("approved"), proposal_id, caller),
+            proposal.approvals.len(),
+        );
+
+        Ok(proposal.approvals.len())
     }
 
-    /// Update contract status (admin only)
-    pub fn update_contract_status(
+    /// Cancel a proposed payroll run and return the contract to active
+    pub fn cancel_payroll_run(
         env: Env,
         caller: Address,
-        new_status: ContractStatus,
+        proposal_id: u64,
     ) -> Result<(), PayrollError> {
         caller.require_auth();
 
         let mut contract = get_contract(&env)?;
 
-        // Validate employer-only access
-        validate_employer_only(&contract, &caller)?;
+        // Validate authorization
+        validate_authorization(&contract, &caller)?;
 
-        contract.status = new_status.clone();
-        store_contract(&env, &contract);
+        get_run_proposal(&env)
+            .filter(|proposal| proposal.proposal_id == proposal_id)
+            .ok_or(PayrollError::ProposalNotFound)?;
+        remove_run_proposal(&env);
+
+        if contract.status == ContractStatus::PendingApproval {
+            contract.status = ContractStatus::Active;
+            store_contract(&env, &contract);
+        }
 
         // Emit event
         env.events()
-            .publish((symbol_short!("status"), caller), new_status as u32);
+            .publish((symbol_short!("cancelled"), proposal_id), caller);
 
         Ok(())
     }
 
-    /// Add or update tax rate for a jurisdiction
-    pub fn set_tax_rate(
+    /// Get the payroll run awaiting approval, if any
+    pub fn get_payroll_run_proposal(env: Env) -> Option<PayrollRunProposal> {
+        get_run_proposal(&env)
+    }
+
+    /// Choose whether a run is rejected or paid in part when the balance falls short (employer only)
+    pub fn set_funding_mode(
         env: Env,
         caller: Address,
-        jurisdiction: String,
-        tax_rate: i128, // Basis points (10000 = 100%)
+        funding_mode: FundingMode,
     ) -> Result<(), PayrollError> {
         caller.require_auth();
 
         let mut contract = get_contract(&env)?;
 
-        // Validate authorization and tax rate
-        validate_authorization(&contract, &caller)?;
-        validate_tax_rate(tax_rate)?;
+        // Validate employer-only access
+        validate_employer_only(&contract, &caller)?;
 
-        contract.tax_rates.set(jurisdiction.clone(), tax_rate);
+        contract.funding_mode = funding_mode.clone();
         store_contract(&env, &contract);
 
         // Emit event
         env.events()
-            .publish((symbol_short!("tax_set"), jurisdiction), tax_rate);
+            .publish((symbol_short!("fund_mode"), caller), funding_mode as u32);
 
         Ok(())
     }
 
-    // Private helper methods
+    /// Deposit payment tokens into the payroll, returning the new balance. Emits a
+    /// low-balance warning if the balance still doesn't cover the next run.
+    pub fn fund_payroll(env: Env, caller: Address, amount: i128) -> Result<i128, PayrollError> {
+        caller.require_auth();
 
-    fn process_employee_payment(
-        env: &Env,
-        employee: &Employee,
-        contract: &PayrollContract,
-    ) -> Result<Payment, PayrollError> {
-        // Calculate payment amounts
-        let gross_amount = calculate_gross_payment(&contract.payment_schedule.frequency, employee.base_salary);
-        let tax_rate = contract
-            .tax_rates
-            .get(employee.tax_jurisdiction.clone())
+        let contract = get_contract(&env)?;
+        if amount <= 0 {
+            return Err(PayrollError::InvalidAmount);
+        }
+
+        let token_client = Client::new(&env, &contract.payment_token.token);
+        token_client.transfer(&caller, &env.current_contract_address(), &amount);
+        let contract_balance = token_client.balance(&env.current_contract_address());
+
+        // Emit event
+        env.events()
+            .publish((symbol_short!("funded"), caller), amount);
+
+        // Warn if the next run still can't be paid in full
+        let next_payment_date = contract.payment_schedule.next_payment_date;
+        let employees = get_employees(&env, &contract.employee_ids)?;
+        let run_total = Self::calculate_run_total(&env, &contract, &employees, next_payment_date)
+            .map(|(total_amount, _, _)| total_amount)
             .unwrap_or(0);
-        let tax_amount = calculate_tax_amount(gross_amount, tax_rate);
-        let net_amount = calculate_net_amount(gross_amount, tax_amount);
+        let required_balance = Self::required_balance(&env, &contract, run_total);
+        if validate_sufficient_balance(contract_balance, required_balance).is_err() {
+            Self::warn_low_balance(&env, contract_balance, required_balance);
+        }
 
-        // Generate payment ID
-        let payment_id_counter = increment_payment_id(env);
-        let payment_id = generate_payment_id(env, payment_id_counter);
+        Ok(contract_balance)
+    }
 
-        // Check contract balance
-        let token_client = Client::new(env, &contract.payment_token.token);
-        let contract_balance = token_client.balance(&env.current_contract_address());
-        
-        validate_sufficient_balance(contract_balance, net_amount)?;
+    /// Retry the failed payments with their original amounts, returning the IDs of
+    /// those paid. Payments that fail again stay queued with the new reason.
+    pub fn retry_failed_payments(env: Env, caller: Address) -> Result<Vec<String>, PayrollError> {
+        caller.require_auth();
 
-        // Execute the payment
-        let _payment_result = token_client.transfer(
-            &env.current_contract_address(),
-            &employee.account_id,
-            &net_amount,
-        );
+        let contract = get_contract(&env)?;
 
-        // Create transaction hash
-        let tx_hash = generate_transaction_hash(env, payment_id_counter);
+        // Validate authorization and contract state
+        validate_authorization(&contract, &caller)?;
+        validate_contract_active(&contract)?;
 
-        let payment = Payment {
-            payment_id: payment_id.clone(),
-            employee_id: employee.employee_id.clone(),
-            gross_amount,
-            tax_amount,
-            net_amount,
-            payment_date: env.ledger().timestamp(),
-            status: PaymentStatus::Completed,
-            transaction_hash: Some(tx_hash),
-        };
+        let mut payment_ids = Vec::new(&env);
+        let mut failed_payments = Vec::new(&env);
 
-        // Emit payment event
-        env.events().publish(
-            (symbol_short!("payment"), employee.employee_id.clone(), payment_id),
-            net_amount,
+        for payment in get_failed_payments(&env).iter() {
+            if is_payment_processed(&env, &payment.payment_id) {
+                continue;
+            }
+
+            let employee = get_employee(&env, &payment.employee_id)?;
+            match Self::process_payment(&env, &contract, &employee, payment.clone()) {
+                Ok(payment) => {
+                    payment_ids.push_back(payment.payment_id.clone());
+                    add_payment(&env, &payment);
+                }
+                Err(error) => {
+                    failed_payments.push_back(Self::failed_payment(&env, payment, error));
+                }
+            }
+        }
+
+        store_failed_payments(&env, &failed_payments);
+
+        // Emit event
+        env.events()
+            .publish((symbol_short!("retried"), caller), payment_ids.len());
+
+        Ok(payment_ids)
+    }
+
+    /// Cancel a failed payment so it is never paid, recording why (employer only)
+    pub fn cancel_failed_payment(
+        env: Env,
+        caller: Address,
+        payment_id: String,
+        reason: String,
+    ) -> Result<(), PayrollError> {
+        caller.require_auth();
+
+        let contract = get_contract(&env)?;
+
+        // Validate employer-only access
+        validate_employer_only(&contract, &caller)?;
+
+        let mut failed_payments = get_failed_payments(&env);
+        let index = failed_payments
+            .iter()
+            .position(|payment| payment.payment_id == payment_id)
+            .ok_or(PayrollError::PaymentNotFound)?;
+        let mut payment = failed_payments.get(index as u32).unwrap();
+        failed_payments.remove(index as u32);
+
+        // The pay period is settled as cancelled and can no longer be paid
+        payment.status = PaymentStatus::Cancelled;
+        payment.reason = Some(reason);
+        mark_payment_processed(&env, &payment.payment_id);
+
+        add_payment(&env, &payment);
+        store_failed_payments(&env, &failed_payments);
+
+        // Emit event
+        env.events()
+            .publish((symbol_short!("pay_cncl"), payment_id), caller);
+
+        Ok(())
+    }
+
+    /// Get the failed payments waiting to be retried or cancelled
+    pub fn get_failed_payments(env: Env) -> Vec<Payment> {
+        get_failed_payments(&env)
+    }
+
+    /// Preview the payroll run for the employees due now without changing any state
+    pub fn preview_payroll(env: Env) -> Result<PayrollPreview, PayrollError> {
+        let contract = get_contract(&env)?;
+        let run_date = env.ledger().timestamp();
+
+        let token_client = Client::new(&env, &contract.payment_token.token);
+        let contract_balance = token_client.balance(&env.current_contract_address());
+
+        let mut preview = PayrollPreview {
+            run_date,
+            payments: Vec::new(&env),
+            total_amount: 0,
+            contract_balance,
+            required_balance: 0,
+            insufficient_funds: Vec::new(&env),
+            invalid_amounts: Vec::new(&env),
+        };
+
+        // Walk the due employees in the order a run pays them, tracking the
+        // balance each payment would leave behind
+        let mut available_balance = Self::available_balance(&env, &contract);
+        for employee in get_employees(&env, &contract.employee_ids)?.iter() {
+            if !is_employee_due(&employee, run_date) {
+                continue;
+            }
+            let payment = match Self::calculate_employee_payment(&env, &contract, &employee) {
+                Some(payment) => payment,
+                None => continue,
+            };
+            if validate_payment_amounts(&payment).is_err() {
+                preview.invalid_amounts.push_back(employee.employee_id.clone());
+                continue;
+            }
+
+            let payment_amount = payment.gross_amount + payment.employer_contributions;
+            if validate_sufficient_balance(available_balance, payment_amount).is_ok() {
+                available_balance -= calculate_payment_outflow(&payment);
+            } else {
+                preview.insufficient_funds.push_back(employee.employee_id.clone());
+            }
+            preview.total_amount += payment_amount;
+            preview.payments.push_back(payment);
+        }
+
+        preview.required_balance = Self::required_balance(&env, &contract, preview.total_amount);
+
+        Ok(preview)
+    }
+
+    /// Execute the approved payroll run, paying the employees that were due when it was proposed
+    pub fn execute_payment(env: Env, caller: Address) -> Result<Vec<String>, PayrollError> {
+        caller.require_auth();
+
+        let mut contract = get_contract(&env)?;
+
+        // Validate authorization and contract state
+        validate_authorization(&contract, &caller)?;
+        if contract.status != ContractStatus::PendingApproval {
+            return Err(PayrollError::ContractNotActive);
+        }
+
+        let current_time = env.ledger().timestamp();
+
+        // The run needs enough approvals before it expires
+        let proposal = get_run_proposal(&env).ok_or(PayrollError::ProposalNotFound)?;
+        if current_time > proposal.expires_at {
+            return Err(PayrollError::ProposalExpired);
+        }
+        if proposal.approvals.len() < contract.approval_threshold {
+            return Err(PayrollError::ProposalNotApproved);
+        }
+
+        // The run must still match what the signers approved
+        let run_date = proposal.run_date;
+        let employees = get_employees(&env, &contract.employee_ids)?;
+        let (total_amount, _, payments_hash) =
+            Self::calculate_run_total(&env, &contract, &employees, run_date)?;
+        if total_amount != proposal.total_amount || payments_hash != proposal.payments_hash {
+            return Err(PayrollError::ProposalOutdated);
+        }
+
+        // Reject the run if any due pay period has already been paid
+        for employee in employees.iter() {
+            if is_employee_due(&employee, run_date) {
+                let payment_id = generate_payment_id(
+                    &env,
+                    &contract.contract_id,
+                    employee.next_payment_date,
+                    &employee.employee_id,
+                );
+                if is_payment_processed(&env, &payment_id) {
+                    return Err(PayrollError::DuplicatePayment);
+                }
+            }
+        }
+
+        // Check the balance covers the whole run before paying anyone
+        let token_client = Client::new(&env, &contract.payment_token.token);
+        let contract_balance = token_client.balance(&env.current_contract_address());
+        let required_balance = Self::required_balance(&env, &contract, total_amount);
+        if validate_sufficient_balance(contract_balance, required_balance).is_err() {
+            if contract.funding_mode == FundingMode::AllOrNothing {
+                return Err(PayrollError::InsufficientBalance);
+            }
+            Self::warn_low_balance(&env, contract_balance, required_balance);
+        }
+
+        let mut payment_ids = Vec::new(&env);
+        let mut failed_payments = get_failed_payments(&env);
+
+        // Process payments for each employee whose pay period has ended
+        for employee in employees.iter() {
+            if !is_employee_due(&employee, run_date) {
+                continue;
+            }
+
+            let mut updated_employee = employee.clone();
+            if let Some(payment) = Self::calculate_employee_payment(&env, &contract, &employee) {
+                match Self::process_payment(&env, &contract, &employee, payment.clone()) {
+                    Ok(payment) => {
+                        payment_ids.push_back(payment.payment_id.clone());
+                        add_payment(&env, &payment);
+                    }
+                    Err(error) => {
+                        // An all-or-nothing run pays everyone or no one
+                        if contract.funding_mode == FundingMode::AllOrNothing {
+                            return Err(error);
+                        }
+
+                        // Queue the period's payment for retry
+                        failed_payments.push_back(Self::failed_payment(&env, payment, error));
+                        env.events().publish(
+                            (symbol_short!("pay_fail"), employee.employee_id.clone()),
+                            0,
+                        );
+                    }
+                }
+
+                // One-off earnings such as bonuses belong to this period's payment
+                updated_employee.benefits = Self::remove_earnings(&env, &employee.benefits);
+            }
+
+            // Move the employee on to their next pay period
+            let period_end = employee.next_payment_date;
+            updated_employee.next_payment_date = calculate_next_payment_date(
+                &employee.pay_frequency,
+                &employee.pay_day_rule,
+                period_end,
+            );
+
+            // Salary changes become the base salary once the period they start in is settled
+            let mut salary_changes = Vec::new(&env);
+            for salary_change in employee.salary_changes.iter() {
+                if salary_change.effective_date <= period_end {
+                    updated_employee.base_salary = salary_change.base_salary;
+                } else {
+                    salary_changes.push_back(salary_change);
+                }
+            }
+            updated_employee.salary_changes = salary_changes;
+
+            // Nothing more is owed once the period containing the termination is settled
+            if let Some(termination_date) = employee.termination_date {
+                if termination_date <= period_end {
+                    updated_employee.is_active = false;
+                }
+            }
+            store_employee(&env, &updated_employee);
+        }
+
+        // Queue the failed payments
+        store_failed_payments(&env, &failed_payments);
+
+        // Schedule the next run for the earliest upcoming employee pay date
+        let mut next_payment_date: Option<u64> = None;
+        for employee in get_employees(&env, &contract.employee_ids)?.iter() {
+            if employee.is_active {
+                next_payment_date = Some(match next_payment_date {
+                    Some(date) => date.min(employee.next_payment_date),
+                    None => employee.next_payment_date,
+                });
+            }
+        }
+        contract.payment_schedule.next_payment_date = next_payment_date.unwrap_or(
+            calculate_next_payment_date(
+                &contract.payment_schedule.frequency,
+                &contract.payment_schedule.pay_day_rule,
+                current_time,
+            ),
+        );
+        contract.status = ContractStatus::Active;
+        store_contract(&env, &contract);
+        remove_run_proposal(&env);
+
+        // Emit event
+        env.events().publish(
+            (symbol_short!("executed"), current_time),
+            payment_ids.len(),
+        );
+
+        Ok(payment_ids)
+    }
+
+    /// Get the current status of the payroll contract
+    pub fn get_payroll_status(env: Env) -> Result<ContractStatus, PayrollError> {
+        let contract = get_contract(&env)?;
+        Ok(contract.status)
+    }
+
+    /// Get all employees in the payroll
+    pub fn get_employees(env: Env) -> Result<Vec<Employee>, PayrollError> {
+        let contract = get_contract(&env)?;
+        get_employees(&env, &contract.employee_ids)
+    }
+
+    /// Get a page of settled payments matching a query, oldest first. Pass 0 as the
+    /// cursor for the first page and the returned `next_cursor` for the following ones.
+    /// A page stops early once `MAX_PAGE_SCAN` payments have been read, so a page
+    /// can hold fewer matches than the limit and still have a next cursor.
+    pub fn get_payment_history(
+        env: Env,
+        query: PaymentQuery,
+        cursor: u32,
+        limit: u32,
+    ) -> Result<PaymentPage, PayrollError> {
+        if limit == 0 {
+            return Err(PayrollError::InvalidAmount);
+        }
+
+        // An employee's payments are read from their own index
+        let payment_count = match &query.employee_id {
+            Some(employee_id) => {
+                get_employee(&env, employee_id)?;
+                get_employee_payment_count(&env, employee_id)
+            }
+            None => get_payment_count(&env),
+        };
+
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let mut page = PaymentPage {
+            payments: Vec::new(&env),
+            next_cursor: None,
+        };
+        let mut index = cursor;
+        while index < payment_count {
+            if page.payments.len() == limit || index - cursor == MAX_PAGE_SCAN {
+                page.next_cursor = Some(index);
+                break;
+            }
+
+            let payment_id = match &query.employee_id {
+                Some(employee_id) => get_employee_payment_id_at(&env, employee_id, index),
+                None => get_payment_id_at(&env, index),
+            };
+            if let Some(payment) = payment_id.and_then(|id| get_payment(&env, &id)) {
+                if matches_payment_query(&payment, &query) {
+                    page.payments.push_back(payment);
+                }
+            }
+            index += 1;
+        }
+
+        Ok(page)
+    }
+
+    /// Get a settled payment by its ID
+    pub fn get_payment(env: Env, payment_id: String) -> Result<Payment, PayrollError> {
+        get_payment(&env, &payment_id).ok_or(PayrollError::PaymentNotFound)
+    }
+
+    /// Get an employee's statement of earnings, deductions, tax and net pay for a calendar year
+    pub fn get_employee_statement(
+        env: Env,
+        employee_id: String,
+        year: u32,
+    ) -> Result<EmployeeStatement, PayrollError> {
+        let employee = get_employee(&env, &employee_id)?;
+        Ok(EmployeeStatement {
+            year_to_date: get_year_to_date(&env, &employee_id, year),
+            employee_id,
+            account_id: employee.account_id,
+            tax_jurisdiction: employee.tax_jurisdiction,
+        })
+    }
+
+    /// Get a page of an employee's change log, oldest first. Pass 0 as the cursor
+    /// for the first page and the returned `next_cursor` for the following ones.
+    pub fn get_employee_changes(
+        env: Env,
+        employee_id: String,
+        cursor: u32,
+        limit: u32,
+    ) -> Result<EmployeeChangePage, PayrollError> {
+        if limit == 0 {
+            return Err(PayrollError::InvalidAmount);
+        }
+
+        get_employee(&env, &employee_id)?;
+        let change_count = get_employee_change_count(&env, &employee_id);
+
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let mut page = EmployeeChangePage {
+            changes: Vec::new(&env),
+            next_cursor: None,
+        };
+        let mut index = cursor;
+        while index < change_count {
+            if page.changes.len() == limit {
+                page.next_cursor = Some(index);
+                break;
+            }
+            if let Some(change) = get_employee_change_at(&env, &employee_id, index) {
+                page.changes.push_back(change);
+            }
+            index += 1;
+        }
+
+        Ok(page)
+    }
+
+    /// Update contract status (admin only)
+    pub fn update_contract_status(
+        env: Env,
+        caller: Address,
+        new_status: ContractStatus,
+    ) -> Result<(), PayrollError> {
+        caller.require_auth();
+
+        let mut contract = get_contract(&env)?;
+
+        // Validate employer-only access
+        validate_employer_only(&contract, &caller)?;
+
+        // A status change withdraws any run awaiting approval
+        Self::discard_run_proposal(&env, &mut contract, &caller);
+        contract.status = new_status.clone();
+        store_contract(&env, &contract);
+
+        // Emit event
+        env.events()
+            .publish((symbol_short!("status"), caller), new_status as u32);
+
+        Ok(())
+    }
+
+    /// Move a contract deployed before storage version 2 to the current layout
+    /// (employer only). Each employee gets their own storage entry and pay
+    /// schedule, and legacy payments move to the payment index. Returns the
+    /// number of employees on the payroll that were migrated.
+    pub fn migrate_storage(env: Env, caller: Address) -> Result<u32, PayrollError> {
+        caller.require_auth();
+
+        if get_storage_version(&env) >= STORAGE_VERSION {
+            return Err(PayrollError::InvalidStatus);
+        }
+
+        let legacy = get_legacy_contract(&env)?;
+        if caller != legacy.employer_account {
+            return Err(PayrollError::Unauthorized);
+        }
+
+        // Legacy contracts paid everyone on the schedule's dates, so the schedule
+        // keeps its day of the month and becomes each employee's schedule
+        let legacy_schedule = legacy.payment_schedule;
+        let payment_schedule = TimeSchedule {
+            frequency: legacy_schedule.frequency,
+            pay_day_rule: anchor_pay_day_rule(
+                &PayDayRule::SameDay,
+                legacy_schedule.next_payment_date,
+            ),
+            start_date: legacy_schedule.start_date,
+            next_payment_date: legacy_schedule.next_payment_date,
+            end_date: legacy_schedule.end_date,
+        };
+        let hire_date = legacy.created_at.min(payment_schedule.start_date);
+
+        // Earlier versions only kept the separately stored record up to date,
+        // so it is preferred over the copy embedded in the contract
+        let mut employee_ids = Vec::new(&env);
+        let mut legacy_employees = Vec::new(&env);
+        for embedded in legacy.employees.iter() {
+            let employee = get_legacy_employee(&env, &embedded.employee_id).unwrap_or(embedded);
+            employee_ids.push_back(employee.employee_id.clone());
+            legacy_employees.push_back(employee);
+        }
+
+        // Removed employees are only reachable through their payments
+        let payment_history = get_legacy_payment_history(&env);
+        let mut migrated_ids = employee_ids.clone();
+        for payment in payment_history.iter() {
+            if migrated_ids.contains(&payment.employee_id) {
+                continue;
+            }
+            if let Some(employee) = get_legacy_employee(&env, &payment.employee_id) {
+                legacy_employees.push_back(employee);
+            }
+            migrated_ids.push_back(payment.employee_id);
+        }
+
+        // Index the payments in the order they were made. Each employee's
+        // history repeats the global one, so duplicates are skipped.
+        for payment in payment_history.iter() {
+            Self::migrate_legacy_payment(&env, &legacy.contract_id, payment);
+        }
+        for employee in legacy_employees.iter() {
+            for payment in employee.payment_history.iter() {
+                Self::migrate_legacy_payment(&env, &legacy.contract_id, payment);
+            }
+        }
+
+        for employee in legacy_employees.iter() {
+            store_employee(
+                &env,
+                &Self::migrate_legacy_employee(&env, employee, &payment_schedule, hire_date),
+            );
+        }
+        for (jurisdiction, _) in legacy.tax_rates.iter() {
+            add_tax_jurisdiction(&env, &jurisdiction);
+        }
+
+        // Settings that didn't exist before take the defaults of a new contract,
+        // and no run can be awaiting approval
+        let status = match legacy.status {
+            ContractStatus::PendingApproval => ContractStatus::Active,
+            status => status,
+        };
+        let contract = PayrollContract {
+            employer_account: legacy.employer_account,
+            payment_schedule,
+            employee_ids: employee_ids.clone(),
+            tax_rates: legacy.tax_rates,
+            payment_token: legacy.payment_token,
+            minimum_balance: legacy.minimum_balance,
+            signers: legacy.signers,
+            approval_threshold: 1,
+            proposal_ttl: DEFAULT_PROPOSAL_TTL,
+            funding_mode: FundingMode::AllOrNothing,
+            status,
+            contract_id: legacy.contract_id,
+            created_at: legacy.created_at,
+        };
+        store_contract(&env, &contract);
+        remove_legacy_storage(&env);
+        set_storage_version(&env, STORAGE_VERSION);
+
+        // Emit event
+        env.events()
+            .publish((symbol_short!("migrated"), caller), STORAGE_VERSION);
+
+        Ok(employee_ids.len())
+    }
+
+    /// Add or update tax rate for a jurisdiction
+    pub fn set_tax_rate(
+        env: Env,
+        caller: Address,
+        jurisdiction: String,
+        tax_rate: i128, // Basis points (10000 = 100%)
+    ) -> Result<(), PayrollError> {
+        caller.require_auth();
+
+        let mut contract = get_contract(&env)?;
+
+        // Validate authorization and tax rate
+        validate_authorization(&contract, &caller)?;
+        validate_tax_rate(tax_rate)?;
+
+        // Withholding changes, so any run awaiting approval is withdrawn
+        Self::discard_run_proposal(&env, &mut contract, &caller);
+        contract.tax_rates.set(jurisdiction.clone(), tax_rate);
+        store_contract(&env, &contract);
+        add_tax_jurisdiction(&env, &jurisdiction);
+
+        // Emit event
+        env.events()
+            .publish((symbol_short!("tax_set"), jurisdiction), tax_rate);
+
+        Ok(())
+    }
+
+    /// Set the progressive tax levies for a jurisdiction, replacing its flat rate.
+    /// An empty list removes the levies and restores the flat rate.
+    pub fn set_tax_levies(
+        env: Env,
+        caller: Address,
+        jurisdiction: String,
+        levies: Vec<TaxLevy>,
+    ) -> Result<(), PayrollError> {
+        caller.require_auth();
+
+        let mut contract = get_contract(&env)?;
+
+        // Validate authorization and levies
+        validate_authorization(&contract, &caller)?;
+        validate_tax_levies(&levies)?;
+
+        // Withholding changes, so any run awaiting approval is withdrawn
+        Self::discard_run_proposal(&env, &mut contract, &caller);
+        store_contract(&env, &contract);
+        store_tax_levies(&env, &jurisdiction, &levies);
+        add_tax_jurisdiction(&env, &jurisdiction);
+
+        // Emit event
+        env.events()
+            .publish((symbol_short!("levy_set"), jurisdiction), levies.len());
+
+        Ok(())
+    }
+
+    /// Get the progressive tax levies for a jurisdiction
+    pub fn get_tax_levies(env: Env, jurisdiction: String) -> Vec<TaxLevy> {
+        get_tax_levies(&env, &jurisdiction)
+    }
+
+    /// Get an employee's earnings and withholdings for the current calendar year
+    pub fn get_year_to_date(env: Env, employee_id: String) -> Result<YearToDate, PayrollError> {
+        get_employee(&env, &employee_id)?;
+        let (year, _, _) = timestamp_to_date(env.ledger().timestamp());
+        Ok(get_year_to_date(&env, &employee_id, year as u32))
+    }
+
+    /// Set the tax authority account that a jurisdiction's withholdings are remitted to (employer only)
+    pub fn set_tax_authority(
+        env: Env,
+        caller: Address,
+        jurisdiction: String,
+        authority: Address,
+    ) -> Result<(), PayrollError> {
+        caller.require_auth();
+
+        let contract = get_contract(&env)?;
+
+        // Validate employer-only access
+        validate_employer_only(&contract, &caller)?;
+
+        let mut liability = get_tax_liability(&env, &jurisdiction);
+        liability.authority = Some(authority.clone());
+        store_tax_liability(&env, &liability);
+
+        // Emit event
+        env.events()
+            .publish((symbol_short!("tax_auth"), jurisdiction), authority);
+
+        Ok(())
+    }
+
+    /// Transfer all outstanding withholdings for a jurisdiction to its tax authority
+    pub fn remit_withholdings(
+        env: Env,
+        caller: Address,
+        jurisdiction: String,
+    ) -> Result<i128, PayrollError> {
+        caller.require_auth();
+
+        let contract = get_contract(&env)?;
+
+        // Validate authorization
+        validate_authorization(&contract, &caller)?;
+
+        let mut liability = get_tax_liability(&env, &jurisdiction);
+        let authority = liability
+            .authority
+            .clone()
+            .ok_or(PayrollError::TaxAuthorityNotSet)?;
+        let amount = liability.outstanding;
+        if amount <= 0 {
+            return Err(PayrollError::InvalidAmount);
+        }
+
+        // Pay the tax authority
+        let token_client = Client::new(&env, &contract.payment_token.token);
+        token_client.transfer(&env.current_contract_address(), &authority, &amount);
+
+        liability.remitted += amount;
+        liability.outstanding = 0;
+        store_tax_liability(&env, &liability);
+
+        // Record the remittance for the audit trail
+        add_tax_remittance(
+            &env,
+            &TaxRemittance {
+                jurisdiction: jurisdiction.clone(),
+                authority: authority.clone(),
+                amount,
+                remitted_at: env.ledger().timestamp(),
+                ledger_sequence: env.ledger().sequence(),
+            },
+        );
+
+        // Emit event
+        env.events()
+            .publish((symbol_short!("remitted"), jurisdiction, authority), amount);
+
+        Ok(amount)
+    }
+
+    /// Get the withholding liability for a jurisdiction
+    pub fn get_tax_liability(env: Env, jurisdiction: String) -> TaxLiability {
+        get_tax_liability(&env, &jurisdiction)
+    }
+
+    /// Get the withholding liability for every jurisdiction with a tax rate or levies
+    pub fn get_tax_liabilities(env: Env) -> Vec<TaxLiability> {
+        let mut liabilities = Vec::new(&env);
+        for jurisdiction in get_tax_jurisdictions(&env).iter() {
+            liabilities.push_back(get_tax_liability(&env, &jurisdiction));
+        }
+        liabilities
+    }
+
+    /// Get all remittances made to a jurisdiction's tax authority
+    pub fn get_tax_remittances(env: Env, jurisdiction: String) -> Vec<TaxRemittance> {
+        get_tax_remittances(&env, &jurisdiction)
+    }
+
+    // Private helper methods
+
+    /// Sum the withholdings held by the contract that have not been remitted yet
+    fn outstanding_withholdings(env: &Env) -> i128 {
+        let mut outstanding = 0;
+        for jurisdiction in get_tax_jurisdictions(env).iter() {
+            outstanding += get_tax_liability(env, &jurisdiction).outstanding;
+        }
+        outstanding
+    }
+
+    /// Get the balance available for payments: the contract's token balance less
+    /// withholdings held for the tax authorities and the minimum balance
+    fn available_balance(env: &Env, contract: &PayrollContract) -> i128 {
+        let token_client = Client::new(env, &contract.payment_token.token);
+        token_client.balance(&env.current_contract_address())
+            - Self::outstanding_withholdings(env)
+            - contract.minimum_balance
+    }
+
+    /// Get the token balance needed to pay a run total while keeping withheld
+    /// tax and the minimum balance in the contract
+    fn required_balance(env: &Env, contract: &PayrollContract, run_total: i128) -> i128 {
+        run_total + Self::outstanding_withholdings(env) + contract.minimum_balance
+    }
+
+    /// Discard the payroll run awaiting approval, if any, so the signers approve a
+    /// fresh proposal after a change that could affect it. A contract waiting on
+    /// the proposal returns to active; the caller stores the contract.
+    fn discard_run_proposal(env: &Env, contract: &mut PayrollContract, caller: &Address) {
+        if let Some(proposal) = get_run_proposal(env) {
+            remove_run_proposal(env);
+            env.events()
+                .publish((symbol_short!("cancelled"), proposal.proposal_id), caller.clone());
+        }
+        if contract.status == ContractStatus::PendingApproval {
+            contract.status = ContractStatus::Active;
+        }
+    }
+
+    /// Emit a warning that the balance doesn't cover the next payroll run
+    fn warn_low_balance(env: &Env, contract_balance: i128, required_balance: i128) {
+        env.events().publish(
+            (symbol_short!("low_bal"),),
+            (contract_balance, required_balance),
+        );
+    }
+
+    /// Calculate the tax to withhold from a payment, using the jurisdiction's
+    /// progressive levies if it has any and its flat rate otherwise
+    fn calculate_withholding(
+        env: &Env,
+        contract: &PayrollContract,
+        employee: &Employee,
+        taxable_amount: i128,
+        ytd_taxable: i128,
+    ) -> i128 {
+        let levies = get_tax_levies(env, &employee.tax_jurisdiction);
+        if !levies.is_empty() {
+            return calculate_progressive_tax(
+                &levies,
+                &employee.pay_frequency,
+                taxable_amount,
+                ytd_taxable,
+            );
+        }
+
+        let tax_rate = contract
+            .tax_rates
+            .get(employee.tax_jurisdiction.clone())
+            .unwrap_or(0);
+        calculate_tax_amount(taxable_amount, tax_rate)
+    }
+
+    /// Calculate the total a run on `run_date` would pay out (gross pay plus employer
+    /// contributions), how many employees it would pay and a hash of their payments
+    fn calculate_run_total(
+        env: &Env,
+        contract: &PayrollContract,
+        employees: &Vec<Employee>,
+        run_date: u64,
+    ) -> Result<(i128, u32, BytesN<32>), PayrollError> {
+        let mut total_amount = 0;
+        let mut employee_count = 0;
+        let mut payments = Vec::new(env);
+        let mut any_due = false;
+        for employee in employees.iter() {
+            if !is_employee_due(&employee, run_date) {
+                continue;
+            }
+            any_due = true;
+            if let Some(payment) = Self::calculate_employee_payment(env, contract, &employee) {
+                if validate_payment_amounts(&payment).is_ok() {
+                    total_amount += payment.gross_amount + payment.employer_contributions;
+                    employee_count += 1;
+                }
+                payments.push_back((employee.account_id.clone(), payment));
+            }
+        }
+
+        if !any_due {
+            return Err(PayrollError::PaymentNotDue);
+        }
+        Ok((total_amount, employee_count, hash_run_payments(env, &payments)))
+    }
+
+    /// Calculate an employee's payment for the period ending at their next payment
+    /// date without moving any funds. Returns `None` when they were not employed at
+    /// any point in the period, or were suspended, and nothing else is owed.
+    /// Deductions may exceed pay, leaving a negative net amount that
+    /// `validate_payment_amounts` rejects.
+    fn calculate_employee_payment(
+        env: &Env,
+        contract: &PayrollContract,
+        employee: &Employee,
+    ) -> Option<Payment> {
+        // Suspended employees aren't paid for the period
+        let base_pay = if employee.is_suspended {
+            0
+        } else {
+            Self::calculate_base_pay(employee)
+        };
+
+        // Unused vacation is paid out with the final paycheck
+        let vacation_payout = match employee.termination_date {
+            Some(termination_date) if termination_date <= employee.next_payment_date => {
+                calculate_vacation_payout(
+                    &employee.benefits,
+                    Self::salary_on(employee, termination_date),
+                )
+            }
+            _ => 0,
+        };
+        if base_pay == 0 && vacation_payout == 0 {
+            return None;
+        }
+
+        // Apply benefits: earnings add to gross pay, pre-tax deductions reduce
+        // taxable pay and post-tax deductions come out of pay after tax
+        let mut line_items = calculate_benefit_line_items(&employee.benefits, base_pay);
+        if vacation_payout > 0 {
+            line_items.push_back(PaymentLineItem {
+                benefit_type: BenefitType::VacationDays,
+                treatment: BenefitTreatment::Earning,
+                description: String::from_str(env, "Unused vacation payout"),
+                amount: vacation_payout,
+                recipient: None,
+            });
+        }
+        let gross_amount = base_pay + sum_line_items(&line_items, &BenefitTreatment::Earning);
+        let pre_tax_deductions = sum_line_items(&line_items, &BenefitTreatment::PreTaxDeduction);
+        let post_tax_deductions = sum_line_items(&line_items, &BenefitTreatment::PostTaxDeduction);
+        let employer_contributions =
+            sum_line_items(&line_items, &BenefitTreatment::EmployerContribution);
+
+        let taxable_amount = gross_amount - pre_tax_deductions;
+        let (year, _, _) = timestamp_to_date(env.ledger().timestamp());
+        let ytd = get_year_to_date(env, &employee.employee_id, year as u32);
+        let tax_amount = if taxable_amount > 0 {
+            Self::calculate_withholding(env, contract, employee, taxable_amount, ytd.taxable_wages)
+        } else {
+            0
+        };
+        let net_amount = calculate_net_amount(taxable_amount, tax_amount) - post_tax_deductions;
+
+        // Derive the payment ID from the contract, pay period and employee
+        let payment_id = generate_payment_id(
+            env,
+            &contract.contract_id,
+            employee.next_payment_date,
+            &employee.employee_id,
+        );
+
+        Some(Payment {
+            payment_id,
+            employee_id: employee.employee_id.clone(),
+            recipient: Some(employee.account_id.clone()),
+            period_end: employee.next_payment_date,
+            gross_amount,
+            pre_tax_deductions,
+            tax_amount,
+            post_tax_deductions,
+            net_amount,
+            employer_contributions,
+            line_items,
+            payment_date: env.ledger().timestamp(),
+            status: PaymentStatus::Pending,
+            reason: None,
+            transaction_hash: None,
+            ledger_sequence: 0,
+        })
+    }
+
+    /// Calculate an employee's base pay for the period ending at their next payment
+    /// date, pro-rated for partial employment and split at pending salary changes
+    fn calculate_base_pay(employee: &Employee) -> i128 {
+        let prorated = |annual_salary, worked_from, worked_until| {
+            calculate_prorated_gross_payment(
+                &employee.pay_frequency,
+                &employee.pay_day_rule,
+                annual_salary,
+                employee.next_payment_date,
+                worked_from,
+                worked_until,
+            )
+        };
+
+        // Each salary is paid from when it took effect until the next change
+        let mut base_pay = 0;
+        let mut annual_salary = employee.base_salary;
+        let mut worked_from = employee.hire_date;
+        for salary_change in employee.salary_changes.iter() {
+            let effective_date = salary_change.effective_date;
+            let worked_until = employee
+                .termination_date
+                .map_or(effective_date, |date| date.min(effective_date));
+            base_pay += prorated(annual_salary, worked_from, Some(worked_until));
+            annual_salary = salary_change.base_salary;
+            worked_from = worked_from.max(effective_date);
+        }
+        base_pay + prorated(annual_salary, worked_from, employee.termination_date)
+    }
+
+    /// Get the annual salary an employee earns on a date
+    fn salary_on(employee: &Employee, date: u64) -> i128 {
+        let mut annual_salary = employee.base_salary;
+        for salary_change in employee.salary_changes.iter() {
+            if salary_change.effective_date <= date {
+                annual_salary = salary_change.base_salary;
+            }
+        }
+        annual_salary
+    }
+
+    /// Drop one-off earnings such as bonuses once the payment they belong to is made
+    fn remove_earnings(env: &Env, benefits: &Vec<Benefit>) -> Vec<Benefit> {
+        let mut remaining = Vec::new(env);
+        for benefit in benefits.iter() {
+            if benefit.treatment != BenefitTreatment::Earning {
+                remaining.push_back(benefit);
+            }
+        }
+        remaining
+    }
+
+    /// Record a change in an employee's change log
+    fn log_employee_change(
+        env: &Env,
+        employee_id: &String,
+        kind: EmployeeChangeKind,
+        changed_by: &Address,
+        effective_date: u64,
+    ) {
+        add_employee_change(
+            env,
+            &EmployeeChange {
+                employee_id: employee_id.clone(),
+                kind,
+                changed_by: changed_by.clone(),
+                changed_at: env.ledger().timestamp(),
+                effective_date,
+            },
+        );
+    }
+
+    /// Convert a legacy employee record, putting them on the contract's schedule.
+    /// Legacy benefits were recorded but never applied to pay, so they stay non-monetary.
+    fn migrate_legacy_employee(
+        env: &Env,
+        legacy: LegacyEmployee,
+        payment_schedule: &TimeSchedule,
+        hire_date: u64,
+    ) -> Employee {
+        let mut benefits = Vec::new(env);
+        for benefit in legacy.benefits.iter() {
+            benefits.push_back(Benefit {
+                benefit_type: benefit.benefit_type,
+                amount: benefit.amount,
+                description: benefit.description,
+                treatment: BenefitTreatment::NonMonetary,
+                calculation: BenefitCalculation::Fixed,
+                recipient: None,
+            });
+        }
+
+        Employee {
+            account_id: legacy.account_id,
+            employee_id: legacy.employee_id,
+            base_salary: legacy.base_salary,
+            salary_changes: Vec::new(env),
+            tax_jurisdiction: legacy.tax_jurisdiction,
+            benefits,
+            is_active: legacy.is_active,
+            is_suspended: false,
+            pay_frequency: payment_schedule.frequency.clone(),
+            pay_day_rule: payment_schedule.pay_day_rule.clone(),
+            hire_date,
+            termination_date: None,
+            next_payment_date: payment_schedule.next_payment_date,
+        }
+    }
+
+    /// Add a legacy payment to the payment index under an ID derived from its
+    /// employee and date, and count completed payments in year-to-date totals.
+    /// A payment already indexed is skipped.
+    fn migrate_legacy_payment(env: &Env, contract_id: &String, legacy: LegacyPayment) {
+        let payment_id =
+            generate_payment_id(env, contract_id, legacy.payment_date, &legacy.employee_id);
+        if get_payment(env, &payment_id).is_some() {
+            return;
+        }
+
+        let payment = Payment {
+            payment_id,
+            employee_id: legacy.employee_id,
+            recipient: None,
+            period_end: legacy.payment_date,
+            gross_amount: legacy.gross_amount,
+            pre_tax_deductions: 0,
+            tax_amount: legacy.tax_amount,
+            post_tax_deductions: 0,
+            net_amount: legacy.net_amount,
+            employer_contributions: 0,
+            line_items: Vec::new(env),
+            payment_date: legacy.payment_date,
+            status: legacy.status,
+            reason: None,
+            transaction_hash: legacy.transaction_hash,
+            ledger_sequence: 0,
+        };
+        add_payment(env, &payment);
+
+        if payment.status == PaymentStatus::Completed {
+            let (year, _, _) = timestamp_to_date(payment.payment_date);
+            let mut ytd = get_year_to_date(env, &payment.employee_id, year as u32);
+            ytd.gross_earnings += payment.gross_amount;
+            ytd.taxable_wages += payment.gross_amount;
+            ytd.tax_withheld += payment.tax_amount;
+            ytd.net_pay += payment.net_amount;
+            ytd.payment_count += 1;
+            store_year_to_date(env, &payment.employee_id, &ytd);
+        }
+    }
+
+    /// Mark a payment as failed, recording why
+    fn failed_payment(env: &Env, mut payment: Payment, error: PayrollError) -> Payment {
+        let reason = match error {
+            PayrollError::InsufficientBalance => "Insufficient balance",
+            PayrollError::InvalidAmount => "Deductions exceed pay",
+            _ => "Payment failed",
+        };
+        payment.status = PaymentStatus::Failed;
+        payment.reason = Some(String::from_str(env, reason));
+        payment
+    }
+
+    /// Make a calculated payment to an employee: transfer net pay and provider
+    /// amounts, hold the withheld tax and update year-to-date totals
+    fn process_payment(
+        env: &Env,
+        contract: &PayrollContract,
+        employee: &Employee,
+        mut payment: Payment,
+    ) -> Result<Payment, PayrollError> {
+        validate_payment_amounts(&payment)?;
+
+        // Check the contract can cover gross pay and employer contributions,
+        // without touching withheld tax or the minimum balance
+        validate_sufficient_balance(
+            Self::available_balance(env, contract),
+            payment.gross_amount + payment.employer_contributions,
+        )?;
+
+        let token_client = Client::new(env, &contract.payment_token.token);
+
+        // Pay the account approved with the run, even if the employee's account has changed since
+        let recipient = payment.recipient.clone().unwrap_or(employee.account_id.clone());
+        let _payment_result = token_client.transfer(
+            &env.current_contract_address(),
+            &recipient,
+            &payment.net_amount,
+        );
+
+        // Pay deductions and employer contributions to their providers
+        for line_item in payment.line_items.iter() {
+            if !is_provider_payment(&line_item) {
+                continue;
+            }
+            if let Some(recipient) = line_item.recipient {
+                token_client.transfer(
+                    &env.current_contract_address(),
+                    &recipient,
+                    &line_item.amount,
+                );
+            }
+        }
+
+        mark_payment_processed(env, &payment.payment_id);
+
+        // Hold the withheld tax for the employee's jurisdiction
+        if payment.tax_amount > 0 {
+            accrue_tax_withholding(env, &employee.tax_jurisdiction, payment.tax_amount);
+        }
+
+        // Update the employee's year-to-date totals
+        let (year, _, _) = timestamp_to_date(env.ledger().timestamp());
+        let mut ytd = get_year_to_date(env, &employee.employee_id, year as u32);
+        ytd.gross_earnings += payment.gross_amount;
+        ytd.taxable_wages += (payment.gross_amount - payment.pre_tax_deductions).max(0);
+        ytd.pre_tax_deductions += payment.pre_tax_deductions;
+        ytd.tax_withheld += payment.tax_amount;
+        ytd.post_tax_deductions += payment.post_tax_deductions;
+        ytd.net_pay += payment.net_amount;
+        ytd.employer_contributions += payment.employer_contributions;
+        ytd.payment_count += 1;
+        store_year_to_date(env, &employee.employee_id, &ytd);
+
+        // Create the transaction reference from the settled transfer
+        payment.transaction_hash = Some(generate_transaction_hash(
+            env,
+            &payment.payment_id,
+            &contract.payment_token.token,
+            &recipient,
+            payment.net_amount,
+        ));
+        payment.status = PaymentStatus::Completed;
+        payment.reason = None;
+        payment.payment_date = env.ledger().timestamp();
+        payment.ledger_sequence = env.ledger().sequence();
+
+        // Emit payment event
+        env.events().publish(
+            (
+                symbol_short!("payment"),
+                employee.employee_id.clone(),
+                payment.payment_id.clone(),
+            ),
+            payment.net_amount,
         );
 
         Ok(payment)
     }
-} 
\ No newline at end of file
+}
\ No newline at end of file
@@ -14,4 +14,6 @@ pub use contract::*;
 pub use types::*;
 
 #[cfg(test)]
-mod test; 
\ No newline at end of file
+mod test;
+#[cfg(test)]
+mod testutils; 
\ No newline at end of file
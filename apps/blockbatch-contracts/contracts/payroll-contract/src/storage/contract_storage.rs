@@ -1,14 +1,64 @@
-use crate::types::{DataKey, PayrollContract, PayrollError, Payment};
-use soroban_sdk::{Env, Vec};
+use crate::types::{
+    DataKey, LegacyDataKey, LegacyPayment, LegacyPayrollContract, PayrollContract, PayrollError,
+    PayrollRunProposal, Payment,
+};
+use soroban_sdk::{Env, String, Vec};
+
+/// Storage layout version written by this contract
+pub const STORAGE_VERSION: u32 = 2;
 
 /// Get the payroll contract from storage
 pub fn get_contract(env: &Env) -> Result<PayrollContract, PayrollError> {
+    if !env.storage().persistent().has(&DataKey::Contract) {
+        return Err(PayrollError::NotInitialized);
+    }
+    if get_storage_version(env) < STORAGE_VERSION {
+        return Err(PayrollError::MigrationRequired);
+    }
+    env.storage()
+        .persistent()
+        .get(&DataKey::Contract)
+        .ok_or(PayrollError::NotInitialized)
+}
+
+/// Get the payroll contract in the layout used before storage version 2
+pub fn get_legacy_contract(env: &Env) -> Result<LegacyPayrollContract, PayrollError> {
     env.storage()
         .persistent()
         .get(&DataKey::Contract)
         .ok_or(PayrollError::NotInitialized)
 }
 
+/// Get the payment history kept before storage version 2, oldest first
+pub fn get_legacy_payment_history(env: &Env) -> Vec<LegacyPayment> {
+    env.storage()
+        .persistent()
+        .get(&LegacyDataKey::PaymentHistory)
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+/// Remove the storage entries only used before storage version 2
+pub fn remove_legacy_storage(env: &Env) {
+    let storage = env.storage().persistent();
+    storage.remove(&LegacyDataKey::PaymentHistory);
+    storage.remove(&LegacyDataKey::NextPaymentId);
+}
+
+/// Get the storage layout version, 1 for deployments that predate versioning
+pub fn get_storage_version(env: &Env) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::StorageVersion)
+        .unwrap_or(1)
+}
+
+/// Record the storage layout version
+pub fn set_storage_version(env: &Env, version: u32) {
+    env.storage()
+        .persistent()
+        .set(&DataKey::StorageVersion, &version);
+}
+
 /// Store the payroll contract
 pub fn store_contract(env: &Env, contract: &PayrollContract) {
     env.storage()
@@ -16,59 +66,82 @@ pub fn store_contract(env: &Env, contract: &PayrollContract) {
         .set(&DataKey::Contract, contract);
 }
 
-/// Get the next payment ID counter
-pub fn get_next_payment_id(env: &Env) -> u64 {
+/// Check if a payment ID has already been paid
+pub fn is_payment_processed(env: &Env, payment_id: &String) -> bool {
     env.storage()
         .persistent()
-        .get(&DataKey::NextPaymentId)
-        .unwrap_or(0)
+        .has(&DataKey::ProcessedPayment(payment_id.clone()))
 }
 
-/// Increment and store the payment ID counter
-pub fn increment_payment_id(env: &Env) -> u64 {
-    let current_id = get_next_payment_id(env);
-    let next_id = current_id + 1;
+/// Record a payment ID as paid so it cannot be paid again
+pub fn mark_payment_processed(env: &Env, payment_id: &String) {
     env.storage()
         .persistent()
-        .set(&DataKey::NextPaymentId, &next_id);
+        .set(&DataKey::ProcessedPayment(payment_id.clone()), &true);
+}
+
+/// Get the payroll run awaiting approval, if any
+pub fn get_run_proposal(env: &Env) -> Option<PayrollRunProposal> {
+    env.storage().persistent().get(&DataKey::RunProposal)
+}
+
+/// Store the payroll run awaiting approval
+pub fn store_run_proposal(env: &Env, proposal: &PayrollRunProposal) {
+    env.storage()
+        .persistent()
+        .set(&DataKey::RunProposal, proposal);
+}
+
+/// Remove the payroll run awaiting approval
+pub fn remove_run_proposal(env: &Env) {
+    env.storage().persistent().remove(&DataKey::RunProposal);
+}
+
+/// Increment and store the run proposal ID counter
+pub fn increment_proposal_id(env: &Env) -> u64 {
+    let current_id: u64 = env
+        .storage()
+        .persistent()
+        .get(&DataKey::NextProposalId)
+        .unwrap_or(0);
+    env.storage()
+        .persistent()
+        .set(&DataKey::NextProposalId, &(current_id + 1));
     current_id
 }
 
-/// Get the total number of employees
-pub fn get_total_employees(env: &Env) -> u32 {
+/// Get the failed payments waiting to be retried or cancelled
+pub fn get_failed_payments(env: &Env) -> Vec<Payment> {
     env.storage()
         .persistent()
-        .get(&DataKey::TotalEmployees)
-        .unwrap_or(0)
+        .get(&DataKey::FailedPayments)
+        .unwrap_or(Vec::new(env))
 }
 
-/// Update the total number of employees
-pub fn set_total_employees(env: &Env, count: u32) {
+/// Store the failed payments waiting to be retried or cancelled
+pub fn store_failed_payments(env: &Env, failed_payments: &Vec<Payment>) {
     env.storage()
         .persistent()
-        .set(&DataKey::TotalEmployees, &count);
+        .set(&DataKey::FailedPayments, failed_payments);
 }
 
-/// Get global payment history
-pub fn get_payment_history(env: &Env) -> Vec<Payment> {
+/// Get the total number of employees
+pub fn get_total_employees(env: &Env) -> u32 {
     env.storage()
         .persistent()
-        .get(&DataKey::PaymentHistory)
-        .unwrap_or_else(|| Vec::new(env))
+        .get(&DataKey::TotalEmployees)
+        .unwrap_or(0)
 }
 
-/// Store global payment history
-pub fn store_payment_history(env: &Env, history: &Vec<Payment>) {
+/// Update the total number of employees
+pub fn set_total_employees(env: &Env, count: u32) {
     env.storage()
         .persistent()
-        .set(&DataKey::PaymentHistory, history);
+        .set(&DataKey::TotalEmployees, &count);
 }
 
 /// Initialize contract storage with default values
 pub fn initialize_storage(env: &Env) {
-    env.storage().persistent().set(&DataKey::NextPaymentId, &0u64);
     env.storage().persistent().set(&DataKey::TotalEmployees, &0u32);
-    env.storage()
-        .persistent()
-        .set(&DataKey::PaymentHistory, &Vec::<Payment>::new(env));
+    set_storage_version(env, STORAGE_VERSION);
 } 
\ No newline at end of file
@@ -1,5 +1,5 @@
-use crate::types::{DataKey, Employee, PayrollError};
-use soroban_sdk::{Env, String};
+use crate::types::{DataKey, Employee, EmployeeChange, LegacyEmployee, PayrollError, YearToDate};
+use soroban_sdk::{Env, String, Vec};
 
 /// Check if an employee exists in storage
 pub fn employee_exists(env: &Env, employee_id: &String) -> bool {
@@ -16,6 +16,22 @@ pub fn get_employee(env: &Env, employee_id: &String) -> Result<Employee, Payroll
         .ok_or(PayrollError::EmployeeNotFound)
 }
 
+/// Get the employees in an employee ID index, in index order
+pub fn get_employees(env: &Env, employee_ids: &Vec<String>) -> Result<Vec<Employee>, PayrollError> {
+    let mut employees = Vec::new(env);
+    for employee_id in employee_ids.iter() {
+        employees.push_back(get_employee(env, &employee_id)?);
+    }
+    Ok(employees)
+}
+
+/// Get an employee record in the layout used before storage version 2
+pub fn get_legacy_employee(env: &Env, employee_id: &String) -> Option<LegacyEmployee> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Employee(employee_id.clone()))
+}
+
 /// Store an employee
 pub fn store_employee(env: &Env, employee: &Employee) {
     env.storage().persistent().set(
@@ -33,6 +49,31 @@ pub fn update_employee(env: &Env, employee: &Employee) -> Result<(), PayrollErro
     Ok(())
 }
 
+/// Get an employee's totals for a calendar year, starting from zero
+pub fn get_year_to_date(env: &Env, employee_id: &String, year: u32) -> YearToDate {
+    env.storage()
+        .persistent()
+        .get(&DataKey::YearToDate(employee_id.clone(), year))
+        .unwrap_or(YearToDate {
+            year,
+            gross_earnings: 0,
+            taxable_wages: 0,
+            pre_tax_deductions: 0,
+            tax_withheld: 0,
+            post_tax_deductions: 0,
+            net_pay: 0,
+            employer_contributions: 0,
+            payment_count: 0,
+        })
+}
+
+/// Store an employee's totals for a calendar year
+pub fn store_year_to_date(env: &Env, employee_id: &String, ytd: &YearToDate) {
+    env.storage()
+        .persistent()
+        .set(&DataKey::YearToDate(employee_id.clone(), ytd.year), ytd);
+}
+
 /// Mark an employee as inactive (soft delete)
 pub fn deactivate_employee(env: &Env, employee_id: &String) -> Result<(), PayrollError> {
     let mut employee = get_employee(env, employee_id)?;
@@ -50,4 +91,28 @@ pub fn filter_active_employees(employees: &soroban_sdk::Vec<Employee>) -> soroba
         }
     }
     active_employees
-} 
\ No newline at end of file
+} 
+
+/// Append an entry to an employee's change log
+pub fn add_employee_change(env: &Env, change: &EmployeeChange) {
+    let employee_id = change.employee_id.clone();
+    let index = get_employee_change_count(env, &employee_id);
+    let storage = env.storage().persistent();
+    storage.set(&DataKey::EmployeeChange(employee_id.clone(), index), change);
+    storage.set(&DataKey::EmployeeChangeCount(employee_id), &(index + 1));
+}
+
+/// Get the number of entries in an employee's change log
+pub fn get_employee_change_count(env: &Env, employee_id: &String) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::EmployeeChangeCount(employee_id.clone()))
+        .unwrap_or(0)
+}
+
+/// Get the entry at a position in an employee's change log
+pub fn get_employee_change_at(env: &Env, employee_id: &String, index: u32) -> Option<EmployeeChange> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::EmployeeChange(employee_id.clone(), index))
+}
\ No newline at end of file
@@ -1,5 +1,9 @@
 pub mod contract_storage;
 pub mod employee_storage;
+pub mod payment_storage;
+pub mod tax_storage;
 
 pub use contract_storage::*;
-pub use employee_storage::*; 
\ No newline at end of file
+pub use employee_storage::*;
+pub use payment_storage::*;
+pub use tax_storage::*; 
\ No newline at end of file
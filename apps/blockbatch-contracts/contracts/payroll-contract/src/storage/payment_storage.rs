@@ -0,0 +1,61 @@
+use crate::types::{DataKey, Payment};
+use soroban_sdk::{Env, String};
+
+/// Store a settled payment under its ID and append it to the global and
+/// employee payment indexes
+pub fn add_payment(env: &Env, payment: &Payment) {
+    let storage = env.storage().persistent();
+    storage.set(&DataKey::Payment(payment.payment_id.clone()), payment);
+
+    let index = get_payment_count(env);
+    storage.set(&DataKey::PaymentIndex(index), &payment.payment_id);
+    storage.set(&DataKey::PaymentCount, &(index + 1));
+
+    let employee_id = payment.employee_id.clone();
+    let employee_index = get_employee_payment_count(env, &employee_id);
+    storage.set(
+        &DataKey::EmployeePaymentIndex(employee_id.clone(), employee_index),
+        &payment.payment_id,
+    );
+    storage.set(
+        &DataKey::EmployeePaymentCount(employee_id),
+        &(employee_index + 1),
+    );
+}
+
+/// Get a settled payment by its ID
+pub fn get_payment(env: &Env, payment_id: &String) -> Option<Payment> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Payment(payment_id.clone()))
+}
+
+/// Get the number of settled payments
+pub fn get_payment_count(env: &Env) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::PaymentCount)
+        .unwrap_or(0)
+}
+
+/// Get the ID of the settled payment at a position in the global index
+pub fn get_payment_id_at(env: &Env, index: u32) -> Option<String> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::PaymentIndex(index))
+}
+
+/// Get the number of settled payments for an employee
+pub fn get_employee_payment_count(env: &Env, employee_id: &String) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::EmployeePaymentCount(employee_id.clone()))
+        .unwrap_or(0)
+}
+
+/// Get the ID of the settled payment at a position in an employee's index
+pub fn get_employee_payment_id_at(env: &Env, employee_id: &String, index: u32) -> Option<String> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::EmployeePaymentIndex(employee_id.clone(), index))
+}
\ No newline at end of file
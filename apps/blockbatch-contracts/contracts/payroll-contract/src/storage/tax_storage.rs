@@ -0,0 +1,87 @@
+use crate::types::{DataKey, TaxLevy, TaxLiability, TaxRemittance};
+use soroban_sdk::{Env, String, Vec};
+
+/// Get every jurisdiction that has a tax rate or levies
+pub fn get_tax_jurisdictions(env: &Env) -> Vec<String> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::TaxJurisdictions)
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+/// Register a jurisdiction so its withholdings are reported
+pub fn add_tax_jurisdiction(env: &Env, jurisdiction: &String) {
+    let mut jurisdictions = get_tax_jurisdictions(env);
+    if !jurisdictions.contains(jurisdiction) {
+        jurisdictions.push_back(jurisdiction.clone());
+        env.storage()
+            .persistent()
+            .set(&DataKey::TaxJurisdictions, &jurisdictions);
+    }
+}
+
+/// Get the progressive tax levies for a jurisdiction
+pub fn get_tax_levies(env: &Env, jurisdiction: &String) -> Vec<TaxLevy> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::TaxLevies(jurisdiction.clone()))
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+/// Store the progressive tax levies for a jurisdiction, removing them when empty
+pub fn store_tax_levies(env: &Env, jurisdiction: &String, levies: &Vec<TaxLevy>) {
+    let key = DataKey::TaxLevies(jurisdiction.clone());
+    if levies.is_empty() {
+        env.storage().persistent().remove(&key);
+    } else {
+        env.storage().persistent().set(&key, levies);
+    }
+}
+
+/// Get the withholding liability for a jurisdiction
+pub fn get_tax_liability(env: &Env, jurisdiction: &String) -> TaxLiability {
+    env.storage()
+        .persistent()
+        .get(&DataKey::TaxLiability(jurisdiction.clone()))
+        .unwrap_or_else(|| TaxLiability {
+            jurisdiction: jurisdiction.clone(),
+            authority: None,
+            withheld: 0,
+            remitted: 0,
+            outstanding: 0,
+        })
+}
+
+/// Store the withholding liability for a jurisdiction
+pub fn store_tax_liability(env: &Env, liability: &TaxLiability) {
+    env.storage().persistent().set(
+        &DataKey::TaxLiability(liability.jurisdiction.clone()),
+        liability,
+    );
+}
+
+/// Accrue tax withheld from a payment to its jurisdiction
+pub fn accrue_tax_withholding(env: &Env, jurisdiction: &String, amount: i128) {
+    let mut liability = get_tax_liability(env, jurisdiction);
+    liability.withheld += amount;
+    liability.outstanding += amount;
+    store_tax_liability(env, &liability);
+}
+
+/// Get all remittances made to a jurisdiction's tax authority
+pub fn get_tax_remittances(env: &Env, jurisdiction: &String) -> Vec<TaxRemittance> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::TaxRemittances(jurisdiction.clone()))
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+/// Record a remittance to a jurisdiction's tax authority
+pub fn add_tax_remittance(env: &Env, remittance: &TaxRemittance) {
+    let mut remittances = get_tax_remittances(env, &remittance.jurisdiction);
+    remittances.push_back(remittance.clone());
+    env.storage().persistent().set(
+        &DataKey::TaxRemittances(remittance.jurisdiction.clone()),
+        &remittances,
+    );
+}
@@ -1,11 +1,18 @@
 #![cfg(test)]
 
 use super::*;
+use crate::storage::{add_payment, get_contract, get_employee, store_contract, store_employee};
+use crate::testutils::create_levy;
+use crate::utils::{date_to_timestamp, generate_payment_id};
 use soroban_sdk::{
-    testutils::{Address as _},
-    Address, Env, String, Vec,
+    testutils::{Address as _, EnvTestConfig, Ledger},
+    token::{StellarAssetClient, TokenClient},
+    Address, Env, Map, String, Vec,
 };
 
+const DAY: u64 = 24 * 60 * 60;
+const START: u64 = 1_700_000_000;
+
 fn create_test_env() -> Env {
     Env::default()
 }
@@ -27,10 +34,22 @@ fn create_test_asset(env: &Env) -> Asset {
     }
 }
 
+fn create_funded_asset(env: &Env, holder: &Address, amount: i128) -> Asset {
+    let admin = Address::generate(env);
+    let token = env.register_stellar_asset_contract_v2(admin).address();
+    StellarAssetClient::new(env, &token).mint(holder, &amount);
+    Asset {
+        token,
+        symbol: String::from_str(env, "USDC"),
+        decimals: 7,
+    }
+}
+
 fn create_test_payment_schedule(env: &Env) -> TimeSchedule {
     let current_time = env.ledger().timestamp();
     TimeSchedule {
         frequency: PaymentFrequency::Monthly,
+        pay_day_rule: PayDayRule::SameDay,
         start_date: current_time + 86400, // Start tomorrow
         next_payment_date: current_time + 86400,
         end_date: None,
@@ -42,13 +61,62 @@ fn create_test_employee(env: &Env, account_id: Address, employee_id: &str) -> Em
         account_id,
         employee_id: String::from_str(env, employee_id),
         base_salary: 100_000_0000000, // 100,000 USDC (7 decimals)
+        salary_changes: Vec::new(env),
         tax_jurisdiction: String::from_str(env, "US"),
         benefits: Vec::new(env),
-        payment_history: Vec::new(env),
         is_active: true,
+        is_suspended: false,
+        pay_frequency: PaymentFrequency::Monthly,
+        pay_day_rule: PayDayRule::SameDay,
+        hire_date: env.ledger().timestamp(),
+        termination_date: None,
+        next_payment_date: 0, // Aligned with the payroll schedule on add
     }
 }
 
+fn setup_funded_payroll(env: &Env) -> (PayrollContractImplClient<'_>, Address, Asset) {
+    env.ledger().set_timestamp(START);
+    env.mock_all_auths();
+
+    let contract_id = env.register(PayrollContractImpl, ());
+    let client = PayrollContractImplClient::new(env, &contract_id);
+    let employer = Address::generate(env);
+    let asset = create_funded_asset(env, &contract_id, 1_000_000_0000000);
+    let schedule = TimeSchedule {
+        frequency: PaymentFrequency::Monthly,
+        pay_day_rule: PayDayRule::SameDay,
+        start_date: START,
+        next_payment_date: START + 30 * DAY,
+        end_date: None,
+    };
+
+    client.create_payroll_contract(
+        &employer,
+        &schedule,
+        &asset,
+        &1000_0000000,
+        &Vec::from_array(env, [employer.clone()]),
+        &String::from_str(env, "PAYROLL_001"),
+    );
+
+    (client, employer, asset)
+}
+
+fn payment_history(client: &PayrollContractImplClient<'_>, employee_id: Option<&str>) -> Vec<Payment> {
+    let query = PaymentQuery {
+        employee_id: employee_id.map(|id| String::from_str(&client.env, id)),
+        period_end: None,
+        from_date: None,
+        to_date: None,
+    };
+    client.get_payment_history(&query, &0, &50).payments
+}
+
+fn run_payroll(client: &PayrollContractImplClient<'_>, signer: &Address) -> Vec<String> {
+    client.propose_payroll_run(signer);
+    client.execute_payment(signer)
+}
+
 #[test]
 fn test_create_payroll_contract() {
     let env = create_test_env();
@@ -227,7 +295,7 @@ fn test_get_payment_history() {
     );
 
     // Get global payment history (should be empty initially)
-    let history = client.get_payment_history(&None);
+    let history = payment_history(&client, None);
     assert_eq!(history.len(), 0);
 }
 
@@ -261,4 +329,1726 @@ fn test_update_contract_status() {
     // Verify status was updated
     let status = client.get_payroll_status();
     assert_eq!(status, ContractStatus::Paused);
-} 
\ No newline at end of file
+}
+
+#[test]
+fn test_add_employee_aligns_with_schedule() {
+    let env = create_test_env();
+    let (client, employer, _asset) = setup_funded_payroll(&env);
+
+    // Hired after the first payroll date, so the next monthly date is used
+    let mut employee = create_test_employee(&env, Address::generate(&env), "EMP_001");
+    employee.hire_date = START + 40 * DAY;
+    client.add_employee(&employer, &employee);
+
+    // Payroll runs on the 14th: December 14th, then January 14th
+    let stored = client.get_employees().get(0).unwrap();
+    assert_eq!(stored.next_payment_date, START + 61 * DAY);
+
+    // A same-day rule is pinned to the day of the first payment
+    assert_eq!(stored.pay_day_rule, PayDayRule::DayOfMonth(14));
+}
+
+#[test]
+fn test_add_employee_rejects_unschedulable_pay_dates() {
+    let env = create_test_env();
+    let (client, employer, _asset) = setup_funded_payroll(&env);
+
+    // A day of month that doesn't exist
+    let mut employee = create_test_employee(&env, Address::generate(&env), "EMP_001");
+    employee.pay_day_rule = PayDayRule::DayOfMonth(0);
+    assert_eq!(
+        client.try_add_employee(&employer, &employee),
+        Err(Ok(PayrollError::InvalidPaymentSchedule))
+    );
+
+    // A hire date too many pay periods after the payroll schedule
+    let mut employee = create_test_employee(&env, Address::generate(&env), "EMP_002");
+    employee.pay_frequency = PaymentFrequency::Weekly;
+    employee.hire_date = START + 10_000 * DAY;
+    assert_eq!(
+        client.try_add_employee(&employer, &employee),
+        Err(Ok(PayrollError::InvalidPaymentSchedule))
+    );
+}
+
+#[test]
+fn test_execute_payment_pays_only_due_employees() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+
+    let weekly_account = Address::generate(&env);
+    let mut weekly = create_test_employee(&env, weekly_account.clone(), "EMP_WEEKLY");
+    weekly.pay_frequency = PaymentFrequency::Weekly;
+    weekly.next_payment_date = START + 7 * DAY;
+    client.add_employee(&employer, &weekly);
+
+    let monthly_account = Address::generate(&env);
+    let monthly = create_test_employee(&env, monthly_account.clone(), "EMP_MONTHLY");
+    client.add_employee(&employer, &monthly);
+
+    // Nobody is due yet
+    assert_eq!(
+        client.try_propose_payroll_run(&employer),
+        Err(Ok(PayrollError::PaymentNotDue))
+    );
+
+    // Only the weekly employee is paid after a week
+    env.ledger().set_timestamp(START + 7 * DAY);
+    let payment_ids = run_payroll(&client, &employer);
+    assert_eq!(payment_ids.len(), 1);
+
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&weekly_account), 100_000_0000000 / 52);
+    assert_eq!(token.balance(&monthly_account), 0);
+
+    // Running again in the same week pays nobody
+    assert_eq!(
+        client.try_propose_payroll_run(&employer),
+        Err(Ok(PayrollError::PaymentNotDue))
+    );
+
+    let weekly = client.get_employees().get(0).unwrap();
+    assert_eq!(weekly.next_payment_date, START + 14 * DAY);
+}
+
+#[test]
+fn test_execute_payment_prorates_mid_cycle_hire() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+
+    // Joins with ten days left in the first monthly period
+    let account = Address::generate(&env);
+    let mut employee = create_test_employee(&env, account.clone(), "EMP_001");
+    employee.hire_date = START + 20 * DAY;
+    client.add_employee(&employer, &employee);
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    run_payroll(&client, &employer);
+
+    let token = TokenClient::new(&env, &asset.token);
+    let monthly_gross = 100_000_0000000 / 12;
+    assert_eq!(token.balance(&account), monthly_gross / 3);
+
+    // The following period, ending January 14th, is paid in full
+    env.ledger().set_timestamp(START + 61 * DAY);
+    run_payroll(&client, &employer);
+    assert_eq!(token.balance(&account), monthly_gross / 3 + monthly_gross);
+}
+
+#[test]
+fn test_execute_payment_prorates_final_period() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+
+    // Leaves halfway through the first monthly period
+    let account = Address::generate(&env);
+    let mut employee = create_test_employee(&env, account.clone(), "EMP_001");
+    employee.termination_date = Some(START + 15 * DAY);
+    client.add_employee(&employer, &employee);
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    run_payroll(&client, &employer);
+
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&account), 100_000_0000000 / 12 / 2);
+
+    // No further pay once the final period is settled
+    let stored = client.get_employees().get(0).unwrap();
+    assert!(!stored.is_active);
+    env.ledger().set_timestamp(START + 61 * DAY);
+    assert_eq!(
+        client.try_propose_payroll_run(&employer),
+        Err(Ok(PayrollError::PaymentNotDue))
+    );
+}
+
+#[test]
+fn test_execute_payment_on_last_business_day() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+
+    // Paid on the last business day of each month, starting November 30th 2023
+    let account = Address::generate(&env);
+    let mut employee = create_test_employee(&env, account.clone(), "EMP_001");
+    employee.pay_day_rule = PayDayRule::LastBusinessDay;
+    employee.next_payment_date = date_to_timestamp(2023, 11, 30, 0);
+    client.add_employee(&employer, &employee);
+
+    env.ledger().set_timestamp(date_to_timestamp(2023, 11, 30, 0));
+    run_payroll(&client, &employer);
+
+    // December 31st 2023 is a Sunday, so the next pay day is Friday the 29th
+    let stored = client.get_employees().get(0).unwrap();
+    assert_eq!(stored.next_payment_date, date_to_timestamp(2023, 12, 29, 0));
+
+    env.ledger().set_timestamp(date_to_timestamp(2023, 12, 29, 0));
+    run_payroll(&client, &employer);
+
+    let stored = client.get_employees().get(0).unwrap();
+    assert_eq!(stored.next_payment_date, date_to_timestamp(2024, 1, 31, 0));
+
+    let token = TokenClient::new(&env, &asset.token);
+    assert!(token.balance(&account) > 0);
+}
+
+#[test]
+fn test_execute_payment_uses_unique_payment_ids() {
+    let env = create_test_env();
+    let (client, employer, _asset) = setup_funded_payroll(&env);
+
+    client.add_employee(&employer, &create_test_employee(&env, Address::generate(&env), "EMP_001"));
+    client.add_employee(&employer, &create_test_employee(&env, Address::generate(&env), "EMP_002"));
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    let first_run = run_payroll(&client, &employer);
+    env.ledger().set_timestamp(START + 61 * DAY);
+    let second_run = run_payroll(&client, &employer);
+
+    // Every payment in the history has its own ID and transaction reference
+    let history = payment_history(&client, None);
+    assert_eq!(history.len(), 4);
+    for i in 0..history.len() {
+        for j in (i + 1)..history.len() {
+            let a = history.get(i).unwrap();
+            let b = history.get(j).unwrap();
+            assert_ne!(a.payment_id, b.payment_id);
+            assert_ne!(a.transaction_hash, b.transaction_hash);
+        }
+    }
+    assert_eq!(first_run.get(0).unwrap(), history.get(0).unwrap().payment_id);
+    assert_eq!(second_run.get(1).unwrap(), history.get(3).unwrap().payment_id);
+
+    // IDs are derived from the contract, pay period and employee
+    let expected = generate_payment_id(
+        &env,
+        &String::from_str(&env, "PAYROLL_001"),
+        START + 30 * DAY,
+        &String::from_str(&env, "EMP_001"),
+    );
+    assert_eq!(history.get(0).unwrap().payment_id, expected);
+}
+
+#[test]
+fn test_execute_payment_rejects_duplicate_period() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+
+    let account = Address::generate(&env);
+    client.add_employee(&employer, &create_test_employee(&env, account.clone(), "EMP_001"));
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    run_payroll(&client, &employer);
+
+    // Rewind the employee's schedule so the same period comes due again
+    env.as_contract(&client.address, || {
+        let mut employee = get_employee(&env, &String::from_str(&env, "EMP_001")).unwrap();
+        employee.next_payment_date = START + 30 * DAY;
+        store_employee(&env, &employee);
+    });
+
+    client.propose_payroll_run(&employer);
+    assert_eq!(
+        client.try_execute_payment(&employer),
+        Err(Ok(PayrollError::DuplicatePayment))
+    );
+
+    // The employee was paid exactly once
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&account), 100_000_0000000 / 12);
+}
+
+#[test]
+fn test_tax_withholding_accrues_and_remits() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+    let us = String::from_str(&env, "US");
+    let authority = Address::generate(&env);
+
+    client.set_tax_rate(&employer, &us, &2500);
+    client.add_employee(&employer, &create_test_employee(&env, Address::generate(&env), "EMP_001"));
+    client.add_employee(&employer, &create_test_employee(&env, Address::generate(&env), "EMP_002"));
+
+    // Withholdings accrue to the employee's jurisdiction on every run
+    env.ledger().set_timestamp(START + 30 * DAY);
+    run_payroll(&client, &employer);
+
+    let monthly_tax = (100_000_0000000 / 12) * 2500 / 10000;
+    let liability = client.get_tax_liability(&us);
+    assert_eq!(liability.withheld, 2 * monthly_tax);
+    assert_eq!(liability.outstanding, 2 * monthly_tax);
+    assert_eq!(liability.remitted, 0);
+
+    // Remitting needs a tax authority
+    assert_eq!(
+        client.try_remit_withholdings(&employer, &us),
+        Err(Ok(PayrollError::TaxAuthorityNotSet))
+    );
+    client.set_tax_authority(&employer, &us, &authority);
+
+    let remitted = client.remit_withholdings(&employer, &us);
+    assert_eq!(remitted, 2 * monthly_tax);
+
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&authority), 2 * monthly_tax);
+
+    let liability = client.get_tax_liability(&us);
+    assert_eq!(liability.authority, Some(authority.clone()));
+    assert_eq!(liability.remitted, 2 * monthly_tax);
+    assert_eq!(liability.outstanding, 0);
+
+    // Every remittance is recorded
+    let remittances = client.get_tax_remittances(&us);
+    assert_eq!(remittances.len(), 1);
+    let remittance = remittances.get(0).unwrap();
+    assert_eq!(remittance.authority, authority);
+    assert_eq!(remittance.amount, 2 * monthly_tax);
+
+    // Nothing left to remit
+    assert_eq!(
+        client.try_remit_withholdings(&employer, &us),
+        Err(Ok(PayrollError::InvalidAmount))
+    );
+
+    let liabilities = client.get_tax_liabilities();
+    assert_eq!(liabilities.len(), 1);
+    assert_eq!(liabilities.get(0).unwrap(), liability);
+}
+
+#[test]
+fn test_withheld_tax_is_not_used_for_salaries() {
+    let env = create_test_env();
+    env.ledger().set_timestamp(START);
+    env.mock_all_auths();
+
+    // Funded with exactly one month's gross pay on top of the minimum balance
+    let contract_id = env.register(PayrollContractImpl, ());
+    let client = PayrollContractImplClient::new(&env, &contract_id);
+    let employer = Address::generate(&env);
+    let monthly_gross = 100_000_0000000 / 12;
+    let asset = create_funded_asset(&env, &contract_id, monthly_gross + 1000_0000000);
+    let mut schedule = create_test_payment_schedule(&env);
+    schedule.next_payment_date = START + 30 * DAY;
+    client.create_payroll_contract(
+        &employer,
+        &schedule,
+        &asset,
+        &1000_0000000,
+        &Vec::from_array(&env, [employer.clone()]),
+        &String::from_str(&env, "PAYROLL_001"),
+    );
+    client.set_tax_rate(&employer, &String::from_str(&env, "US"), &2500);
+
+    let account = Address::generate(&env);
+    client.add_employee(&employer, &create_test_employee(&env, account.clone(), "EMP_001"));
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    run_payroll(&client, &employer);
+
+    // The next month fails rather than paying salary out of withheld tax
+    env.ledger().set_timestamp(START + 61 * DAY);
+    client.propose_payroll_run(&employer);
+    assert_eq!(
+        client.try_execute_payment(&employer),
+        Err(Ok(PayrollError::InsufficientBalance))
+    );
+
+    let monthly_tax = monthly_gross * 2500 / 10000;
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&account), monthly_gross - monthly_tax);
+    assert_eq!(token.balance(&contract_id), monthly_tax + 1000_0000000);
+}
+
+#[test]
+fn test_progressive_levies_with_year_to_date_cap() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+    let us = String::from_str(&env, "US");
+
+    // Federal: 0% up to 12,000 then 10%. Social security: 6% on the first 10,000 a year
+    let levies = Vec::from_array(
+        &env,
+        [
+            create_levy(&env, "federal", &[(0, 0), (12_000_0000000, 1000)], None),
+            create_levy(&env, "social_security", &[(0, 600)], Some(10_000_0000000)),
+        ],
+    );
+    client.set_tax_levies(&employer, &us, &levies);
+    assert_eq!(client.get_tax_levies(&us), levies);
+
+    // 72,000 a year, paid monthly
+    let account = Address::generate(&env);
+    let mut employee = create_test_employee(&env, account.clone(), "EMP_001");
+    employee.base_salary = 72_000_0000000;
+    client.add_employee(&employer, &employee);
+
+    // December: federal 6,000 / 12 = 500, social security 6% of 6,000 = 360
+    env.ledger().set_timestamp(START + 30 * DAY);
+    run_payroll(&client, &employer);
+    let payment = payment_history(&client, None).get(0).unwrap();
+    assert_eq!(payment.tax_amount, 500_0000000 + 360_0000000);
+
+    let ytd = client.get_year_to_date(&String::from_str(&env, "EMP_001"));
+    assert_eq!(ytd.year, 2023);
+    assert_eq!(ytd.gross_earnings, 6_000_0000000);
+    assert_eq!(ytd.tax_withheld, payment.tax_amount);
+
+    // January starts a new year, so the social security cap resets
+    env.ledger().set_timestamp(START + 61 * DAY);
+    run_payroll(&client, &employer);
+    let payment = payment_history(&client, None).get(1).unwrap();
+    assert_eq!(payment.tax_amount, 500_0000000 + 360_0000000);
+
+    // February: 6,000 already earned this year, so only 4,000 is under the cap
+    env.ledger().set_timestamp(START + 92 * DAY);
+    run_payroll(&client, &employer);
+    let payment = payment_history(&client, None).get(2).unwrap();
+    assert_eq!(payment.tax_amount, 500_0000000 + 240_0000000);
+
+    let ytd = client.get_year_to_date(&String::from_str(&env, "EMP_001"));
+    assert_eq!(ytd.year, 2024);
+    assert_eq!(ytd.gross_earnings, 12_000_0000000);
+    assert_eq!(ytd.tax_withheld, 860_0000000 + 740_0000000);
+
+    // Levied tax accrues to the jurisdiction like flat-rate tax
+    let liability = client.get_tax_liability(&us);
+    assert_eq!(liability.withheld, 860_0000000 * 2 + 740_0000000);
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&account), 18_000_0000000 - liability.withheld);
+}
+
+#[test]
+fn test_levy_cap_counts_taxable_wages() {
+    let env = create_test_env();
+    let (client, employer, _asset) = setup_funded_payroll(&env);
+    let us = String::from_str(&env, "US");
+
+    // 10% on the first 12,000 of taxable wages a year
+    let levies = Vec::from_array(
+        &env,
+        [create_levy(&env, "social_security", &[(0, 1000)], Some(12_000_0000000))],
+    );
+    client.set_tax_levies(&employer, &us, &levies);
+
+    // 10,000 a month with half deferred pre-tax, so 5,000 a month is taxable
+    let account = Address::generate(&env);
+    let mut employee = create_test_employee(&env, account, "EMP_001");
+    employee.base_salary = 120_000_0000000;
+    employee.benefits = Vec::from_array(
+        &env,
+        [create_test_benefit(
+            &env,
+            BenefitType::Retirement,
+            BenefitTreatment::PreTaxDeduction,
+            BenefitCalculation::BasisPoints,
+            5000,
+            Some(Address::generate(&env)),
+        )],
+    );
+    client.add_employee(&employer, &employee);
+
+    // Pay December, then January and February of the next year
+    for days in [30, 61, 92] {
+        env.ledger().set_timestamp(START + days * DAY);
+        run_payroll(&client, &employer);
+    }
+    let ytd = client.get_year_to_date(&String::from_str(&env, "EMP_001"));
+    assert_eq!(ytd.gross_earnings, 20_000_0000000);
+    assert_eq!(ytd.taxable_wages, 10_000_0000000);
+
+    // March: 10,000 of taxable wages so far, so only 2,000 is under the cap
+    env.ledger().set_timestamp(START + 121 * DAY);
+    run_payroll(&client, &employer);
+    let payment = payment_history(&client, None).get(3).unwrap();
+    assert_eq!(payment.tax_amount, 200_0000000);
+}
+
+#[test]
+fn test_add_employee_requires_deduction_recipient() {
+    let env = create_test_env();
+    let (client, employer, _asset) = setup_funded_payroll(&env);
+
+    let mut employee = create_test_employee(&env, Address::generate(&env), "EMP_001");
+    employee.benefits = Vec::from_array(
+        &env,
+        [create_test_benefit(
+            &env,
+            BenefitType::HealthInsurance,
+            BenefitTreatment::PostTaxDeduction,
+            BenefitCalculation::Fixed,
+            200_0000000,
+            None,
+        )],
+    );
+    assert_eq!(
+        client.try_add_employee(&employer, &employee),
+        Err(Ok(PayrollError::BenefitRecipientRequired))
+    );
+}
+
+#[test]
+fn test_set_tax_levies_rejects_invalid_brackets() {
+    let env = create_test_env();
+    let (client, employer, _asset) = setup_funded_payroll(&env);
+
+    let levies = Vec::from_array(
+        &env,
+        [create_levy(&env, "federal", &[(1, 1000)], None)],
+    );
+    assert_eq!(
+        client.try_set_tax_levies(&employer, &String::from_str(&env, "US"), &levies),
+        Err(Ok(PayrollError::InvalidTaxRate))
+    );
+}
+
+fn create_test_benefit(
+    env: &Env,
+    benefit_type: BenefitType,
+    treatment: BenefitTreatment,
+    calculation: BenefitCalculation,
+    amount: i128,
+    recipient: Option<Address>,
+) -> Benefit {
+    Benefit {
+        benefit_type,
+        amount,
+        description: String::from_str(env, "benefit"),
+        treatment,
+        calculation,
+        recipient,
+    }
+}
+
+#[test]
+fn test_execute_payment_applies_benefits() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+    client.set_tax_rate(&employer, &String::from_str(&env, "US"), &2000);
+
+    let account = Address::generate(&env);
+    let retirement_fund = Address::generate(&env);
+    let insurer = Address::generate(&env);
+
+    // 120,000 a year: 10,000 a month base pay
+    let mut employee = create_test_employee(&env, account.clone(), "EMP_001");
+    employee.base_salary = 120_000_0000000;
+    employee.benefits = Vec::from_array(
+        &env,
+        [
+            // 5% pre-tax retirement contribution
+            create_test_benefit(
+                &env,
+                BenefitType::Retirement,
+                BenefitTreatment::PreTaxDeduction,
+                BenefitCalculation::BasisPoints,
+                500,
+                Some(retirement_fund.clone()),
+            ),
+            // 200 post-tax health insurance premium
+            create_test_benefit(
+                &env,
+                BenefitType::HealthInsurance,
+                BenefitTreatment::PostTaxDeduction,
+                BenefitCalculation::Fixed,
+                200_0000000,
+                Some(insurer.clone()),
+            ),
+            // 1,000 one-off bonus
+            create_test_benefit(
+                &env,
+                BenefitType::Bonus,
+                BenefitTreatment::Earning,
+                BenefitCalculation::Fixed,
+                1_000_0000000,
+                None,
+            ),
+            // 3% employer retirement match
+            create_test_benefit(
+                &env,
+                BenefitType::Retirement,
+                BenefitTreatment::EmployerContribution,
+                BenefitCalculation::BasisPoints,
+                300,
+                Some(retirement_fund.clone()),
+            ),
+            // Vacation days don't affect pay
+            create_test_benefit(
+                &env,
+                BenefitType::VacationDays,
+                BenefitTreatment::NonMonetary,
+                BenefitCalculation::Fixed,
+                20,
+                None,
+            ),
+        ],
+    );
+    client.add_employee(&employer, &employee);
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    run_payroll(&client, &employer);
+
+    // Gross 11,000; pre-tax 500; taxable 10,500; tax 2,100; post-tax 200; net 8,200
+    let payment = payment_history(&client, None).get(0).unwrap();
+    assert_eq!(payment.gross_amount, 11_000_0000000);
+    assert_eq!(payment.pre_tax_deductions, 500_0000000);
+    assert_eq!(payment.tax_amount, 2_100_0000000);
+    assert_eq!(payment.post_tax_deductions, 200_0000000);
+    assert_eq!(payment.net_amount, 8_200_0000000);
+    assert_eq!(payment.employer_contributions, 300_0000000);
+
+    // Each monetary benefit is itemized
+    assert_eq!(payment.line_items.len(), 4);
+    assert_eq!(payment.line_items.get(0).unwrap().amount, 500_0000000);
+    assert_eq!(payment.line_items.get(2).unwrap().benefit_type, BenefitType::Bonus);
+
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&account), 8_200_0000000);
+    assert_eq!(token.balance(&retirement_fund), 500_0000000 + 300_0000000);
+    assert_eq!(token.balance(&insurer), 200_0000000);
+
+    // The bonus is not paid again the following month
+    let stored = client.get_employees().get(0).unwrap();
+    assert_eq!(stored.benefits.len(), 4);
+    env.ledger().set_timestamp(START + 61 * DAY);
+    run_payroll(&client, &employer);
+    let payment = payment_history(&client, None).get(1).unwrap();
+    assert_eq!(payment.gross_amount, 10_000_0000000);
+    assert_eq!(payment.line_items.len(), 3);
+}
+
+#[test]
+fn test_execute_payment_rejects_deductions_above_pay() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+
+    let account = Address::generate(&env);
+    let mut employee = create_test_employee(&env, account.clone(), "EMP_001");
+    employee.benefits = Vec::from_array(
+        &env,
+        [create_test_benefit(
+            &env,
+            BenefitType::HealthInsurance,
+            BenefitTreatment::PostTaxDeduction,
+            BenefitCalculation::Fixed,
+            1_000_000_0000000,
+            Some(Address::generate(&env)),
+        )],
+    );
+    client.add_employee(&employer, &employee);
+    client.set_funding_mode(&employer, &FundingMode::Partial);
+
+    // The payment fails rather than producing negative pay
+    env.ledger().set_timestamp(START + 30 * DAY);
+    let payment_ids = run_payroll(&client, &employer);
+    assert_eq!(payment_ids.len(), 0);
+
+    let failed = client.get_failed_payments().get(0).unwrap();
+    assert_eq!(failed.employee_id, String::from_str(&env, "EMP_001"));
+    assert_eq!(failed.status, PaymentStatus::Failed);
+    assert_eq!(failed.reason, Some(String::from_str(&env, "Deductions exceed pay")));
+
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&account), 0);
+}
+
+
+fn add_signers(env: &Env, client: &PayrollContractImplClient<'_>, count: u32) -> Vec<Address> {
+    let mut signers = Vec::new(env);
+    for _ in 0..count {
+        signers.push_back(Address::generate(env));
+    }
+    env.as_contract(&client.address, || {
+        let mut contract = get_contract(env).unwrap();
+        contract.signers.append(&signers);
+        store_contract(env, &contract);
+    });
+    signers
+}
+
+#[test]
+fn test_payroll_run_requires_approval_threshold() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+    let signers = add_signers(&env, &client, 2);
+    client.set_approval_policy(&employer, &2, &(3 * DAY));
+
+    let account = Address::generate(&env);
+    client.add_employee(&employer, &create_test_employee(&env, account.clone(), "EMP_001"));
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    let proposal_id = client.propose_payroll_run(&employer);
+    assert_eq!(client.get_payroll_status(), ContractStatus::PendingApproval);
+
+    let proposal = client.get_payroll_run_proposal().unwrap();
+    assert_eq!(proposal.total_amount, 100_000_0000000 / 12);
+    assert_eq!(proposal.employee_count, 1);
+    assert_eq!(proposal.approvals.len(), 1);
+
+    // One approval is not enough
+    assert_eq!(
+        client.try_execute_payment(&employer),
+        Err(Ok(PayrollError::ProposalNotApproved))
+    );
+
+    assert_eq!(client.approve_payroll_run(&signers.get(0).unwrap(), &proposal_id), 2);
+    let payment_ids = client.execute_payment(&signers.get(1).unwrap());
+    assert_eq!(payment_ids.len(), 1);
+
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&account), 100_000_0000000 / 12);
+    assert!(client.get_payroll_run_proposal().is_none());
+    assert_eq!(client.get_payroll_status(), ContractStatus::Active);
+}
+
+#[test]
+fn test_payroll_run_approval_errors() {
+    let env = create_test_env();
+    let (client, employer, _) = setup_funded_payroll(&env);
+    add_signers(&env, &client, 1);
+    client.set_approval_policy(&employer, &2, &(3 * DAY));
+
+    let account = Address::generate(&env);
+    client.add_employee(&employer, &create_test_employee(&env, account, "EMP_001"));
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    let proposal_id = client.propose_payroll_run(&employer);
+
+    assert_eq!(
+        client.try_approve_payroll_run(&employer, &proposal_id),
+        Err(Ok(PayrollError::AlreadyApproved))
+    );
+    assert_eq!(
+        client.try_approve_payroll_run(&employer, &(proposal_id + 1)),
+        Err(Ok(PayrollError::ProposalNotFound))
+    );
+    assert_eq!(
+        client.try_approve_payroll_run(&Address::generate(&env), &proposal_id),
+        Err(Ok(PayrollError::Unauthorized))
+    );
+
+    // Only one run can be open at a time
+    assert_eq!(
+        client.try_propose_payroll_run(&employer),
+        Err(Ok(PayrollError::InvalidStatus))
+    );
+}
+
+#[test]
+fn test_payroll_run_proposal_expires() {
+    let env = create_test_env();
+    let (client, employer, _) = setup_funded_payroll(&env);
+    let signers = add_signers(&env, &client, 1);
+    client.set_approval_policy(&employer, &2, &DAY);
+
+    let account = Address::generate(&env);
+    client.add_employee(&employer, &create_test_employee(&env, account, "EMP_001"));
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    let proposal_id = client.propose_payroll_run(&employer);
+
+    env.ledger().set_timestamp(START + 32 * DAY);
+    assert_eq!(
+        client.try_approve_payroll_run(&signers.get(0).unwrap(), &proposal_id),
+        Err(Ok(PayrollError::ProposalExpired))
+    );
+    assert_eq!(
+        client.try_execute_payment(&employer),
+        Err(Ok(PayrollError::ProposalExpired))
+    );
+
+    // An expired proposal can be replaced
+    let new_proposal_id = client.propose_payroll_run(&employer);
+    assert_ne!(new_proposal_id, proposal_id);
+    assert_eq!(client.get_payroll_run_proposal().unwrap().run_date, START + 32 * DAY);
+}
+
+#[test]
+fn test_payroll_run_rejects_outdated_proposal() {
+    let env = create_test_env();
+    let (client, employer, _) = setup_funded_payroll(&env);
+
+    let account = Address::generate(&env);
+    client.add_employee(&employer, &create_test_employee(&env, account, "EMP_001"));
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    client.propose_payroll_run(&employer);
+
+    // A salary change after approval no longer matches the proposed total
+    env.as_contract(&client.address, || {
+        let mut employee = get_employee(&env, &String::from_str(&env, "EMP_001")).unwrap();
+        employee.base_salary = 120_000_0000000;
+        store_employee(&env, &employee);
+    });
+
+    assert_eq!(
+        client.try_execute_payment(&employer),
+        Err(Ok(PayrollError::ProposalOutdated))
+    );
+}
+
+#[test]
+fn test_payroll_run_rejects_changed_payments() {
+    let env = create_test_env();
+    let (client, employer, _) = setup_funded_payroll(&env);
+
+    let account = Address::generate(&env);
+    client.add_employee(&employer, &create_test_employee(&env, account, "EMP_001"));
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    client.propose_payroll_run(&employer);
+
+    // Redirecting pay leaves the total unchanged but not what was approved
+    env.as_contract(&client.address, || {
+        let mut employee = get_employee(&env, &String::from_str(&env, "EMP_001")).unwrap();
+        employee.account_id = Address::generate(&env);
+        store_employee(&env, &employee);
+    });
+
+    assert_eq!(
+        client.try_execute_payment(&employer),
+        Err(Ok(PayrollError::ProposalOutdated))
+    );
+}
+
+#[test]
+fn test_tax_and_status_changes_withdraw_proposal() {
+    let env = create_test_env();
+    let (client, employer, _) = setup_funded_payroll(&env);
+    let us = String::from_str(&env, "US");
+
+    let account = Address::generate(&env);
+    client.add_employee(&employer, &create_test_employee(&env, account, "EMP_001"));
+    env.ledger().set_timestamp(START + 30 * DAY);
+
+    // A new tax rate withdraws the proposal
+    client.propose_payroll_run(&employer);
+    client.set_tax_rate(&employer, &us, &2500);
+    assert!(client.get_payroll_run_proposal().is_none());
+    assert_eq!(client.get_payroll_status(), ContractStatus::Active);
+    assert_eq!(
+        client.try_execute_payment(&employer),
+        Err(Ok(PayrollError::ContractNotActive))
+    );
+
+    // So do new levies
+    client.propose_payroll_run(&employer);
+    let levies = Vec::from_array(&env, [create_levy(&env, "federal", &[(0, 1000)], None)]);
+    client.set_tax_levies(&employer, &us, &levies);
+    assert!(client.get_payroll_run_proposal().is_none());
+    assert_eq!(client.get_payroll_status(), ContractStatus::Active);
+
+    // And a status change
+    client.propose_payroll_run(&employer);
+    client.update_contract_status(&employer, &ContractStatus::Paused);
+    client.update_contract_status(&employer, &ContractStatus::PendingApproval);
+    assert!(client.get_payroll_run_proposal().is_none());
+    assert_eq!(
+        client.try_execute_payment(&employer),
+        Err(Ok(PayrollError::ProposalNotFound))
+    );
+}
+
+#[test]
+fn test_propose_replacing_expired_proposal_requires_active_contract() {
+    let env = create_test_env();
+    let (client, employer, _) = setup_funded_payroll(&env);
+    client.set_approval_policy(&employer, &1, &DAY);
+
+    let account = Address::generate(&env);
+    client.add_employee(&employer, &create_test_employee(&env, account, "EMP_001"));
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    client.propose_payroll_run(&employer);
+
+    // The proposal expires while the contract is paused
+    env.ledger().set_timestamp(START + 32 * DAY);
+    env.as_contract(&client.address, || {
+        let mut contract = get_contract(&env).unwrap();
+        contract.status = ContractStatus::Paused;
+        store_contract(&env, &contract);
+    });
+
+    assert_eq!(
+        client.try_propose_payroll_run(&employer),
+        Err(Ok(PayrollError::ContractNotActive))
+    );
+}
+
+#[test]
+fn test_cancel_payroll_run() {
+    let env = create_test_env();
+    let (client, employer, _) = setup_funded_payroll(&env);
+
+    let account = Address::generate(&env);
+    client.add_employee(&employer, &create_test_employee(&env, account, "EMP_001"));
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    let proposal_id = client.propose_payroll_run(&employer);
+    client.cancel_payroll_run(&employer, &proposal_id);
+
+    assert!(client.get_payroll_run_proposal().is_none());
+    assert_eq!(client.get_payroll_status(), ContractStatus::Active);
+    assert_eq!(
+        client.try_execute_payment(&employer),
+        Err(Ok(PayrollError::ContractNotActive))
+    );
+}
+
+#[test]
+fn test_set_approval_policy_validates_threshold() {
+    let env = create_test_env();
+    let (client, employer, _) = setup_funded_payroll(&env);
+
+    assert_eq!(
+        client.try_set_approval_policy(&employer, &0, &DAY),
+        Err(Ok(PayrollError::InvalidSigner))
+    );
+    assert_eq!(
+        client.try_set_approval_policy(&employer, &2, &DAY),
+        Err(Ok(PayrollError::InvalidSigner))
+    );
+    assert_eq!(
+        client.try_set_approval_policy(&employer, &1, &0),
+        Err(Ok(PayrollError::InvalidPaymentSchedule))
+    );
+}
+
+#[test]
+fn test_preview_payroll_matches_execution() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+    client.set_tax_rate(&employer, &String::from_str(&env, "US"), &2000);
+
+    let account = Address::generate(&env);
+    let insurer = Address::generate(&env);
+    let mut employee = create_test_employee(&env, account.clone(), "EMP_001");
+    employee.base_salary = 120_000_0000000;
+    employee.benefits = Vec::from_array(
+        &env,
+        [create_test_benefit(
+            &env,
+            BenefitType::HealthInsurance,
+            BenefitTreatment::PostTaxDeduction,
+            BenefitCalculation::Fixed,
+            200_0000000,
+            Some(insurer),
+        )],
+    );
+    client.add_employee(&employer, &employee);
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    let preview = client.preview_payroll();
+    assert_eq!(preview.run_date, START + 30 * DAY);
+    assert_eq!(preview.payments.len(), 1);
+    assert_eq!(preview.total_amount, 10_000_0000000);
+    assert_eq!(preview.contract_balance, 1_000_000_0000000);
+    assert_eq!(preview.required_balance, 10_000_0000000 + 1000_0000000);
+    assert!(preview.insufficient_funds.is_empty());
+
+    // Previewing changes nothing
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&account), 0);
+    assert_eq!(payment_history(&client, None).len(), 0);
+    assert_eq!(client.get_employees().get(0).unwrap().next_payment_date, START + 30 * DAY);
+
+    // The run pays exactly what the preview showed
+    run_payroll(&client, &employer);
+    let previewed = preview.payments.get(0).unwrap();
+    let payment = payment_history(&client, None).get(0).unwrap();
+    assert_eq!(previewed.payment_id, payment.payment_id);
+    assert_eq!(previewed.gross_amount, payment.gross_amount);
+    assert_eq!(previewed.tax_amount, payment.tax_amount);
+    assert_eq!(previewed.post_tax_deductions, payment.post_tax_deductions);
+    assert_eq!(previewed.net_amount, payment.net_amount);
+    assert_eq!(previewed.line_items, payment.line_items);
+    assert_eq!(token.balance(&account), previewed.net_amount);
+}
+
+#[test]
+fn test_preview_payroll_reports_insufficient_funds() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+    client.set_funding_mode(&employer, &FundingMode::Partial);
+
+    // 750,000 a month each against a balance of 1,000,000
+    for employee_id in ["EMP_001", "EMP_002"] {
+        let mut employee = create_test_employee(&env, Address::generate(&env), employee_id);
+        employee.base_salary = 9_000_000_0000000;
+        client.add_employee(&employer, &employee);
+    }
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    let preview = client.preview_payroll();
+    assert_eq!(preview.payments.len(), 2);
+    assert_eq!(preview.total_amount, 1_500_000_0000000);
+    assert_eq!(preview.required_balance, 1_500_000_0000000 + 1000_0000000);
+    assert_eq!(
+        preview.insufficient_funds,
+        Vec::from_array(&env, [String::from_str(&env, "EMP_002")])
+    );
+
+    // Execution fails the same employee
+    let payment_ids = run_payroll(&client, &employer);
+    assert_eq!(payment_ids.len(), 1);
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&client.address), 250_000_0000000);
+}
+
+#[test]
+fn test_all_or_nothing_run_requires_full_balance() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+
+    // 600,000 a month each against a balance of 1,000,000
+    for employee_id in ["EMP_001", "EMP_002"] {
+        let mut employee = create_test_employee(&env, Address::generate(&env), employee_id);
+        employee.base_salary = 7_200_000_0000000;
+        client.add_employee(&employer, &employee);
+    }
+
+    // Nobody is paid while the balance is short
+    env.ledger().set_timestamp(START + 30 * DAY);
+    client.propose_payroll_run(&employer);
+    assert_eq!(
+        client.try_execute_payment(&employer),
+        Err(Ok(PayrollError::InsufficientBalance))
+    );
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&client.address), 1_000_000_0000000);
+
+    // The run needs both salaries plus the minimum balance
+    let funder = Address::generate(&env);
+    StellarAssetClient::new(&env, &asset.token).mint(&funder, &1_000_000_0000000);
+    let balance = client.fund_payroll(&funder, &201_000_0000000);
+    assert_eq!(balance, 1_201_000_0000000);
+    assert_eq!(token.balance(&funder), 799_000_0000000);
+
+    let payment_ids = client.execute_payment(&employer);
+    assert_eq!(payment_ids.len(), 2);
+    assert_eq!(token.balance(&client.address), 1000_0000000);
+    assert!(client.get_failed_payments().is_empty());
+}
+
+#[test]
+fn test_partial_run_queues_failed_employees_for_retry() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+    client.set_funding_mode(&employer, &FundingMode::Partial);
+
+    let first = Address::generate(&env);
+    let second = Address::generate(&env);
+    for (account, employee_id) in [(first.clone(), "EMP_001"), (second.clone(), "EMP_002")] {
+        let mut employee = create_test_employee(&env, account, employee_id);
+        employee.base_salary = 7_200_000_0000000;
+        client.add_employee(&employer, &employee);
+    }
+
+    // The first employee is paid and the second's payment is queued
+    env.ledger().set_timestamp(START + 30 * DAY);
+    assert_eq!(run_payroll(&client, &employer).len(), 1);
+    let failed = client.get_failed_payments();
+    assert_eq!(failed.len(), 1);
+    let failed = failed.get(0).unwrap();
+    assert_eq!(failed.employee_id, String::from_str(&env, "EMP_002"));
+    assert_eq!(failed.status, PaymentStatus::Failed);
+    assert_eq!(failed.reason, Some(String::from_str(&env, "Insufficient balance")));
+
+    // Both employees move on to their next period
+    for employee in client.get_employees().iter() {
+        assert_eq!(employee.next_payment_date, START + 61 * DAY);
+    }
+
+    // Retrying without funds keeps the payment queued
+    assert_eq!(client.retry_failed_payments(&employer).len(), 0);
+    assert_eq!(client.get_failed_payments().len(), 1);
+
+    // Once funded, the retry pays the original amount
+    let funder = Address::generate(&env);
+    StellarAssetClient::new(&env, &asset.token).mint(&funder, &1_000_000_0000000);
+    client.fund_payroll(&funder, &1_000_000_0000000);
+    let payment_ids = client.retry_failed_payments(&employer);
+    assert_eq!(payment_ids, Vec::from_array(&env, [failed.payment_id.clone()]));
+    assert!(client.get_failed_payments().is_empty());
+
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&first), 600_000_0000000);
+    assert_eq!(token.balance(&second), 600_000_0000000);
+
+    let history = payment_history(&client, Some("EMP_002"));
+    assert_eq!(history.len(), 1);
+    assert_eq!(history.get(0).unwrap().status, PaymentStatus::Completed);
+    assert_eq!(history.get(0).unwrap().reason, None);
+}
+
+#[test]
+fn test_retry_pays_the_account_approved_with_the_run() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+    client.set_funding_mode(&employer, &FundingMode::Partial);
+    let signer = add_signers(&env, &client, 1).get(0).unwrap();
+    client.set_approval_policy(&employer, &2, &(7 * DAY));
+
+    let account = Address::generate(&env);
+    let mut employee = create_test_employee(&env, account.clone(), "EMP_001");
+    employee.base_salary = 24_000_000_0000000;
+    client.add_employee(&employer, &employee);
+
+    // Both signers approve a run that cannot be funded
+    env.ledger().set_timestamp(START + 30 * DAY);
+    let proposal_id = client.propose_payroll_run(&employer);
+    client.approve_payroll_run(&signer, &proposal_id);
+    client.execute_payment(&employer);
+    let failed = client.get_failed_payments().get(0).unwrap();
+    assert_eq!(failed.recipient, Some(account.clone()));
+
+    // One signer points the employee at another account and retries alone
+    let other_account = Address::generate(&env);
+    let update = EmployeeUpdate {
+        base_salary: None,
+        salary_effective_date: 0,
+        tax_jurisdiction: None,
+        benefits: None,
+        account_id: Some(other_account.clone()),
+    };
+    client.update_employee(&signer, &String::from_str(&env, "EMP_001"), &update);
+    let funder = Address::generate(&env);
+    StellarAssetClient::new(&env, &asset.token).mint(&funder, &10_000_000_0000000);
+    client.fund_payroll(&funder, &10_000_000_0000000);
+    assert_eq!(client.retry_failed_payments(&signer).len(), 1);
+
+    // The payment still goes to the approved account
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&account), failed.net_amount);
+    assert_eq!(token.balance(&other_account), 0);
+    let history = payment_history(&client, Some("EMP_001"));
+    assert_eq!(history.get(0).unwrap().recipient, Some(account));
+}
+
+#[test]
+fn test_cancel_failed_payment() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+    client.set_funding_mode(&employer, &FundingMode::Partial);
+
+    let account = Address::generate(&env);
+    let mut employee = create_test_employee(&env, account.clone(), "EMP_001");
+    employee.base_salary = 24_000_000_0000000;
+    client.add_employee(&employer, &employee);
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    run_payroll(&client, &employer);
+    let failed = client.get_failed_payments().get(0).unwrap();
+
+    let reason = String::from_str(&env, "Paid off-chain");
+    assert_eq!(
+        client.try_cancel_failed_payment(
+            &employer,
+            &String::from_str(&env, "PAY-unknown"),
+            &reason
+        ),
+        Err(Ok(PayrollError::PaymentNotFound))
+    );
+    client.cancel_failed_payment(&employer, &failed.payment_id, &reason);
+
+    // The period is settled as cancelled and never paid
+    assert!(client.get_failed_payments().is_empty());
+    let history = payment_history(&client, Some("EMP_001"));
+    let cancelled = history.get(0).unwrap();
+    assert_eq!(cancelled.payment_id, failed.payment_id);
+    assert_eq!(cancelled.status, PaymentStatus::Cancelled);
+    assert_eq!(cancelled.reason, Some(reason));
+    assert_eq!(payment_history(&client, None).len(), 1);
+
+    let funder = Address::generate(&env);
+    StellarAssetClient::new(&env, &asset.token).mint(&funder, &10_000_000_0000000);
+    client.fund_payroll(&funder, &10_000_000_0000000);
+    assert_eq!(client.retry_failed_payments(&employer).len(), 0);
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&account), 0);
+}
+
+#[test]
+fn test_fund_payroll_rejects_invalid_amount() {
+    let env = create_test_env();
+    let (client, _, _) = setup_funded_payroll(&env);
+
+    assert_eq!(
+        client.try_fund_payroll(&Address::generate(&env), &0),
+        Err(Ok(PayrollError::InvalidAmount))
+    );
+}
+
+#[test]
+fn test_payment_history_pagination_and_filters() {
+    let env = create_test_env();
+    let (client, employer, _) = setup_funded_payroll(&env);
+
+    for employee_id in ["EMP_001", "EMP_002"] {
+        client.add_employee(
+            &employer,
+            &create_test_employee(&env, Address::generate(&env), employee_id),
+        );
+    }
+
+    // Three monthly runs: December, January and February
+    for days in [30, 61, 92] {
+        env.ledger().set_timestamp(START + days * DAY);
+        run_payroll(&client, &employer);
+    }
+
+    let mut query = PaymentQuery {
+        employee_id: None,
+        period_end: None,
+        from_date: None,
+        to_date: None,
+    };
+
+    // Pages follow on from the returned cursor
+    let first_page = client.get_payment_history(&query, &0, &4);
+    assert_eq!(first_page.payments.len(), 4);
+    assert_eq!(first_page.next_cursor, Some(4));
+    let second_page = client.get_payment_history(&query, &4, &4);
+    assert_eq!(second_page.payments.len(), 2);
+    assert_eq!(second_page.next_cursor, None);
+    assert_eq!(
+        client.try_get_payment_history(&query, &0, &0),
+        Err(Ok(PayrollError::InvalidAmount))
+    );
+
+    // By pay period
+    query.period_end = Some(START + 30 * DAY);
+    assert_eq!(client.get_payment_history(&query, &0, &10).payments.len(), 2);
+
+    // By date range
+    query.period_end = None;
+    query.from_date = Some(START + 61 * DAY);
+    query.to_date = Some(START + 92 * DAY);
+    assert_eq!(client.get_payment_history(&query, &0, &10).payments.len(), 4);
+
+    // By employee
+    query.from_date = None;
+    query.to_date = None;
+    query.employee_id = Some(String::from_str(&env, "EMP_002"));
+    let page = client.get_payment_history(&query, &0, &10);
+    assert_eq!(page.payments.len(), 3);
+    for payment in page.payments.iter() {
+        assert_eq!(payment.employee_id, String::from_str(&env, "EMP_002"));
+    }
+
+    query.employee_id = Some(String::from_str(&env, "EMP_404"));
+    assert_eq!(
+        client.try_get_payment_history(&query, &0, &10),
+        Err(Ok(PayrollError::EmployeeNotFound))
+    );
+
+    // Single payments by ID
+    let payment = page.payments.get(0).unwrap();
+    assert_eq!(client.get_payment(&payment.payment_id), payment);
+    assert_eq!(
+        client.try_get_payment(&String::from_str(&env, "PAY-unknown")),
+        Err(Ok(PayrollError::PaymentNotFound))
+    );
+}
+
+#[test]
+fn test_payment_history_caps_payments_scanned() {
+    // Hundreds of payments would make an oversized snapshot
+    let env = Env::new_with_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    });
+    let (client, employer, _) = setup_funded_payroll(&env);
+
+    let account = Address::generate(&env);
+    client.add_employee(&employer, &create_test_employee(&env, account, "EMP_001"));
+    env.ledger().set_timestamp(START + 30 * DAY);
+    run_payroll(&client, &employer);
+
+    // 250 older pay periods follow the December payment in the history
+    env.cost_estimate().budget().reset_unlimited();
+    let december = payment_history(&client, None).get(0).unwrap();
+    env.as_contract(&client.address, || {
+        for period_end in 0..250u64 {
+            let mut payment = december.clone();
+            payment.payment_id = generate_payment_id(
+                &env,
+                &String::from_str(&env, "PAYROLL_001"),
+                period_end,
+                &payment.employee_id,
+            );
+            payment.period_end = period_end;
+            add_payment(&env, &payment);
+        }
+    });
+    env.cost_estimate().budget().reset_default();
+
+    // Only December matches, so the first page stops after 200 payments
+    let query = PaymentQuery {
+        employee_id: None,
+        period_end: Some(START + 30 * DAY),
+        from_date: None,
+        to_date: None,
+    };
+    let page = client.get_payment_history(&query, &0, &50);
+    assert_eq!(page.payments, Vec::from_array(&env, [december]));
+    assert_eq!(page.next_cursor, Some(200));
+
+    let page = client.get_payment_history(&query, &200, &50);
+    assert_eq!(page.payments.len(), 0);
+    assert_eq!(page.next_cursor, None);
+}
+
+#[test]
+fn test_employee_statement_totals_by_year() {
+    let env = create_test_env();
+    let (client, employer, _) = setup_funded_payroll(&env);
+    client.set_tax_rate(&employer, &String::from_str(&env, "US"), &2000);
+
+    let account = Address::generate(&env);
+    client.add_employee(&employer, &create_test_employee(&env, account.clone(), "EMP_001"));
+
+    for days in [30, 61, 92] {
+        env.ledger().set_timestamp(START + days * DAY);
+        run_payroll(&client, &employer);
+    }
+
+    let monthly_gross = 100_000_0000000 / 12;
+    let monthly_tax = monthly_gross * 2000 / 10000;
+    let employee_id = String::from_str(&env, "EMP_001");
+
+    // December 2023
+    let statement = client.get_employee_statement(&employee_id, &2023);
+    assert_eq!(statement.account_id, account);
+    assert_eq!(statement.year_to_date.payment_count, 1);
+    assert_eq!(statement.year_to_date.gross_earnings, monthly_gross);
+
+    // January and February 2024
+    let statement = client.get_employee_statement(&employee_id, &2024);
+    let ytd = statement.year_to_date;
+    assert_eq!(ytd.year, 2024);
+    assert_eq!(ytd.payment_count, 2);
+    assert_eq!(ytd.gross_earnings, 2 * monthly_gross);
+    assert_eq!(ytd.tax_withheld, 2 * monthly_tax);
+    assert_eq!(ytd.net_pay, 2 * (monthly_gross - monthly_tax));
+}
+
+fn legacy_payment(
+    env: &Env,
+    employee_id: &str,
+    gross_amount: i128,
+    payment_date: u64,
+) -> LegacyPayment {
+    LegacyPayment {
+        payment_id: String::from_str(env, "PAY"),
+        employee_id: String::from_str(env, employee_id),
+        gross_amount,
+        tax_amount: 0,
+        net_amount: gross_amount,
+        payment_date,
+        status: PaymentStatus::Completed,
+        transaction_hash: Some(String::from_str(env, "TX")),
+    }
+}
+
+fn legacy_employee(env: &Env, employee_id: &str, base_salary: i128) -> LegacyEmployee {
+    LegacyEmployee {
+        account_id: Address::generate(env),
+        employee_id: String::from_str(env, employee_id),
+        base_salary,
+        tax_jurisdiction: String::from_str(env, "US"),
+        benefits: Vec::new(env),
+        payment_history: Vec::new(env),
+        is_active: true,
+    }
+}
+
+#[test]
+fn test_migrate_storage_from_legacy_layout() {
+    let env = create_test_env();
+    env.ledger().set_timestamp(START);
+    env.mock_all_auths();
+
+    let contract_id = env.register(PayrollContractImpl, ());
+    let client = PayrollContractImplClient::new(&env, &contract_id);
+    let employer = Address::generate(&env);
+    let asset = create_funded_asset(&env, &contract_id, 1_000_000_0000000);
+    let created_at = START - 90 * DAY;
+    let (october, november) = (START - 30 * DAY, START);
+
+    // A deployment from before versioning: employees embedded in the contract and
+    // stored separately, each with their payments, plus a global payment history.
+    // EMP_003 was removed, so only the separate record and the payments remain.
+    let mut first = legacy_employee(&env, "EMP_001", 120_000_0000000);
+    first.benefits = Vec::from_array(
+        &env,
+        [LegacyBenefit {
+            benefit_type: BenefitType::HealthInsurance,
+            amount: 200_0000000,
+            description: String::from_str(&env, "Health plan"),
+        }],
+    );
+    let mut second = legacy_employee(&env, "EMP_002", 60_000_0000000);
+    let mut removed = legacy_employee(&env, "EMP_003", 60_000_0000000);
+    removed.is_active = false;
+
+    let legacy_history = Vec::from_array(
+        &env,
+        [
+            legacy_payment(&env, "EMP_001", 10_000_0000000, october),
+            legacy_payment(&env, "EMP_003", 5_000_0000000, october),
+            legacy_payment(&env, "EMP_001", 10_000_0000000, november),
+            legacy_payment(&env, "EMP_002", 5_000_0000000, november),
+        ],
+    );
+
+    // The contract only embeds the employees as they were when added
+    let mut embedded_first = first.clone();
+    embedded_first.base_salary = 90_000_0000000;
+    let legacy = LegacyPayrollContract {
+        employer_account: employer.clone(),
+        payment_schedule: LegacyTimeSchedule {
+            frequency: PaymentFrequency::Monthly,
+            start_date: created_at,
+            next_payment_date: START + 30 * DAY,
+            end_date: None,
+        },
+        employees: Vec::from_array(&env, [embedded_first, second.clone()]),
+        tax_rates: Map::from_array(&env, [(String::from_str(&env, "US"), 0)]),
+        payment_token: asset.clone(),
+        minimum_balance: 1000_0000000,
+        signers: Vec::from_array(&env, [employer.clone()]),
+        status: ContractStatus::Active,
+        contract_id: String::from_str(&env, "PAYROLL_001"),
+        created_at,
+    };
+
+    first.payment_history = Vec::from_array(
+        &env,
+        [legacy_history.get(0).unwrap(), legacy_history.get(2).unwrap()],
+    );
+    second.payment_history = Vec::from_array(&env, [legacy_history.get(3).unwrap()]);
+    removed.payment_history = Vec::from_array(&env, [legacy_history.get(1).unwrap()]);
+
+    env.as_contract(&contract_id, || {
+        let storage = env.storage().persistent();
+        storage.set(&DataKey::Contract, &legacy);
+        for employee in [&first, &second, &removed] {
+            storage.set(&DataKey::Employee(employee.employee_id.clone()), employee);
+        }
+        storage.set(&LegacyDataKey::PaymentHistory, &legacy_history);
+        storage.set(&LegacyDataKey::NextPaymentId, &4u64);
+        storage.set(&DataKey::TotalEmployees, &2u32);
+    });
+
+    assert_eq!(
+        client.try_get_employees(),
+        Err(Ok(PayrollError::MigrationRequired))
+    );
+    assert_eq!(
+        client.try_migrate_storage(&Address::generate(&env)),
+        Err(Ok(PayrollError::Unauthorized))
+    );
+
+    assert_eq!(client.migrate_storage(&employer), 2);
+    assert_eq!(
+        client.try_migrate_storage(&employer),
+        Err(Ok(PayrollError::InvalidStatus))
+    );
+
+    // The separately stored records replace the embedded copies, and everyone
+    // is put on the contract's schedule with its day of the month
+    let employees = client.get_employees();
+    assert_eq!(employees.len(), 2);
+    let employee = employees.get(0).unwrap();
+    assert_eq!(employee.base_salary, 120_000_0000000);
+    assert_eq!(employee.account_id, first.account_id);
+    assert_eq!(employee.pay_frequency, PaymentFrequency::Monthly);
+    assert_eq!(employee.pay_day_rule, PayDayRule::DayOfMonth(14));
+    assert_eq!(employee.hire_date, created_at);
+    assert_eq!(employee.next_payment_date, START + 30 * DAY);
+    let benefit = employee.benefits.get(0).unwrap();
+    assert_eq!(benefit.treatment, BenefitTreatment::NonMonetary);
+    assert_eq!(benefit.amount, 200_0000000);
+
+    // Payments keep their order under new IDs, without the duplicates from
+    // the employee histories
+    let payments = payment_history(&client, None);
+    assert_eq!(payments.len(), 4);
+    for (payment, legacy_payment) in payments.iter().zip(legacy_history.iter()) {
+        assert_eq!(payment.employee_id, legacy_payment.employee_id);
+        assert_eq!(payment.payment_date, legacy_payment.payment_date);
+        assert_eq!(payment.net_amount, legacy_payment.net_amount);
+        assert_eq!(client.get_payment(&payment.payment_id), payment);
+    }
+    assert_eq!(payment_history(&client, Some("EMP_001")).len(), 2);
+    assert_eq!(payment_history(&client, Some("EMP_003")).len(), 1);
+    assert_eq!(client.get_year_to_date(&String::from_str(&env, "EMP_001")).payment_count, 2);
+
+    // The removed employee's record is migrated but stays off the payroll
+    env.as_contract(&contract_id, || {
+        let employee = get_employee(&env, &String::from_str(&env, "EMP_003")).unwrap();
+        assert!(!employee.is_active);
+
+        let storage = env.storage().persistent();
+        assert!(!storage.has(&LegacyDataKey::PaymentHistory));
+        assert!(!storage.has(&LegacyDataKey::NextPaymentId));
+    });
+
+    // New settings take the defaults of a new contract
+    assert_eq!(client.get_payroll_status(), ContractStatus::Active);
+    env.ledger().set_timestamp(START + 30 * DAY);
+    assert_eq!(run_payroll(&client, &employer).len(), 2);
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&first.account_id), 120_000_0000000 / 12);
+}
+
+fn employee_changes(client: &PayrollContractImplClient<'_>, employee_id: &str) -> Vec<EmployeeChange> {
+    let employee_id = String::from_str(&client.env, employee_id);
+    client.get_employee_changes(&employee_id, &0, &50).changes
+}
+
+#[test]
+fn test_update_employee_splits_pay_at_salary_change() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+
+    let account = Address::generate(&env);
+    client.add_employee(&employer, &create_test_employee(&env, account.clone(), "EMP_001"));
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    run_payroll(&client, &employer);
+
+    // A raise half way through the 31-day period ending on January 14th,
+    // paid to a new account under a new jurisdiction
+    let new_account = Address::generate(&env);
+    let update = EmployeeUpdate {
+        base_salary: Some(200_000_0000000),
+        salary_effective_date: START + 45 * DAY,
+        tax_jurisdiction: Some(String::from_str(&env, "CA")),
+        benefits: None,
+        account_id: Some(new_account.clone()),
+    };
+    let employee_id = String::from_str(&env, "EMP_001");
+    client.update_employee(&employer, &employee_id, &update);
+
+    // Nothing changes until the period is paid
+    let stored = client.get_employees().get(0).unwrap();
+    assert_eq!(stored.base_salary, 100_000_0000000);
+    assert_eq!(stored.salary_changes.len(), 1);
+
+    env.ledger().set_timestamp(START + 61 * DAY);
+    run_payroll(&client, &employer);
+
+    let old_monthly = 100_000_0000000 / 12;
+    let new_monthly = 200_000_0000000 / 12;
+    let split_pay = (old_monthly * (15 * DAY) as i128) / (31 * DAY) as i128
+        + (new_monthly * (16 * DAY) as i128) / (31 * DAY) as i128;
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&new_account), split_pay);
+
+    // The new salary is the base salary from the next period
+    let stored = client.get_employees().get(0).unwrap();
+    assert_eq!(stored.base_salary, 200_000_0000000);
+    assert_eq!(stored.salary_changes.len(), 0);
+
+    env.ledger().set_timestamp(START + 92 * DAY);
+    run_payroll(&client, &employer);
+    assert_eq!(token.balance(&new_account), split_pay + new_monthly);
+
+    let changes = employee_changes(&client, "EMP_001");
+    assert_eq!(changes.len(), 4);
+    assert_eq!(changes.get(0).unwrap().kind, EmployeeChangeKind::Hired);
+    let salary_change = changes.get(1).unwrap();
+    assert_eq!(
+        salary_change.kind,
+        EmployeeChangeKind::SalaryChanged(100_000_0000000, 200_000_0000000)
+    );
+    assert_eq!(salary_change.effective_date, START + 45 * DAY);
+    assert_eq!(salary_change.changed_by, employer);
+    assert_eq!(
+        changes.get(3).unwrap().kind,
+        EmployeeChangeKind::AccountChanged(account, new_account)
+    );
+    assert_eq!(
+        client.try_get_employee_changes(&employee_id, &0, &0),
+        Err(Ok(PayrollError::InvalidAmount))
+    );
+
+    // Salaries must stay positive
+    let update = EmployeeUpdate {
+        base_salary: Some(0),
+        salary_effective_date: START + 92 * DAY,
+        tax_jurisdiction: None,
+        benefits: None,
+        account_id: None,
+    };
+    assert_eq!(
+        client.try_update_employee(&employer, &employee_id, &update),
+        Err(Ok(PayrollError::InvalidAmount))
+    );
+}
+
+#[test]
+fn test_suspended_employee_is_skipped_until_resumed() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+
+    let first = Address::generate(&env);
+    let second = Address::generate(&env);
+    client.add_employee(&employer, &create_test_employee(&env, first.clone(), "EMP_001"));
+    client.add_employee(&employer, &create_test_employee(&env, second.clone(), "EMP_002"));
+
+    let employee_id = String::from_str(&env, "EMP_002");
+    client.suspend_employee(&employer, &employee_id);
+    assert_eq!(
+        client.try_suspend_employee(&employer, &employee_id),
+        Err(Ok(PayrollError::InvalidStatus))
+    );
+
+    // The suspended employee's period passes unpaid
+    env.ledger().set_timestamp(START + 30 * DAY);
+    assert_eq!(run_payroll(&client, &employer).len(), 1);
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&second), 0);
+    let stored = client.get_employees().get(1).unwrap();
+    assert!(stored.is_active);
+    assert_eq!(stored.next_payment_date, START + 61 * DAY);
+
+    client.resume_employee(&employer, &employee_id);
+    env.ledger().set_timestamp(START + 61 * DAY);
+    assert_eq!(run_payroll(&client, &employer).len(), 2);
+    assert_eq!(token.balance(&second), 100_000_0000000 / 12);
+
+    let changes = employee_changes(&client, "EMP_002");
+    assert_eq!(changes.get(1).unwrap().kind, EmployeeChangeKind::Suspended);
+    assert_eq!(changes.get(2).unwrap().kind, EmployeeChangeKind::Resumed);
+}
+
+#[test]
+fn test_terminate_employee_pays_final_paycheck_with_vacation() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+
+    let account = Address::generate(&env);
+    let mut employee = create_test_employee(&env, account.clone(), "EMP_001");
+    employee.benefits = Vec::from_array(
+        &env,
+        [create_test_benefit(
+            &env,
+            BenefitType::VacationDays,
+            BenefitTreatment::NonMonetary,
+            BenefitCalculation::Fixed,
+            10,
+            None,
+        )],
+    );
+    client.add_employee(&employer, &employee);
+
+    env.ledger().set_timestamp(START + 30 * DAY);
+    run_payroll(&client, &employer);
+
+    // Termination can't be dated in the future
+    env.ledger().set_timestamp(START + 40 * DAY);
+    let employee_id = String::from_str(&env, "EMP_001");
+    assert_eq!(
+        client.try_terminate_employee(&employer, &employee_id, &(START + 41 * DAY)),
+        Err(Ok(PayrollError::InvalidPaymentSchedule))
+    );
+
+    // Ten days of the 31-day period plus ten unused vacation days
+    let final_payment = client
+        .terminate_employee(&employer, &employee_id, &(START + 40 * DAY))
+        .unwrap();
+    let monthly = 100_000_0000000 / 12;
+    let prorated_pay = (monthly * (10 * DAY) as i128) / (31 * DAY) as i128;
+    let vacation_payout = 100_000_0000000 * 10 / 260;
+    assert_eq!(final_payment.gross_amount, prorated_pay + vacation_payout);
+    assert_eq!(final_payment.period_end, START + 61 * DAY);
+    let payout = final_payment.line_items.get(0).unwrap();
+    assert_eq!(payout.benefit_type, BenefitType::VacationDays);
+    assert_eq!(payout.amount, vacation_payout);
+
+    // It is paid by the next run, before the period's usual pay date
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&account), monthly);
+    let payment_ids = run_payroll(&client, &employer);
+    assert_eq!(payment_ids, Vec::from_array(&env, [final_payment.payment_id.clone()]));
+    assert_eq!(token.balance(&account), monthly + final_payment.net_amount);
+    let paid = client.get_payment(&final_payment.payment_id);
+    assert_eq!(paid.status, PaymentStatus::Completed);
+    assert_eq!(paid.net_amount, final_payment.net_amount);
+
+    // The record is kept but nothing more is paid
+    let stored = client.get_employees().get(0).unwrap();
+    assert!(!stored.is_active);
+    assert_eq!(stored.termination_date, Some(START + 40 * DAY));
+    assert_eq!(
+        client.try_terminate_employee(&employer, &employee_id, &(START + 40 * DAY)),
+        Err(Ok(PayrollError::InvalidEmployee))
+    );
+
+    let changes = employee_changes(&client, "EMP_001");
+    let termination = changes.get(1).unwrap();
+    assert_eq!(
+        termination.kind,
+        EmployeeChangeKind::Terminated(Some(final_payment.payment_id))
+    );
+    assert_eq!(termination.effective_date, START + 40 * DAY);
+}
+
+#[test]
+fn test_termination_payout_needs_run_approval() {
+    let env = create_test_env();
+    let (client, employer, asset) = setup_funded_payroll(&env);
+    let signer = add_signers(&env, &client, 1).get(0).unwrap();
+    client.set_approval_policy(&employer, &2, &(7 * DAY));
+
+    let account = Address::generate(&env);
+    client.add_employee(&employer, &create_test_employee(&env, account, "EMP_001"));
+
+    // One signer redirects the pay, adds a large vacation balance and terminates
+    env.ledger().set_timestamp(START + 10 * DAY);
+    let employee_id = String::from_str(&env, "EMP_001");
+    let own_account = Address::generate(&env);
+    let update = EmployeeUpdate {
+        base_salary: None,
+        salary_effective_date: 0,
+        tax_jurisdiction: None,
+        benefits: Some(Vec::from_array(
+            &env,
+            [create_test_benefit(
+                &env,
+                BenefitType::VacationDays,
+                BenefitTreatment::NonMonetary,
+                BenefitCalculation::Fixed,
+                10_000,
+                None,
+            )],
+        )),
+        account_id: Some(own_account.clone()),
+    };
+    client.update_employee(&signer, &employee_id, &update);
+    let final_payment = client
+        .terminate_employee(&signer, &employee_id, &(START + 10 * DAY))
+        .unwrap();
+    assert!(final_payment.net_amount > 0);
+
+    // Nothing moves until a second signer approves the run
+    let token = TokenClient::new(&env, &asset.token);
+    assert_eq!(token.balance(&own_account), 0);
+    let proposal_id = client.propose_payroll_run(&signer);
+    assert_eq!(
+        client.try_execute_payment(&signer),
+        Err(Ok(PayrollError::ProposalNotApproved))
+    );
+    assert_eq!(token.balance(&own_account), 0);
+
+    // The other signer sees the payout in the proposal and cancels it
+    let proposal = client.get_payroll_run_proposal().unwrap();
+    assert_eq!(
+        proposal.total_amount,
+        final_payment.gross_amount + final_payment.employer_contributions
+    );
+    client.cancel_payroll_run(&employer, &proposal_id);
+    assert_eq!(token.balance(&own_account), 0);
+    assert!(client.get_employees().get(0).unwrap().is_active);
+}
\ No newline at end of file
@@ -0,0 +1,25 @@
+#![cfg(test)]
+
+use crate::types::{TaxBracket, TaxLevy};
+use soroban_sdk::{Env, String, Vec};
+
+/// Build a levy from `(threshold, rate)` bracket pairs
+pub(crate) fn create_levy(
+    env: &Env,
+    name: &str,
+    brackets: &[(i128, i128)],
+    annual_cap: Option<i128>,
+) -> TaxLevy {
+    let mut bracket_list = Vec::new(env);
+    for (threshold, rate) in brackets {
+        bracket_list.push_back(TaxBracket {
+            threshold: *threshold,
+            rate: *rate,
+        });
+    }
+    TaxLevy {
+        name: String::from_str(env, name),
+        brackets: bracket_list,
+        annual_cap,
+    }
+}
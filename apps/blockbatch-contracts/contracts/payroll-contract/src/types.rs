@@ -1,4 +1,4 @@
-use soroban_sdk::{contracterror, contracttype, Address, Map, String, Vec};
+use soroban_sdk::{contracterror, contracttype, Address, BytesN, Map, String, Vec};
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -9,6 +9,14 @@ pub enum ContractStatus {
     PendingApproval,
 }
 
+/// What a payroll run does when the balance can't cover every payment
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FundingMode {
+    AllOrNothing, // Reject the run unless every payment can be made
+    Partial,      // Pay who can be paid and queue the rest for retry
+}
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum PaymentFrequency {
@@ -17,12 +25,24 @@ pub enum PaymentFrequency {
     Monthly,
     Quarterly,
     Annually,
+    SemiMonthly, // On the 15th and the last day of each month
+}
+
+/// Which day of the month a monthly, quarterly or annual payment falls on
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PayDayRule {
+    SameDay,         // Same day as the previous payment, clamped to the month length
+    DayOfMonth(u32), // Fixed day (1-31), clamped to the month length
+    LastDayOfMonth,
+    LastBusinessDay, // Last Monday to Friday of the month
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct TimeSchedule {
     pub frequency: PaymentFrequency,
+    pub pay_day_rule: PayDayRule,
     pub start_date: u64, // Unix timestamp
     pub next_payment_date: u64, // Unix timestamp
     pub end_date: Option<u64>, // Optional end date
@@ -39,12 +59,33 @@ pub enum BenefitType {
     Other(String),
 }
 
+/// How a benefit affects an employee's pay
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BenefitTreatment {
+    PreTaxDeduction,      // Taken from gross pay before tax, e.g. retirement contributions
+    PostTaxDeduction,     // Taken from pay after tax
+    Earning,              // One-off addition to gross pay, removed once paid, e.g. a bonus
+    EmployerContribution, // Paid by the employer on top of the employee's pay
+    NonMonetary,          // Recorded only, e.g. vacation or sick leave allowances
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BenefitCalculation {
+    Fixed,       // Amount in stroops per pay period
+    BasisPoints, // Share of the period's base pay (10000 = 100%)
+}
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Benefit {
     pub benefit_type: BenefitType,
     pub amount: i128, // Amount in stroops or percentage (basis points)
     pub description: String,
+    pub treatment: BenefitTreatment,
+    pub calculation: BenefitCalculation,
+    pub recipient: Option<Address>, // Provider deductions and contributions are paid to; required for them
 }
 
 #[contracttype]
@@ -56,17 +97,44 @@ pub enum PaymentStatus {
     Cancelled,
 }
 
+/// One benefit's effect on a payment
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PaymentLineItem {
+    pub benefit_type: BenefitType,
+    pub treatment: BenefitTreatment,
+    pub description: String,
+    pub amount: i128,
+    pub recipient: Option<Address>,
+}
+
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Payment {
     pub payment_id: String,
     pub employee_id: String,
-    pub gross_amount: i128,
+    pub recipient: Option<Address>, // Account the net pay goes to, fixed when the run is approved
+    pub period_end: u64,    // End of the pay period the payment covers
+    pub gross_amount: i128, // Base pay plus one-off earnings
+    pub pre_tax_deductions: i128,
     pub tax_amount: i128,
+    pub post_tax_deductions: i128,
     pub net_amount: i128,
+    pub employer_contributions: i128,
+    pub line_items: Vec<PaymentLineItem>,
     pub payment_date: u64, // Unix timestamp
     pub status: PaymentStatus,
+    pub reason: Option<String>, // Why the payment failed or was cancelled
     pub transaction_hash: Option<String>,
+    pub ledger_sequence: u32, // Ledger the payment settled in
+}
+
+/// A salary change that takes effect part way through a later pay period
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SalaryChange {
+    pub base_salary: i128,   // New annual salary in stroops
+    pub effective_date: u64, // Unix timestamp
 }
 
 #[contracttype]
@@ -75,10 +143,61 @@ pub struct Employee {
     pub account_id: Address,
     pub employee_id: String,
     pub base_salary: i128, // Annual salary in stroops
+    pub salary_changes: Vec<SalaryChange>, // Pending, oldest first; applied once their period is settled
     pub tax_jurisdiction: String,
     pub benefits: Vec<Benefit>,
-    pub payment_history: Vec<Payment>,
     pub is_active: bool,
+    pub is_suspended: bool, // Kept on the payroll but not paid
+    pub pay_frequency: PaymentFrequency,
+    pub pay_day_rule: PayDayRule,
+    pub hire_date: u64, // Unix timestamp
+    pub termination_date: Option<u64>, // Unix timestamp, set when the employee leaves
+    pub next_payment_date: u64, // End of the current pay period (Unix timestamp)
+}
+
+/// Changes to an employee's record. Fields left as `None` are unchanged.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EmployeeUpdate {
+    pub base_salary: Option<i128>,
+    pub salary_effective_date: u64, // When a new base salary starts to accrue
+    pub tax_jurisdiction: Option<String>,
+    pub benefits: Option<Vec<Benefit>>,
+    pub account_id: Option<Address>, // Account net pay is sent to
+}
+
+/// What changed in an employee's record, with the previous and new values
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EmployeeChangeKind {
+    Hired,
+    SalaryChanged(i128, i128),
+    JurisdictionChanged(String, String),
+    BenefitsChanged(Vec<Benefit>, Vec<Benefit>),
+    AccountChanged(Address, Address),
+    Suspended,
+    Resumed,
+    Terminated(Option<String>), // ID of the final payment, if anything was owed
+    Removed,
+}
+
+/// An entry in an employee's change log
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EmployeeChange {
+    pub employee_id: String,
+    pub kind: EmployeeChangeKind,
+    pub changed_by: Address,
+    pub changed_at: u64,     // Unix timestamp
+    pub effective_date: u64, // Unix timestamp
+}
+
+/// A page of an employee's change log
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EmployeeChangePage {
+    pub changes: Vec<EmployeeChange>,
+    pub next_cursor: Option<u32>, // Cursor for the next page, if there is one
 }
 
 #[contracttype]
@@ -94,16 +213,191 @@ pub struct Asset {
 pub struct PayrollContract {
     pub employer_account: Address,
     pub payment_schedule: TimeSchedule,
-    pub employees: Vec<Employee>,
+    pub employee_ids: Vec<String>, // Employee records are stored under DataKey::Employee
     pub tax_rates: Map<String, i128>, // Tax rates as basis points (10000 = 100%)
     pub payment_token: Asset,
     pub minimum_balance: i128, // Minimum balance required in stroops
     pub signers: Vec<Address>,
+    pub approval_threshold: u32, // Signer approvals needed to execute a payroll run
+    pub proposal_ttl: u64, // Seconds a payroll run proposal stays open
+    pub funding_mode: FundingMode,
     pub status: ContractStatus,
     pub contract_id: String,
     pub created_at: u64,
 }
 
+/// Contract layout from before storage versioning, with every employee embedded.
+/// The legacy types are only read by `migrate_storage`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LegacyPayrollContract {
+    pub employer_account: Address,
+    pub payment_schedule: LegacyTimeSchedule,
+    pub employees: Vec<LegacyEmployee>,
+    pub tax_rates: Map<String, i128>,
+    pub payment_token: Asset,
+    pub minimum_balance: i128,
+    pub signers: Vec<Address>,
+    pub status: ContractStatus,
+    pub contract_id: String,
+    pub created_at: u64,
+}
+
+/// Payment schedule from before pay day rules
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LegacyTimeSchedule {
+    pub frequency: PaymentFrequency,
+    pub start_date: u64,
+    pub next_payment_date: u64,
+    pub end_date: Option<u64>,
+}
+
+/// Employee record from before per-employee schedules, with its payments embedded
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LegacyEmployee {
+    pub account_id: Address,
+    pub employee_id: String,
+    pub base_salary: i128,
+    pub tax_jurisdiction: String,
+    pub benefits: Vec<LegacyBenefit>,
+    pub payment_history: Vec<LegacyPayment>,
+    pub is_active: bool,
+}
+
+/// Benefit from before benefits affected pay
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LegacyBenefit {
+    pub benefit_type: BenefitType,
+    pub amount: i128,
+    pub description: String,
+}
+
+/// Payment from before deductions and line items. Every legacy payment has the
+/// ID `PAY`, so migrated payments are given new IDs.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LegacyPayment {
+    pub payment_id: String,
+    pub employee_id: String,
+    pub gross_amount: i128,
+    pub tax_amount: i128,
+    pub net_amount: i128,
+    pub payment_date: u64,
+    pub status: PaymentStatus,
+    pub transaction_hash: Option<String>,
+}
+
+/// One band of a progressive tax table
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TaxBracket {
+    pub threshold: i128, // Annual income in stroops where this band starts
+    pub rate: i128,      // Basis points charged on income above the threshold
+}
+
+/// A single tax levied by a jurisdiction, such as federal, state or social security tax
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TaxLevy {
+    pub name: String,
+    pub brackets: Vec<TaxBracket>, // Ascending thresholds, the first at 0
+    pub annual_cap: Option<i128>,  // Earnings per year above which the levy stops applying
+}
+
+/// An employee's earnings and withholdings for a calendar year
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct YearToDate {
+    pub year: u32,
+    pub gross_earnings: i128,
+    pub taxable_wages: i128, // Gross earnings less pre-tax deductions
+    pub pre_tax_deductions: i128,
+    pub tax_withheld: i128,
+    pub post_tax_deductions: i128,
+    pub net_pay: i128,
+    pub employer_contributions: i128,
+    pub payment_count: u32,
+}
+
+/// An employee's year-to-date statement
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EmployeeStatement {
+    pub employee_id: String,
+    pub account_id: Address,
+    pub tax_jurisdiction: String,
+    pub year_to_date: YearToDate,
+}
+
+/// Filters for a payment history query; unset filters match every payment
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PaymentQuery {
+    pub employee_id: Option<String>,
+    pub period_end: Option<u64>, // Pay period the payment covers
+    pub from_date: Option<u64>,  // Earliest payment date, inclusive
+    pub to_date: Option<u64>,    // Latest payment date, inclusive
+}
+
+/// One page of payment history
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PaymentPage {
+    pub payments: Vec<Payment>,
+    pub next_cursor: Option<u32>, // Cursor for the next page, if there is one
+}
+
+/// Tax withheld for a jurisdiction and what has been paid to its authority
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TaxLiability {
+    pub jurisdiction: String,
+    pub authority: Option<Address>, // Account withholdings are remitted to
+    pub withheld: i128,              // Total tax withheld from payments
+    pub remitted: i128,              // Total paid to the authority
+    pub outstanding: i128,           // Withheld but not yet remitted
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TaxRemittance {
+    pub jurisdiction: String,
+    pub authority: Address,
+    pub amount: i128,
+    pub remitted_at: u64, // Unix timestamp
+    pub ledger_sequence: u32,
+}
+
+/// A payroll run awaiting approval by the contract's signers
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PayrollRunProposal {
+    pub proposal_id: u64,
+    pub proposer: Address,
+    pub run_date: u64,      // Employees due at this time are paid
+    pub total_amount: i128, // Gross pay plus employer contributions
+    pub employee_count: u32,
+    pub payments_hash: BytesN<32>, // Each employee's account, net pay, tax and line items
+    pub approvals: Vec<Address>,
+    pub expires_at: u64, // Unix timestamp
+}
+
+/// What a payroll run would pay if executed now, without moving any funds
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PayrollPreview {
+    pub run_date: u64,
+    pub payments: Vec<Payment>, // Calculated payments, still pending
+    pub total_amount: i128,     // Gross pay plus employer contributions
+    pub contract_balance: i128,
+    pub required_balance: i128, // Run total, outstanding withholdings and minimum balance
+    pub insufficient_funds: Vec<String>, // Employees the balance would not cover
+    pub invalid_amounts: Vec<String>,    // Employees whose deductions exceed their pay
+}
+
 #[contracterror]
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum PayrollError {
@@ -122,14 +416,46 @@ pub enum PayrollError {
     NotInitialized = 13,
     InvalidSigner = 14,
     DuplicatePayment = 15,
+    TaxAuthorityNotSet = 16,
+    ProposalNotFound = 17,
+    ProposalExpired = 18,
+    ProposalNotApproved = 19,
+    AlreadyApproved = 20,
+    ProposalOutdated = 21,
+    PaymentNotFound = 22,
+    MigrationRequired = 23,
+    BenefitRecipientRequired = 24,
 }
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum DataKey {
-    Contract,           // PayrollContract data
-    Employee(String),   // Employee data by employee_id
-    PaymentHistory,     // Global payment history
-    NextPaymentId,      // Counter for payment IDs
-    TotalEmployees,     // Total number of employees
-} 
\ No newline at end of file
+    Contract,                 // PayrollContract data
+    Employee(String),         // Employee data by employee_id
+    Payment(String),          // Settled payment by payment_id
+    PaymentIndex(u32),        // Payment ID by position in settlement order
+    PaymentCount,             // Number of settled payments
+    EmployeePaymentIndex(String, u32), // Payment ID by employee_id and position
+    EmployeePaymentCount(String),      // Number of settled payments by employee_id
+    ProcessedPayment(String), // Marker for a payment ID already paid
+    TaxLiability(String),     // Withholding liability by jurisdiction
+    TaxRemittances(String),   // Remittance records by jurisdiction
+    TaxJurisdictions,         // Jurisdictions with a tax rate or levies
+    TaxLevies(String),        // Progressive tax levies by jurisdiction
+    YearToDate(String, u32),  // Year-to-date totals by employee_id and year
+    RunProposal,              // Payroll run awaiting approval
+    NextProposalId,           // Counter for run proposal IDs
+    FailedPayments,           // Failed payments waiting to be retried or cancelled
+    TotalEmployees,           // Total number of employees
+    StorageVersion,           // Storage layout version, absent before migration
+    EmployeeChange(String, u32),  // Change log entry by employee_id and position
+    EmployeeChangeCount(String),  // Number of change log entries by employee_id
+}
+
+/// Storage keys only used before storage versioning
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LegacyDataKey {
+    PaymentHistory, // Every payment made, oldest first
+    NextPaymentId,  // Unused payment ID counter
+}
\ No newline at end of file
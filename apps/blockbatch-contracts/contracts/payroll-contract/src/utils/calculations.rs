@@ -1,4 +1,18 @@
-use crate::types::PaymentFrequency;
+use crate::types::{
+    Benefit, BenefitCalculation, BenefitTreatment, BenefitType, PayDayRule, Payment,
+    PaymentFrequency, PaymentLineItem, TaxBracket, TaxLevy,
+};
+use soroban_sdk::Vec;
+use crate::utils::calendar::{
+    add_months, date_to_timestamp, days_in_month, last_business_day, timestamp_to_date,
+    SECONDS_PER_DAY,
+};
+
+/// Working days in a year, used to value a day of vacation
+pub const WORKING_DAYS_PER_YEAR: i128 = 260;
+
+/// Most pay periods stepped through when looking for a first pay date
+pub const MAX_SCHEDULE_STEPS: u32 = 1000;
 
 /// Calculate the gross payment amount based on frequency and annual salary
 pub fn calculate_gross_payment(frequency: &PaymentFrequency, annual_salary: i128) -> i128 {
@@ -8,6 +22,7 @@ pub fn calculate_gross_payment(frequency: &PaymentFrequency, annual_salary: i128
         PaymentFrequency::Monthly => annual_salary / 12,
         PaymentFrequency::Quarterly => annual_salary / 4,
         PaymentFrequency::Annually => annual_salary,
+        PaymentFrequency::SemiMonthly => annual_salary / 24,
     }
 }
 
@@ -16,25 +31,297 @@ pub fn calculate_tax_amount(gross_amount: i128, tax_rate: i128) -> i128 {
     (gross_amount * tax_rate) / 10000 // Convert basis points to actual amount
 }
 
+/// Get the number of pay periods in a year for a frequency
+pub fn periods_per_year(frequency: &PaymentFrequency) -> i128 {
+    match frequency {
+        PaymentFrequency::Weekly => 52,
+        PaymentFrequency::BiWeekly => 26,
+        PaymentFrequency::SemiMonthly => 24,
+        PaymentFrequency::Monthly => 12,
+        PaymentFrequency::Quarterly => 4,
+        PaymentFrequency::Annually => 1,
+    }
+}
+
+/// Calculate the annual tax on an income using progressive brackets, where each
+/// bracket's rate applies only to the income between its threshold and the next one
+pub fn calculate_bracket_tax(brackets: &Vec<TaxBracket>, annual_income: i128) -> i128 {
+    let mut tax = 0;
+    for (i, bracket) in brackets.iter().enumerate() {
+        if annual_income <= bracket.threshold {
+            break;
+        }
+        let upper = match brackets.get(i as u32 + 1) {
+            Some(next) => next.threshold.min(annual_income),
+            None => annual_income,
+        };
+        tax += calculate_tax_amount(upper - bracket.threshold, bracket.rate);
+    }
+    tax
+}
+
+/// Calculate one levy on a payment's taxable wages by annualizing them for the
+/// pay frequency. Capped levies only apply to taxable wages up to the cap for the year.
+pub fn calculate_levy_tax(
+    levy: &TaxLevy,
+    frequency: &PaymentFrequency,
+    taxable_amount: i128,
+    ytd_taxable: i128,
+) -> i128 {
+    let taxable = match levy.annual_cap {
+        Some(cap) => taxable_amount.min((cap - ytd_taxable).max(0)),
+        None => taxable_amount,
+    };
+    let periods = periods_per_year(frequency);
+    calculate_bracket_tax(&levy.brackets, taxable * periods) / periods
+}
+
+/// Calculate the total tax on a payment across all of a jurisdiction's levies
+pub fn calculate_progressive_tax(
+    levies: &Vec<TaxLevy>,
+    frequency: &PaymentFrequency,
+    taxable_amount: i128,
+    ytd_taxable: i128,
+) -> i128 {
+    levies
+        .iter()
+        .map(|levy| calculate_levy_tax(&levy, frequency, taxable_amount, ytd_taxable))
+        .sum()
+}
+
+/// Calculate a benefit's amount for a pay period with the given base pay
+pub fn calculate_benefit_amount(benefit: &Benefit, base_pay: i128) -> i128 {
+    match benefit.calculation {
+        BenefitCalculation::Fixed => benefit.amount,
+        BenefitCalculation::BasisPoints => (base_pay * benefit.amount) / 10000,
+    }
+}
+
+/// Build a payment line item for each monetary benefit
+pub fn calculate_benefit_line_items(
+    benefits: &Vec<Benefit>,
+    base_pay: i128,
+) -> Vec<PaymentLineItem> {
+    let mut line_items = Vec::new(benefits.env());
+    for benefit in benefits.iter() {
+        if benefit.treatment == BenefitTreatment::NonMonetary {
+            continue;
+        }
+        line_items.push_back(PaymentLineItem {
+            benefit_type: benefit.benefit_type.clone(),
+            treatment: benefit.treatment.clone(),
+            description: benefit.description.clone(),
+            amount: calculate_benefit_amount(&benefit, base_pay),
+            recipient: benefit.recipient.clone(),
+        });
+    }
+    line_items
+}
+
+/// Calculate the payout for unused vacation, valuing each day in the employee's
+/// `VacationDays` allowances at one working day of the annual salary
+pub fn calculate_vacation_payout(benefits: &Vec<Benefit>, annual_salary: i128) -> i128 {
+    let unused_days: i128 = benefits
+        .iter()
+        .filter(|benefit| {
+            benefit.benefit_type == BenefitType::VacationDays
+                && benefit.treatment == BenefitTreatment::NonMonetary
+                && benefit.calculation == BenefitCalculation::Fixed
+        })
+        .map(|benefit| benefit.amount)
+        .sum();
+    (annual_salary * unused_days) / WORKING_DAYS_PER_YEAR
+}
+
+/// Sum the line items with the given treatment
+pub fn sum_line_items(line_items: &Vec<PaymentLineItem>, treatment: &BenefitTreatment) -> i128 {
+    line_items
+        .iter()
+        .filter(|line_item| &line_item.treatment == treatment)
+        .map(|line_item| line_item.amount)
+        .sum()
+}
+
+/// Check whether a line item is transferred to a benefit provider
+pub fn is_provider_payment(line_item: &PaymentLineItem) -> bool {
+    line_item.treatment != BenefitTreatment::Earning
+        && line_item.amount != 0
+        && line_item.recipient.is_some()
+}
+
+/// Calculate how much of the contract's available balance a payment uses: net pay,
+/// withheld tax and line items transferred to benefit providers
+pub fn calculate_payment_outflow(payment: &Payment) -> i128 {
+    let provider_payments: i128 = payment
+        .line_items
+        .iter()
+        .filter(is_provider_payment)
+        .map(|line_item| line_item.amount)
+        .sum();
+    payment.net_amount + payment.tax_amount + provider_payments
+}
+
 /// Calculate net payment amount after tax deduction
 pub fn calculate_net_amount(gross_amount: i128, tax_amount: i128) -> i128 {
     gross_amount - tax_amount
 }
 
-/// Calculate the next payment date based on frequency
-pub fn calculate_next_payment_date(frequency: &PaymentFrequency, current_date: u64) -> u64 {
+/// Calculate the length in seconds of the pay period ending at `period_end`
+pub fn calculate_period_length(
+    frequency: &PaymentFrequency,
+    pay_day_rule: &PayDayRule,
+    period_end: u64,
+) -> u64 {
+    period_end - calculate_previous_payment_date(frequency, pay_day_rule, period_end)
+}
+
+/// Calculate the gross payment for the pay period ending at `period_end`,
+/// pro-rated by the share of the period the employee was employed
+pub fn calculate_prorated_gross_payment(
+    frequency: &PaymentFrequency,
+    pay_day_rule: &PayDayRule,
+    annual_salary: i128,
+    period_end: u64,
+    hire_date: u64,
+    termination_date: Option<u64>,
+) -> i128 {
+    let full_amount = calculate_gross_payment(frequency, annual_salary);
+    let period_length = calculate_period_length(frequency, pay_day_rule, period_end);
+    let period_start = period_end - period_length;
+
+    // Clamp the employment window to the pay period
+    let worked_from = hire_date.max(period_start);
+    let worked_until = termination_date.unwrap_or(period_end).min(period_end);
+    if worked_until <= worked_from {
+        return 0;
+    }
+
+    let worked = worked_until - worked_from;
+    if worked == period_length {
+        return full_amount;
+    }
+    (full_amount * worked as i128) / period_length as i128
+}
+
+/// Get the number of calendar months between payments, if the frequency is month based
+fn months_per_period(frequency: &PaymentFrequency) -> Option<i64> {
     match frequency {
-        PaymentFrequency::Weekly => current_date + (7 * 24 * 60 * 60), // 7 days
-        PaymentFrequency::BiWeekly => current_date + (14 * 24 * 60 * 60), // 14 days
-        PaymentFrequency::Monthly => current_date + (30 * 24 * 60 * 60), // 30 days (approximation)
-        PaymentFrequency::Quarterly => current_date + (90 * 24 * 60 * 60), // 90 days
-        PaymentFrequency::Annually => current_date + (365 * 24 * 60 * 60), // 365 days
+        PaymentFrequency::Monthly => Some(1),
+        PaymentFrequency::Quarterly => Some(3),
+        PaymentFrequency::Annually => Some(12),
+        _ => None,
+    }
+}
+
+/// Calculate the day a payment falls on in the given month
+pub fn calculate_pay_day(pay_day_rule: &PayDayRule, year: i64, month: u32, current_day: u32) -> u32 {
+    let last_day = days_in_month(year, month);
+    match pay_day_rule {
+        PayDayRule::SameDay => current_day.min(last_day),
+        PayDayRule::DayOfMonth(day) => (*day).min(last_day),
+        PayDayRule::LastDayOfMonth => last_day,
+        PayDayRule::LastBusinessDay => last_business_day(year, month),
+    }
+}
+
+/// Pin a `SameDay` rule to the day of the month of the first payment, so a short month
+/// doesn't move every later payment earlier
+pub fn anchor_pay_day_rule(pay_day_rule: &PayDayRule, first_payment_date: u64) -> PayDayRule {
+    match pay_day_rule {
+        PayDayRule::SameDay => {
+            let (_, _, day) = timestamp_to_date(first_payment_date);
+            PayDayRule::DayOfMonth(day)
+        }
+        rule => rule.clone(),
+    }
+}
+
+/// Calculate the first payment date on a schedule that falls after the given date, stepping
+/// forward from a known payment date. Returns `None` if it is more than `MAX_SCHEDULE_STEPS`
+/// periods away.
+pub fn calculate_first_payment_date_after(
+    frequency: &PaymentFrequency,
+    pay_day_rule: &PayDayRule,
+    payment_date: u64,
+    after: u64,
+) -> Option<u64> {
+    let mut next_payment_date = payment_date;
+    for _ in 0..MAX_SCHEDULE_STEPS {
+        if next_payment_date > after {
+            return Some(next_payment_date);
+        }
+        next_payment_date = calculate_next_payment_date(frequency, pay_day_rule, next_payment_date);
+    }
+    None
+}
+
+/// Calculate the previous payment date based on frequency and pay-day rule
+pub fn calculate_previous_payment_date(
+    frequency: &PaymentFrequency,
+    pay_day_rule: &PayDayRule,
+    current_date: u64,
+) -> u64 {
+    let time_of_day = current_date % SECONDS_PER_DAY;
+    let (year, month, day) = timestamp_to_date(current_date);
+
+    match frequency {
+        PaymentFrequency::Weekly => current_date.saturating_sub(7 * SECONDS_PER_DAY),
+        PaymentFrequency::BiWeekly => current_date.saturating_sub(14 * SECONDS_PER_DAY),
+        PaymentFrequency::SemiMonthly => {
+            if day > 15 {
+                date_to_timestamp(year, month, 15, time_of_day)
+            } else {
+                let (year, month) = add_months(year, month, -1);
+                date_to_timestamp(year, month, days_in_month(year, month), time_of_day)
+            }
+        }
+        _ => {
+            let months = months_per_period(frequency).unwrap_or(1);
+            let (year, month) = add_months(year, month, -months);
+            let day = calculate_pay_day(pay_day_rule, year, month, day);
+            date_to_timestamp(year, month, day, time_of_day)
+        }
+    }
+}
+
+/// Calculate the next payment date based on frequency and pay-day rule
+pub fn calculate_next_payment_date(
+    frequency: &PaymentFrequency,
+    pay_day_rule: &PayDayRule,
+    current_date: u64,
+) -> u64 {
+    let time_of_day = current_date % SECONDS_PER_DAY;
+    let (year, month, day) = timestamp_to_date(current_date);
+
+    match frequency {
+        PaymentFrequency::Weekly => current_date + 7 * SECONDS_PER_DAY,
+        PaymentFrequency::BiWeekly => current_date + 14 * SECONDS_PER_DAY,
+        PaymentFrequency::SemiMonthly => {
+            let last_day = days_in_month(year, month);
+            if day < 15 {
+                date_to_timestamp(year, month, 15, time_of_day)
+            } else if day < last_day {
+                date_to_timestamp(year, month, last_day, time_of_day)
+            } else {
+                let (year, month) = add_months(year, month, 1);
+                date_to_timestamp(year, month, 15, time_of_day)
+            }
+        }
+        _ => {
+            let months = months_per_period(frequency).unwrap_or(1);
+            let (year, month) = add_months(year, month, months);
+            let day = calculate_pay_day(pay_day_rule, year, month, day);
+            date_to_timestamp(year, month, day, time_of_day)
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testutils::create_levy;
+    use crate::types::{BenefitType, PaymentStatus};
+    use soroban_sdk::{testutils::Address as _, Address, Env, String};
 
     #[test]
     fn test_calculate_gross_payment() {
@@ -62,4 +349,428 @@ mod tests {
         
         assert_eq!(calculate_net_amount(gross_amount, tax_amount), expected_net);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_calculate_prorated_gross_payment() {
+        let annual_salary = 120_000_0000000;
+        let day = 24 * 60 * 60;
+        let period_end = date_to_timestamp(2024, 5, 1, 0); // April has 30 days
+        let full = calculate_gross_payment(&PaymentFrequency::Monthly, annual_salary);
+
+        let monthly = &PaymentFrequency::Monthly;
+        let same_day = &PayDayRule::SameDay;
+
+        // Employed for the whole period
+        assert_eq!(
+            calculate_prorated_gross_payment(monthly, same_day, annual_salary, period_end, 0, None),
+            full
+        );
+
+        // Hired 10 days before the period ends
+        assert_eq!(
+            calculate_prorated_gross_payment(monthly, same_day, annual_salary, period_end, period_end - 10 * day, None),
+            full / 3
+        );
+
+        // Left 15 days into the period
+        assert_eq!(
+            calculate_prorated_gross_payment(monthly, same_day, annual_salary, period_end, 0, Some(period_end - 15 * day)),
+            full / 2
+        );
+
+        // Hired and left within the same period
+        assert_eq!(
+            calculate_prorated_gross_payment(monthly, same_day, annual_salary, period_end, period_end - 25 * day, Some(period_end - 19 * day)),
+            full / 5
+        );
+
+        // Not employed during the period
+        assert_eq!(
+            calculate_prorated_gross_payment(monthly, same_day, annual_salary, period_end, period_end, None),
+            0
+        );
+        assert_eq!(
+            calculate_prorated_gross_payment(monthly, same_day, annual_salary, period_end, 0, Some(period_end - 30 * day)),
+            0
+        );
+    }
+
+    #[test]
+    fn test_calculate_next_payment_date_monthly() {
+        let same_day = &PayDayRule::SameDay;
+        let noon = 12 * 60 * 60;
+
+        // Same day of the next month, keeping the time of day
+        let date = date_to_timestamp(2023, 11, 14, noon);
+        assert_eq!(
+            calculate_next_payment_date(&PaymentFrequency::Monthly, same_day, date),
+            date_to_timestamp(2023, 12, 14, noon)
+        );
+
+        // Across the year boundary
+        let date = date_to_timestamp(2023, 12, 14, noon);
+        assert_eq!(
+            calculate_next_payment_date(&PaymentFrequency::Monthly, same_day, date),
+            date_to_timestamp(2024, 1, 14, noon)
+        );
+
+        // Clamped to the end of February in leap and common years
+        let date = date_to_timestamp(2024, 1, 31, 0);
+        assert_eq!(
+            calculate_next_payment_date(&PaymentFrequency::Monthly, same_day, date),
+            date_to_timestamp(2024, 2, 29, 0)
+        );
+        let date = date_to_timestamp(2025, 1, 31, 0);
+        assert_eq!(
+            calculate_next_payment_date(&PaymentFrequency::Monthly, same_day, date),
+            date_to_timestamp(2025, 2, 28, 0)
+        );
+
+        // A fixed day of month does not drift after a short month
+        let rule = &PayDayRule::DayOfMonth(31);
+        let date = date_to_timestamp(2024, 2, 29, 0);
+        assert_eq!(
+            calculate_next_payment_date(&PaymentFrequency::Monthly, rule, date),
+            date_to_timestamp(2024, 3, 31, 0)
+        );
+
+        // Twelve monthly payments land on the same date a year later
+        let mut date = date_to_timestamp(2023, 3, 15, 0);
+        for _ in 0..12 {
+            date = calculate_next_payment_date(&PaymentFrequency::Monthly, same_day, date);
+        }
+        assert_eq!(date, date_to_timestamp(2024, 3, 15, 0));
+    }
+
+    #[test]
+    fn test_anchor_pay_day_rule() {
+        // A same-day schedule starting on the 31st keeps coming back to the 31st
+        let first = date_to_timestamp(2024, 1, 31, 0);
+        let rule = anchor_pay_day_rule(&PayDayRule::SameDay, first);
+        assert_eq!(rule, PayDayRule::DayOfMonth(31));
+
+        let february = calculate_next_payment_date(&PaymentFrequency::Monthly, &rule, first);
+        assert_eq!(february, date_to_timestamp(2024, 2, 29, 0));
+        let march = calculate_next_payment_date(&PaymentFrequency::Monthly, &rule, february);
+        assert_eq!(march, date_to_timestamp(2024, 3, 31, 0));
+
+        // Other rules are kept as they are
+        assert_eq!(
+            anchor_pay_day_rule(&PayDayRule::LastBusinessDay, first),
+            PayDayRule::LastBusinessDay
+        );
+    }
+
+    #[test]
+    fn test_calculate_first_payment_date_after() {
+        let rule = &PayDayRule::DayOfMonth(14);
+        let date = date_to_timestamp(2023, 11, 14, 0);
+
+        assert_eq!(
+            calculate_first_payment_date_after(&PaymentFrequency::Monthly, rule, date, date),
+            Some(date_to_timestamp(2023, 12, 14, 0))
+        );
+
+        // Dates too many periods away are not searched for
+        let far_away = date + (MAX_SCHEDULE_STEPS as u64 + 1) * 7 * SECONDS_PER_DAY;
+        assert_eq!(
+            calculate_first_payment_date_after(&PaymentFrequency::Weekly, rule, date, far_away),
+            None
+        );
+    }
+
+    #[test]
+    fn test_calculate_next_payment_date_quarterly_and_annually() {
+        let same_day = &PayDayRule::SameDay;
+
+        let date = date_to_timestamp(2023, 11, 30, 0);
+        assert_eq!(
+            calculate_next_payment_date(&PaymentFrequency::Quarterly, same_day, date),
+            date_to_timestamp(2024, 2, 29, 0)
+        );
+
+        let date = date_to_timestamp(2024, 2, 29, 0);
+        assert_eq!(
+            calculate_next_payment_date(&PaymentFrequency::Annually, same_day, date),
+            date_to_timestamp(2025, 2, 28, 0)
+        );
+        assert_eq!(
+            calculate_previous_payment_date(&PaymentFrequency::Annually, same_day, date),
+            date_to_timestamp(2023, 2, 28, 0)
+        );
+    }
+
+    #[test]
+    fn test_calculate_next_payment_date_last_business_day() {
+        let rule = &PayDayRule::LastBusinessDay;
+
+        // November 2023 ends on a Thursday, December 2023 on a Sunday
+        let date = date_to_timestamp(2023, 11, 30, 0);
+        assert_eq!(
+            calculate_next_payment_date(&PaymentFrequency::Monthly, rule, date),
+            date_to_timestamp(2023, 12, 29, 0)
+        );
+        let date = date_to_timestamp(2023, 12, 29, 0);
+        assert_eq!(
+            calculate_next_payment_date(&PaymentFrequency::Monthly, rule, date),
+            date_to_timestamp(2024, 1, 31, 0)
+        );
+        assert_eq!(
+            calculate_previous_payment_date(&PaymentFrequency::Monthly, rule, date),
+            date_to_timestamp(2023, 11, 30, 0)
+        );
+
+        // Last day of the month ignores weekends
+        let date = date_to_timestamp(2023, 11, 30, 0);
+        assert_eq!(
+            calculate_next_payment_date(&PaymentFrequency::Monthly, &PayDayRule::LastDayOfMonth, date),
+            date_to_timestamp(2023, 12, 31, 0)
+        );
+    }
+
+    #[test]
+    fn test_calculate_next_payment_date_semi_monthly() {
+        let rule = &PayDayRule::SameDay;
+        let semi_monthly = &PaymentFrequency::SemiMonthly;
+
+        // 15th, then the last day, then the 15th of the next month
+        let date = date_to_timestamp(2023, 12, 15, 0);
+        assert_eq!(
+            calculate_next_payment_date(semi_monthly, rule, date),
+            date_to_timestamp(2023, 12, 31, 0)
+        );
+        let date = date_to_timestamp(2023, 12, 31, 0);
+        assert_eq!(
+            calculate_next_payment_date(semi_monthly, rule, date),
+            date_to_timestamp(2024, 1, 15, 0)
+        );
+        let date = date_to_timestamp(2024, 2, 15, 0);
+        assert_eq!(
+            calculate_next_payment_date(semi_monthly, rule, date),
+            date_to_timestamp(2024, 2, 29, 0)
+        );
+
+        // Dates between pay days move to the next one
+        let date = date_to_timestamp(2024, 1, 3, 0);
+        assert_eq!(
+            calculate_next_payment_date(semi_monthly, rule, date),
+            date_to_timestamp(2024, 1, 15, 0)
+        );
+
+        // Previous pay days mirror the next ones
+        let date = date_to_timestamp(2024, 1, 15, 0);
+        assert_eq!(
+            calculate_previous_payment_date(semi_monthly, rule, date),
+            date_to_timestamp(2023, 12, 31, 0)
+        );
+        let date = date_to_timestamp(2024, 2, 29, 0);
+        assert_eq!(
+            calculate_previous_payment_date(semi_monthly, rule, date),
+            date_to_timestamp(2024, 2, 15, 0)
+        );
+    }
+
+    #[test]
+    fn test_calculate_period_length() {
+        let day = 24 * 60 * 60;
+        let same_day = &PayDayRule::SameDay;
+
+        let end = date_to_timestamp(2024, 3, 14, 0);
+        assert_eq!(calculate_period_length(&PaymentFrequency::Monthly, same_day, end), 29 * day);
+        let end = date_to_timestamp(2024, 1, 14, 0);
+        assert_eq!(calculate_period_length(&PaymentFrequency::Monthly, same_day, end), 31 * day);
+        assert_eq!(calculate_period_length(&PaymentFrequency::Weekly, same_day, end), 7 * day);
+    }
+
+    fn bracket(threshold: i128, rate: i128) -> TaxBracket {
+        TaxBracket { threshold, rate }
+    }
+
+    #[test]
+    fn test_calculate_bracket_tax_boundaries() {
+        let env = Env::default();
+        // 0% up to 10,000, 10% up to 40,000, 20% above
+        let brackets = Vec::from_array(
+            &env,
+            [bracket(0, 0), bracket(10_000, 1000), bracket(40_000, 2000)],
+        );
+
+        assert_eq!(calculate_bracket_tax(&brackets, 0), 0);
+        assert_eq!(calculate_bracket_tax(&brackets, 10_000), 0);
+        assert_eq!(calculate_bracket_tax(&brackets, 10_010), 1);
+        assert_eq!(calculate_bracket_tax(&brackets, 40_000), 3_000);
+        assert_eq!(calculate_bracket_tax(&brackets, 40_010), 3_002);
+        assert_eq!(calculate_bracket_tax(&brackets, 100_000), 3_000 + 12_000);
+
+        // A single bracket is a flat rate
+        let flat = Vec::from_array(&env, [bracket(0, 2500)]);
+        assert_eq!(calculate_bracket_tax(&flat, 10_000), 2_500);
+    }
+
+    #[test]
+    fn test_calculate_levy_tax_annualizes_by_frequency() {
+        let env = Env::default();
+        let levy = create_levy(&env, "federal", &[(0, 0), (12_000, 1000)], None);
+
+        // 1,000 a month is 12,000 a year: exactly at the boundary, so no tax
+        assert_eq!(calculate_levy_tax(&levy, &PaymentFrequency::Monthly, 1_000, 0), 0);
+
+        // 2,000 a month is 24,000 a year: 1,200 a year, 100 a month
+        assert_eq!(calculate_levy_tax(&levy, &PaymentFrequency::Monthly, 2_000, 0), 100);
+
+        // Weekly pay is annualized over 52 weeks: 230 a week is 11,960 a year
+        assert_eq!(calculate_levy_tax(&levy, &PaymentFrequency::Weekly, 230, 0), 0);
+
+        // 1,000 a week is 52,000 a year: 4,000 a year, 76 a week rounded down
+        assert_eq!(calculate_levy_tax(&levy, &PaymentFrequency::Weekly, 1_000, 0), 76);
+    }
+
+    #[test]
+    fn test_calculate_levy_tax_annual_cap() {
+        let env = Env::default();
+        let levy = create_levy(&env, "federal", &[(0, 1000)], Some(25_000));
+
+        // Below the cap the whole payment is taxed
+        assert_eq!(calculate_levy_tax(&levy, &PaymentFrequency::Monthly, 10_000, 0), 1_000);
+
+        // Crossing the cap only taxes the part below it
+        assert_eq!(calculate_levy_tax(&levy, &PaymentFrequency::Monthly, 10_000, 20_000), 500);
+
+        // At or above the cap nothing is taxed
+        assert_eq!(calculate_levy_tax(&levy, &PaymentFrequency::Monthly, 10_000, 25_000), 0);
+        assert_eq!(calculate_levy_tax(&levy, &PaymentFrequency::Monthly, 10_000, 30_000), 0);
+    }
+
+    #[test]
+    fn test_calculate_progressive_tax_stacks_levies() {
+        let env = Env::default();
+        let levies = Vec::from_array(
+            &env,
+            [
+                create_levy(&env, "federal", &[(0, 0), (12_000, 1000)], None),
+                create_levy(&env, "federal", &[(0, 500)], None),
+                create_levy(&env, "federal", &[(0, 600)], Some(30_000)),
+            ],
+        );
+
+        // 2,000 a month: 100 + 100 + 120
+        assert_eq!(calculate_progressive_tax(&levies, &PaymentFrequency::Monthly, 2_000, 0), 320);
+
+        // Once the capped levy is exhausted only the others apply
+        assert_eq!(calculate_progressive_tax(&levies, &PaymentFrequency::Monthly, 2_000, 30_000), 200);
+
+        // No levies, no tax
+        assert_eq!(calculate_progressive_tax(&Vec::new(&env), &PaymentFrequency::Monthly, 2_000, 0), 0);
+    }
+
+    fn create_benefit(
+        env: &Env,
+        treatment: BenefitTreatment,
+        calculation: BenefitCalculation,
+        amount: i128,
+    ) -> Benefit {
+        Benefit {
+            benefit_type: BenefitType::Other(String::from_str(env, "test")),
+            amount,
+            description: String::from_str(env, "test"),
+            treatment,
+            calculation,
+            recipient: None,
+        }
+    }
+
+    #[test]
+    fn test_calculate_benefit_amount() {
+        let env = Env::default();
+
+        let fixed = create_benefit(&env, BenefitTreatment::PostTaxDeduction, BenefitCalculation::Fixed, 150);
+        assert_eq!(calculate_benefit_amount(&fixed, 10_000), 150);
+        assert_eq!(calculate_benefit_amount(&fixed, 0), 150);
+
+        // 5% of base pay
+        let share = create_benefit(&env, BenefitTreatment::PreTaxDeduction, BenefitCalculation::BasisPoints, 500);
+        assert_eq!(calculate_benefit_amount(&share, 10_000), 500);
+        assert_eq!(calculate_benefit_amount(&share, 0), 0);
+    }
+
+    #[test]
+    fn test_calculate_benefit_line_items() {
+        let env = Env::default();
+        let benefits = Vec::from_array(
+            &env,
+            [
+                create_benefit(&env, BenefitTreatment::PreTaxDeduction, BenefitCalculation::BasisPoints, 500),
+                create_benefit(&env, BenefitTreatment::PreTaxDeduction, BenefitCalculation::Fixed, 100),
+                create_benefit(&env, BenefitTreatment::PostTaxDeduction, BenefitCalculation::Fixed, 200),
+                create_benefit(&env, BenefitTreatment::Earning, BenefitCalculation::Fixed, 1_000),
+                create_benefit(&env, BenefitTreatment::EmployerContribution, BenefitCalculation::BasisPoints, 300),
+                create_benefit(&env, BenefitTreatment::NonMonetary, BenefitCalculation::Fixed, 20),
+            ],
+        );
+
+        let line_items = calculate_benefit_line_items(&benefits, 10_000);
+
+        // Non-monetary benefits are not itemized
+        assert_eq!(line_items.len(), 5);
+        assert_eq!(sum_line_items(&line_items, &BenefitTreatment::PreTaxDeduction), 600);
+        assert_eq!(sum_line_items(&line_items, &BenefitTreatment::PostTaxDeduction), 200);
+        assert_eq!(sum_line_items(&line_items, &BenefitTreatment::Earning), 1_000);
+        assert_eq!(sum_line_items(&line_items, &BenefitTreatment::EmployerContribution), 300);
+        assert_eq!(sum_line_items(&line_items, &BenefitTreatment::NonMonetary), 0);
+    }
+
+    #[test]
+    fn test_calculate_vacation_payout() {
+        let env = Env::default();
+        let mut vacation = create_benefit(&env, BenefitTreatment::NonMonetary, BenefitCalculation::Fixed, 10);
+        vacation.benefit_type = BenefitType::VacationDays;
+        let mut sick_leave = vacation.clone();
+        sick_leave.benefit_type = BenefitType::SickLeave;
+        let benefits = Vec::from_array(&env, [vacation, sick_leave]);
+
+        // 10 days at 130,000 a year is 5,000
+        assert_eq!(calculate_vacation_payout(&benefits, 130_000), 5_000);
+        assert_eq!(calculate_vacation_payout(&Vec::new(&env), 130_000), 0);
+    }
+
+    #[test]
+    fn test_calculate_payment_outflow() {
+        let env = Env::default();
+        let provider = Address::generate(&env);
+        let mut benefits = Vec::new(&env);
+        for (treatment, amount) in [
+            (BenefitTreatment::PreTaxDeduction, 500),
+            (BenefitTreatment::PostTaxDeduction, 200),
+            (BenefitTreatment::EmployerContribution, 300),
+            (BenefitTreatment::Earning, 1_000),
+        ] {
+            let mut benefit = create_benefit(&env, treatment, BenefitCalculation::Fixed, amount);
+            benefit.recipient = Some(provider.clone());
+            benefits.push_back(benefit);
+        }
+        // A deduction without a provider isn't transferred
+        benefits.push_back(create_benefit(&env, BenefitTreatment::PostTaxDeduction, BenefitCalculation::Fixed, 50));
+
+        let line_items = calculate_benefit_line_items(&benefits, 10_000);
+        let payment = Payment {
+            payment_id: String::from_str(&env, "PAY-1"),
+            employee_id: String::from_str(&env, "EMP_001"),
+            recipient: None,
+            period_end: 0,
+            gross_amount: 11_000,
+            pre_tax_deductions: 500,
+            tax_amount: 1_050,
+            post_tax_deductions: 250,
+            net_amount: 9_200,
+            employer_contributions: 300,
+            line_items,
+            payment_date: 0,
+            status: PaymentStatus::Pending,
+            reason: None,
+            transaction_hash: None,
+            ledger_sequence: 0,
+        };
+
+        assert_eq!(calculate_payment_outflow(&payment), 9_200 + 1_050 + 500 + 200 + 300);
+    }
+}
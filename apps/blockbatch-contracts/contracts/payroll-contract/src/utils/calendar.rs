@@ -0,0 +1,139 @@
+/// Number of seconds in a day
+pub const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Check whether a year is a leap year in the Gregorian calendar
+pub fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// Get the number of days in a month (1-12)
+pub fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Convert a calendar date to days since the Unix epoch
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // Shift the year to start in March so the leap day falls at the end
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_from_march = (month as i64 + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Convert days since the Unix epoch to a calendar date (year, month, day)
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Convert a Unix timestamp to a calendar date (year, month, day)
+pub fn timestamp_to_date(timestamp: u64) -> (i64, u32, u32) {
+    civil_from_days((timestamp / SECONDS_PER_DAY) as i64)
+}
+
+/// Convert a calendar date and a time of day in seconds to a Unix timestamp
+pub fn date_to_timestamp(year: i64, month: u32, day: u32, time_of_day: u64) -> u64 {
+    days_from_civil(year, month, day) as u64 * SECONDS_PER_DAY + time_of_day
+}
+
+/// Get the day of the week for a Unix timestamp (0 = Monday, 6 = Sunday)
+pub fn day_of_week(timestamp: u64) -> u32 {
+    // 1970-01-01 was a Thursday
+    ((timestamp / SECONDS_PER_DAY + 3) % 7) as u32
+}
+
+/// Move a (year, month) pair forward or backward by a number of months
+pub fn add_months(year: i64, month: u32, months: i64) -> (i64, u32) {
+    let total = year * 12 + (month as i64 - 1) + months;
+    (total.div_euclid(12), (total.rem_euclid(12) + 1) as u32)
+}
+
+/// Get the last weekday (Monday to Friday) of a month
+pub fn last_business_day(year: i64, month: u32) -> u32 {
+    let last_day = days_in_month(year, month);
+    match day_of_week(date_to_timestamp(year, month, last_day, 0)) {
+        5 => last_day - 1, // Saturday
+        6 => last_day - 2, // Sunday
+        _ => last_day,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_leap_year() {
+        assert!(is_leap_year(2024));
+        assert!(is_leap_year(2000));
+        assert!(!is_leap_year(2023));
+        assert!(!is_leap_year(2100));
+    }
+
+    #[test]
+    fn test_days_in_month() {
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2023, 2), 28);
+        assert_eq!(days_in_month(2023, 4), 30);
+        assert_eq!(days_in_month(2023, 12), 31);
+    }
+
+    #[test]
+    fn test_date_round_trip() {
+        assert_eq!(timestamp_to_date(0), (1970, 1, 1));
+        assert_eq!(timestamp_to_date(1_700_000_000), (2023, 11, 14));
+        assert_eq!(timestamp_to_date(1_709_164_800), (2024, 2, 29));
+
+        // Every day across a leap year boundary converts back to itself
+        let start = days_from_civil(2023, 12, 1);
+        for days in start..start + 400 {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn test_day_of_week() {
+        assert_eq!(day_of_week(0), 3); // Thursday 1970-01-01
+        assert_eq!(day_of_week(date_to_timestamp(2024, 1, 1, 0)), 0); // Monday
+        assert_eq!(day_of_week(date_to_timestamp(2023, 12, 31, 0)), 6); // Sunday
+    }
+
+    #[test]
+    fn test_add_months() {
+        assert_eq!(add_months(2023, 11, 1), (2023, 12));
+        assert_eq!(add_months(2023, 12, 1), (2024, 1));
+        assert_eq!(add_months(2023, 11, 3), (2024, 2));
+        assert_eq!(add_months(2024, 1, -1), (2023, 12));
+        assert_eq!(add_months(2024, 2, 12), (2025, 2));
+    }
+
+    #[test]
+    fn test_last_business_day() {
+        assert_eq!(last_business_day(2024, 1), 31); // Wednesday
+        assert_eq!(last_business_day(2024, 3), 29); // 31st is a Sunday
+        assert_eq!(last_business_day(2023, 9), 29); // 30th is a Saturday
+        assert_eq!(last_business_day(2024, 2), 29); // Thursday in a leap year
+    }
+}
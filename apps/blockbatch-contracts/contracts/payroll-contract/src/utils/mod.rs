@@ -1,5 +1,7 @@
 pub mod calculations;
+pub mod calendar;
 pub mod payment_utils;
 
 pub use calculations::*;
+pub use calendar::*;
 pub use payment_utils::*; 
\ No newline at end of file
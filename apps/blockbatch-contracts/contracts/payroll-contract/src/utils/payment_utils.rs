@@ -1,15 +1,83 @@
-use soroban_sdk::{Env, String};
+use crate::types::{Payment, PaymentQuery};
+use soroban_sdk::{xdr::ToXdr, Address, Bytes, BytesN, Env, String, Vec};
 
-/// Generate a unique payment ID
-pub fn generate_payment_id(env: &Env, _counter: u64) -> String {
-    // Simple payment ID for now - in production, this could be more sophisticated
-    String::from_str(env, "PAY")
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Encode a 32-byte hash as lowercase hex after a short prefix
+fn hex_string(env: &Env, prefix: &[u8], hash: &[u8; 32]) -> String {
+    let mut buffer = [0u8; 72];
+    buffer[..prefix.len()].copy_from_slice(prefix);
+    for (i, byte) in hash.iter().enumerate() {
+        buffer[prefix.len() + 2 * i] = HEX_DIGITS[(byte >> 4) as usize];
+        buffer[prefix.len() + 2 * i + 1] = HEX_DIGITS[(byte & 0x0f) as usize];
+    }
+    String::from_bytes(env, &buffer[..prefix.len() + 64])
+}
+
+/// Generate a deterministic payment ID for an employee's pay period.
+/// The same contract, period and employee always produce the same ID.
+pub fn generate_payment_id(
+    env: &Env,
+    contract_id: &String,
+    period_end: u64,
+    employee_id: &String,
+) -> String {
+    let mut data = Bytes::new(env);
+    data.append(&contract_id.clone().to_xdr(env));
+    data.append(&period_end.to_xdr(env));
+    data.append(&employee_id.clone().to_xdr(env));
+    hex_string(env, b"PAY-", &env.crypto().sha256(&data).to_array())
 }
 
-/// Generate a transaction hash
-pub fn generate_transaction_hash(env: &Env, _counter: u64) -> String {
-    // Simple transaction hash for now - in production, this would be the actual tx hash
-    String::from_str(env, "TX")
+/// Generate a transaction reference for a payment transfer, derived from the
+/// ledger it settled in and the transfer itself so it can be reconciled
+/// against the token's transfer events
+pub fn generate_transaction_hash(
+    env: &Env,
+    payment_id: &String,
+    token: &Address,
+    recipient: &Address,
+    amount: i128,
+) -> String {
+    let mut data = Bytes::new(env);
+    data.append(&payment_id.clone().to_xdr(env));
+    data.append(&env.ledger().sequence().to_xdr(env));
+    data.append(&token.clone().to_xdr(env));
+    data.append(&env.current_contract_address().to_xdr(env));
+    data.append(&recipient.clone().to_xdr(env));
+    data.append(&amount.to_xdr(env));
+    hex_string(env, b"", &env.crypto().sha256(&data).to_array())
+}
+
+/// Hash what signers approve in a payroll run: each employee paid, the account
+/// they are paid to, and their net pay, tax and line items
+pub fn hash_run_payments(env: &Env, payments: &Vec<(Address, Payment)>) -> BytesN<32> {
+    let mut data = Bytes::new(env);
+    for (account_id, payment) in payments.iter() {
+        data.append(&payment.employee_id.to_xdr(env));
+        data.append(&account_id.to_xdr(env));
+        data.append(&payment.net_amount.to_xdr(env));
+        data.append(&payment.tax_amount.to_xdr(env));
+        data.append(&payment.line_items.to_xdr(env));
+    }
+    env.crypto().sha256(&data).to_bytes()
+}
+
+/// Check whether a payment matches a history query's employee, period and date filters
+pub fn matches_payment_query(payment: &Payment, query: &PaymentQuery) -> bool {
+    query
+        .employee_id
+        .as_ref()
+        .is_none_or(|employee_id| &payment.employee_id == employee_id)
+        && query
+            .period_end
+            .is_none_or(|period_end| payment.period_end == period_end)
+        && query
+            .from_date
+            .is_none_or(|from_date| payment.payment_date >= from_date)
+        && query
+            .to_date
+            .is_none_or(|to_date| payment.payment_date <= to_date)
 }
 
 /// Validate payment amount
@@ -25,6 +93,8 @@ pub fn validate_tax_rate(tax_rate: i128) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::PaymentStatus;
+    use soroban_sdk::testutils::{Address as _, Ledger};
     use soroban_sdk::Env;
 
     #[test]
@@ -43,10 +113,106 @@ mod tests {
         assert!(!validate_tax_rate(15000)); // Over 100%
     }
 
+    #[test]
+    fn test_matches_payment_query() {
+        let env = Env::default();
+        let payment = Payment {
+            payment_id: String::from_str(&env, "PAY-1"),
+            employee_id: String::from_str(&env, "EMP_001"),
+            recipient: None,
+            period_end: 2_000,
+            gross_amount: 1000,
+            pre_tax_deductions: 0,
+            tax_amount: 0,
+            post_tax_deductions: 0,
+            net_amount: 1000,
+            employer_contributions: 0,
+            line_items: soroban_sdk::Vec::new(&env),
+            payment_date: 2_500,
+            status: PaymentStatus::Completed,
+            reason: None,
+            transaction_hash: None,
+            ledger_sequence: 0,
+        };
+        let mut query = PaymentQuery {
+            employee_id: None,
+            period_end: None,
+            from_date: None,
+            to_date: None,
+        };
+        assert!(matches_payment_query(&payment, &query));
+
+        query.employee_id = Some(String::from_str(&env, "EMP_002"));
+        assert!(!matches_payment_query(&payment, &query));
+        query.employee_id = Some(String::from_str(&env, "EMP_001"));
+        assert!(matches_payment_query(&payment, &query));
+
+        query.period_end = Some(1_000);
+        assert!(!matches_payment_query(&payment, &query));
+        query.period_end = Some(2_000);
+        assert!(matches_payment_query(&payment, &query));
+
+        // Date range bounds are inclusive
+        query.from_date = Some(2_500);
+        query.to_date = Some(2_500);
+        assert!(matches_payment_query(&payment, &query));
+        query.from_date = Some(2_501);
+        assert!(!matches_payment_query(&payment, &query));
+        query.from_date = None;
+        query.to_date = Some(2_499);
+        assert!(!matches_payment_query(&payment, &query));
+    }
+
     #[test]
     fn test_generate_payment_id() {
         let env = Env::default();
-        let payment_id = generate_payment_id(&env, 1);
-        assert_eq!(payment_id, String::from_str(&env, "PAY"));
+        let contract_id = String::from_str(&env, "PAYROLL_001");
+        let employee_id = String::from_str(&env, "EMP_001");
+
+        let payment_id = generate_payment_id(&env, &contract_id, 1_000, &employee_id);
+        assert_eq!(payment_id.len(), 68);
+
+        // Deterministic for the same inputs
+        assert_eq!(payment_id, generate_payment_id(&env, &contract_id, 1_000, &employee_id));
+
+        // Unique per period, employee and contract
+        assert_ne!(payment_id, generate_payment_id(&env, &contract_id, 2_000, &employee_id));
+        assert_ne!(
+            payment_id,
+            generate_payment_id(&env, &contract_id, 1_000, &String::from_str(&env, "EMP_002"))
+        );
+        assert_ne!(
+            payment_id,
+            generate_payment_id(&env, &String::from_str(&env, "PAYROLL_002"), 1_000, &employee_id)
+        );
+    }
+
+    #[test]
+    fn test_generate_transaction_hash() {
+        let env = Env::default();
+        let contract_address = env.register(crate::PayrollContractImpl, ());
+        let payment_id = String::from_str(&env, "PAY-1");
+        let token = Address::generate(&env);
+        let recipient = Address::generate(&env);
+
+        env.as_contract(&contract_address, || {
+            let reference = generate_transaction_hash(&env, &payment_id, &token, &recipient, 100);
+            assert_eq!(reference.len(), 64);
+            assert_eq!(
+                reference,
+                generate_transaction_hash(&env, &payment_id, &token, &recipient, 100)
+            );
+
+            // A different amount or ledger gives a different reference
+            assert_ne!(
+                reference,
+                generate_transaction_hash(&env, &payment_id, &token, &recipient, 101)
+            );
+            env.ledger().set_sequence_number(env.ledger().sequence() + 1);
+            assert_ne!(
+                reference,
+                generate_transaction_hash(&env, &payment_id, &token, &recipient, 100)
+            );
+        });
     }
 } 
\ No newline at end of file
@@ -1,4 +1,5 @@
 use crate::types::{ContractStatus, PayrollContract, PayrollError, TimeSchedule};
+use crate::validation::validate_pay_day_rule;
 use soroban_sdk::{Address, Env, Vec};
 
 /// Validate contract initialization parameters
@@ -23,6 +24,7 @@ pub fn validate_contract_creation(
     if payment_schedule.start_date < current_time {
         return Err(PayrollError::InvalidPaymentSchedule);
     }
+    validate_pay_day_rule(&payment_schedule.pay_day_rule)?;
 
     Ok(())
 }
@@ -32,6 +34,26 @@ pub fn is_authorized(contract: &PayrollContract, caller: &Address) -> bool {
     caller == &contract.employer_account || contract.signers.contains(caller)
 }
 
+/// Count the distinct accounts that can approve payroll runs (employer and signers)
+pub fn count_approvers(contract: &PayrollContract) -> u32 {
+    if contract.signers.contains(&contract.employer_account) {
+        contract.signers.len()
+    } else {
+        contract.signers.len() + 1
+    }
+}
+
+/// Validate that an approval threshold can be met by the contract's approvers
+pub fn validate_approval_threshold(
+    contract: &PayrollContract,
+    threshold: u32,
+) -> Result<(), PayrollError> {
+    if threshold == 0 || threshold > count_approvers(contract) {
+        return Err(PayrollError::InvalidSigner);
+    }
+    Ok(())
+}
+
 /// Validate that the contract is in an active state for operations
 pub fn validate_contract_active(contract: &PayrollContract) -> Result<(), PayrollError> {
     if contract.status != ContractStatus::Active {
@@ -65,7 +87,7 @@ pub fn validate_employer_only(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{Asset, PaymentFrequency};
+    use crate::types::{Asset, FundingMode, PayDayRule, PaymentFrequency};
     use soroban_sdk::{Env, Map, String};
     use soroban_sdk::testutils::Address as _;
 
@@ -77,11 +99,12 @@ mod tests {
             employer_account: employer,
             payment_schedule: TimeSchedule {
                 frequency: PaymentFrequency::Monthly,
+                pay_day_rule: PayDayRule::SameDay,
                 start_date: env.ledger().timestamp() + 86400,
                 next_payment_date: env.ledger().timestamp() + 86400,
                 end_date: None,
             },
-            employees: Vec::new(env),
+            employee_ids: Vec::new(env),
             tax_rates: Map::new(env),
             payment_token: Asset {
                 token: Address::generate(env),
@@ -90,6 +113,9 @@ mod tests {
             },
             minimum_balance: 1000_0000000,
             signers,
+            approval_threshold: 1,
+            proposal_ttl: 86400,
+            funding_mode: FundingMode::AllOrNothing,
             status: ContractStatus::Active,
             contract_id: String::from_str(env, "TEST_001"),
             created_at: env.ledger().timestamp(),
@@ -102,6 +128,7 @@ mod tests {
         let signers = Vec::from_array(&env, [Address::generate(&env)]);
         let schedule = TimeSchedule {
             frequency: PaymentFrequency::Monthly,
+            pay_day_rule: PayDayRule::SameDay,
             start_date: env.ledger().timestamp() + 86400,
             next_payment_date: env.ledger().timestamp() + 86400,
             end_date: None,
@@ -122,6 +149,42 @@ mod tests {
             validate_contract_creation(&env, 1000, &empty_signers, &schedule),
             Err(PayrollError::InvalidSigner)
         );
+
+        // Pay day outside of a month
+        let mut invalid_schedule = schedule.clone();
+        invalid_schedule.pay_day_rule = PayDayRule::DayOfMonth(0);
+        assert_eq!(
+            validate_contract_creation(&env, 1000, &signers, &invalid_schedule),
+            Err(PayrollError::InvalidPaymentSchedule)
+        );
+    }
+
+    #[test]
+    fn test_validate_approval_threshold() {
+        let env = Env::default();
+        let mut contract = create_test_contract(&env);
+
+        // Employer is also the only signer
+        assert_eq!(count_approvers(&contract), 1);
+        assert!(validate_approval_threshold(&contract, 1).is_ok());
+        assert_eq!(
+            validate_approval_threshold(&contract, 2),
+            Err(PayrollError::InvalidSigner)
+        );
+        assert_eq!(
+            validate_approval_threshold(&contract, 0),
+            Err(PayrollError::InvalidSigner)
+        );
+
+        // Two more signers
+        contract.signers.push_back(Address::generate(&env));
+        contract.signers.push_back(Address::generate(&env));
+        assert_eq!(count_approvers(&contract), 3);
+        assert!(validate_approval_threshold(&contract, 3).is_ok());
+        assert_eq!(
+            validate_approval_threshold(&contract, 4),
+            Err(PayrollError::InvalidSigner)
+        );
     }
 
     #[test]
@@ -1,4 +1,5 @@
-use crate::types::{Employee, PayrollError};
+use crate::types::{BenefitCalculation, BenefitTreatment, Employee, PayrollError};
+use crate::validation::validate_pay_day_rule;
 
 /// Validate employee data before adding to the contract
 pub fn validate_employee_data(employee: &Employee) -> Result<(), PayrollError> {
@@ -17,9 +18,48 @@ pub fn validate_employee_data(employee: &Employee) -> Result<(), PayrollError> {
         return Err(PayrollError::InvalidEmployee);
     }
 
+    // Validate benefit amounts
+    for benefit in employee.benefits.iter() {
+        if benefit.amount < 0 {
+            return Err(PayrollError::InvalidAmount);
+        }
+        if benefit.calculation == BenefitCalculation::BasisPoints && benefit.amount > 10000 {
+            return Err(PayrollError::InvalidAmount);
+        }
+        // Deductions and employer contributions must have a provider to be paid to
+        let paid_to_provider = matches!(
+            benefit.treatment,
+            BenefitTreatment::PreTaxDeduction
+                | BenefitTreatment::PostTaxDeduction
+                | BenefitTreatment::EmployerContribution
+        );
+        if paid_to_provider && benefit.recipient.is_none() {
+            return Err(PayrollError::BenefitRecipientRequired);
+        }
+    }
+
+    // Validate the employee's pay schedule
+    validate_pay_day_rule(&employee.pay_day_rule)?;
+    if employee.next_payment_date <= employee.hire_date {
+        return Err(PayrollError::InvalidPaymentSchedule);
+    }
+    if let Some(termination_date) = employee.termination_date {
+        if termination_date < employee.hire_date {
+            return Err(PayrollError::InvalidPaymentSchedule);
+        }
+    }
+
     Ok(())
 }
 
+/// Check whether an employee is owed a payment at the given time, which a terminated
+/// employee is from their termination date
+pub fn is_employee_due(employee: &Employee, current_time: u64) -> bool {
+    employee.is_active
+        && (employee.next_payment_date <= current_time
+            || employee.termination_date.is_some_and(|date| date <= current_time))
+}
+
 /// Validate that an employee is active
 pub fn validate_employee_active(employee: &Employee) -> Result<(), PayrollError> {
     if !employee.is_active {
@@ -31,7 +71,7 @@ pub fn validate_employee_active(employee: &Employee) -> Result<(), PayrollError>
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::Employee;
+    use crate::types::{Benefit, BenefitTreatment, BenefitType, Employee, PayDayRule, PaymentFrequency};
     use soroban_sdk::{Address, Env, String, Vec};
     use soroban_sdk::testutils::Address as _;
 
@@ -40,10 +80,16 @@ mod tests {
             account_id: Address::generate(env),
             employee_id: String::from_str(env, "EMP_001"),
             base_salary: 100_000_0000000,
+            salary_changes: Vec::new(env),
             tax_jurisdiction: String::from_str(env, "US"),
             benefits: Vec::new(env),
-            payment_history: Vec::new(env),
             is_active: true,
+            is_suspended: false,
+            pay_frequency: PaymentFrequency::Monthly,
+            pay_day_rule: PayDayRule::SameDay,
+            hire_date: 1_000,
+            termination_date: None,
+            next_payment_date: 1_000 + 30 * 86400,
         }
     }
 
@@ -78,6 +124,87 @@ mod tests {
             validate_employee_data(&invalid_employee),
             Err(PayrollError::InvalidEmployee)
         );
+
+        // Negative benefit
+        let mut invalid_employee = valid_employee.clone();
+        invalid_employee.benefits.push_back(Benefit {
+            benefit_type: BenefitType::Retirement,
+            amount: -1,
+            description: String::from_str(&env, "401k"),
+            treatment: BenefitTreatment::PreTaxDeduction,
+            calculation: BenefitCalculation::Fixed,
+            recipient: None,
+        });
+        assert_eq!(
+            validate_employee_data(&invalid_employee),
+            Err(PayrollError::InvalidAmount)
+        );
+
+        // Percentage benefit over 100%
+        let mut invalid_employee = valid_employee.clone();
+        invalid_employee.benefits.push_back(Benefit {
+            benefit_type: BenefitType::Retirement,
+            amount: 10001,
+            description: String::from_str(&env, "401k"),
+            treatment: BenefitTreatment::PreTaxDeduction,
+            calculation: BenefitCalculation::BasisPoints,
+            recipient: None,
+        });
+        assert_eq!(
+            validate_employee_data(&invalid_employee),
+            Err(PayrollError::InvalidAmount)
+        );
+
+        // Deduction without a provider
+        let mut invalid_employee = valid_employee.clone();
+        invalid_employee.benefits.push_back(Benefit {
+            benefit_type: BenefitType::Retirement,
+            amount: 500,
+            description: String::from_str(&env, "401k"),
+            treatment: BenefitTreatment::PreTaxDeduction,
+            calculation: BenefitCalculation::BasisPoints,
+            recipient: None,
+        });
+        assert_eq!(
+            validate_employee_data(&invalid_employee),
+            Err(PayrollError::BenefitRecipientRequired)
+        );
+
+        // First pay date not after the hire date
+        let mut invalid_employee = valid_employee.clone();
+        invalid_employee.next_payment_date = invalid_employee.hire_date;
+        assert_eq!(
+            validate_employee_data(&invalid_employee),
+            Err(PayrollError::InvalidPaymentSchedule)
+        );
+
+        // Terminated before being hired
+        let mut invalid_employee = valid_employee.clone();
+        invalid_employee.termination_date = Some(invalid_employee.hire_date - 1);
+        assert_eq!(
+            validate_employee_data(&invalid_employee),
+            Err(PayrollError::InvalidPaymentSchedule)
+        );
+    }
+
+    #[test]
+    fn test_is_employee_due() {
+        let env = Env::default();
+        let mut employee = create_valid_employee(&env);
+        let pay_date = employee.next_payment_date;
+
+        assert!(!is_employee_due(&employee, pay_date - 1));
+        assert!(is_employee_due(&employee, pay_date));
+        assert!(is_employee_due(&employee, pay_date + 1));
+
+        // A terminated employee's final paycheck is due from the termination date
+        employee.termination_date = Some(pay_date - 10);
+        assert!(!is_employee_due(&employee, pay_date - 11));
+        assert!(is_employee_due(&employee, pay_date - 10));
+
+        // Inactive employees are never due
+        employee.is_active = false;
+        assert!(!is_employee_due(&employee, pay_date));
     }
 
     #[test]
@@ -1,14 +1,5 @@
-use crate::types::PayrollError;
-use soroban_sdk::Env;
-
-/// Validate that a payment is due based on the schedule
-pub fn validate_payment_due(env: &Env, next_payment_date: u64) -> Result<(), PayrollError> {
-    let current_time = env.ledger().timestamp();
-    if current_time < next_payment_date {
-        return Err(PayrollError::PaymentNotDue);
-    }
-    Ok(())
-}
+use crate::types::{PayDayRule, Payment, PayrollError, TaxLevy};
+use soroban_sdk::Vec;
 
 /// Validate that the contract has sufficient balance for a payment
 pub fn validate_sufficient_balance(
@@ -29,6 +20,59 @@ pub fn validate_tax_rate(tax_rate: i128) -> Result<(), PayrollError> {
     Ok(())
 }
 
+/// Validate a jurisdiction's tax levies: each needs ascending brackets starting
+/// at 0 with valid rates, and the combined top rates cannot exceed 100%
+pub fn validate_tax_levies(levies: &Vec<TaxLevy>) -> Result<(), PayrollError> {
+    let mut combined_top_rate = 0;
+    for levy in levies.iter() {
+        match levy.brackets.first() {
+            Some(first) if first.threshold == 0 => {}
+            _ => return Err(PayrollError::InvalidTaxRate),
+        }
+
+        let mut previous_threshold = -1;
+        let mut top_rate = 0;
+        for bracket in levy.brackets.iter() {
+            if bracket.threshold <= previous_threshold {
+                return Err(PayrollError::InvalidTaxRate);
+            }
+            validate_tax_rate(bracket.rate)?;
+            previous_threshold = bracket.threshold;
+            top_rate = top_rate.max(bracket.rate);
+        }
+
+        if let Some(cap) = levy.annual_cap {
+            if cap <= 0 {
+                return Err(PayrollError::InvalidAmount);
+            }
+        }
+        combined_top_rate += top_rate;
+    }
+
+    if combined_top_rate > 10000 {
+        return Err(PayrollError::InvalidTaxRate);
+    }
+    Ok(())
+}
+
+/// Validate a pay-day rule (fixed days must fall within a month)
+pub fn validate_pay_day_rule(pay_day_rule: &PayDayRule) -> Result<(), PayrollError> {
+    if let PayDayRule::DayOfMonth(day) = pay_day_rule {
+        if *day < 1 || *day > 31 {
+            return Err(PayrollError::InvalidPaymentSchedule);
+        }
+    }
+    Ok(())
+}
+
+/// Validate that a payment's deductions and tax don't exceed the pay they come out of
+pub fn validate_payment_amounts(payment: &Payment) -> Result<(), PayrollError> {
+    if payment.net_amount < 0 {
+        return Err(PayrollError::InvalidAmount);
+    }
+    Ok(())
+}
+
 /// Validate payment amount
 pub fn validate_payment_amount(amount: i128) -> Result<(), PayrollError> {
     if amount <= 0 {
@@ -40,22 +84,9 @@ pub fn validate_payment_amount(amount: i128) -> Result<(), PayrollError> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use soroban_sdk::Env;
-
-    #[test]
-    fn test_validate_payment_due() {
-        let env = Env::default();
-        let current_time = env.ledger().timestamp();
-
-        // Payment due (current time)
-        assert!(validate_payment_due(&env, current_time).is_ok());
-
-        // Payment not due (future date)
-        assert_eq!(
-            validate_payment_due(&env, current_time + 1000),
-            Err(PayrollError::PaymentNotDue)
-        );
-    }
+    use crate::testutils::create_levy;
+    use crate::types::PaymentStatus;
+    use soroban_sdk::{Env, String};
 
     #[test]
     fn test_validate_sufficient_balance() {
@@ -72,6 +103,38 @@ mod tests {
         assert!(validate_sufficient_balance(1000, 1000).is_ok());
     }
 
+    #[test]
+    fn test_validate_payment_amounts() {
+        let env = Env::default();
+        let mut payment = Payment {
+            payment_id: String::from_str(&env, "PAY-1"),
+            employee_id: String::from_str(&env, "EMP_001"),
+            recipient: None,
+            period_end: 0,
+            gross_amount: 1000,
+            pre_tax_deductions: 0,
+            tax_amount: 200,
+            post_tax_deductions: 800,
+            net_amount: 0,
+            employer_contributions: 0,
+            line_items: Vec::new(&env),
+            payment_date: 0,
+            status: PaymentStatus::Pending,
+            reason: None,
+            transaction_hash: None,
+            ledger_sequence: 0,
+        };
+        assert!(validate_payment_amounts(&payment).is_ok());
+
+        // Deductions above pay
+        payment.post_tax_deductions = 900;
+        payment.net_amount = -100;
+        assert_eq!(
+            validate_payment_amounts(&payment),
+            Err(PayrollError::InvalidAmount)
+        );
+    }
+
     #[test]
     fn test_validate_tax_rate() {
         // Valid tax rates
@@ -84,6 +147,64 @@ mod tests {
         assert_eq!(validate_tax_rate(15000), Err(PayrollError::InvalidTaxRate));
     }
 
+    #[test]
+    fn test_validate_tax_levies() {
+        let env = Env::default();
+
+        // Stacked levies within 100% combined
+        let levies = Vec::from_array(
+            &env,
+            [
+                create_levy(&env, "levy", &[(0, 1000), (10_000, 3700)], None),
+                create_levy(&env, "levy", &[(0, 620)], Some(160_000)),
+            ],
+        );
+        assert!(validate_tax_levies(&levies).is_ok());
+
+        // First bracket must start at zero
+        let levies = Vec::from_array(&env, [create_levy(&env, "levy", &[(100, 1000)], None)]);
+        assert_eq!(validate_tax_levies(&levies), Err(PayrollError::InvalidTaxRate));
+
+        // Thresholds must ascend
+        let levies = Vec::from_array(&env, [create_levy(&env, "levy", &[(0, 1000), (0, 2000)], None)]);
+        assert_eq!(validate_tax_levies(&levies), Err(PayrollError::InvalidTaxRate));
+
+        // Rates must be valid
+        let levies = Vec::from_array(&env, [create_levy(&env, "levy", &[(0, 10001)], None)]);
+        assert_eq!(validate_tax_levies(&levies), Err(PayrollError::InvalidTaxRate));
+
+        // Caps must be positive
+        let levies = Vec::from_array(&env, [create_levy(&env, "levy", &[(0, 1000)], Some(0))]);
+        assert_eq!(validate_tax_levies(&levies), Err(PayrollError::InvalidAmount));
+
+        // Combined top rates cannot exceed 100%
+        let levies = Vec::from_array(
+            &env,
+            [
+                create_levy(&env, "levy", &[(0, 1000), (10_000, 6000)], None),
+                create_levy(&env, "levy", &[(0, 5000)], None),
+            ],
+        );
+        assert_eq!(validate_tax_levies(&levies), Err(PayrollError::InvalidTaxRate));
+    }
+
+    #[test]
+    fn test_validate_pay_day_rule() {
+        assert!(validate_pay_day_rule(&PayDayRule::SameDay).is_ok());
+        assert!(validate_pay_day_rule(&PayDayRule::LastBusinessDay).is_ok());
+        assert!(validate_pay_day_rule(&PayDayRule::DayOfMonth(1)).is_ok());
+        assert!(validate_pay_day_rule(&PayDayRule::DayOfMonth(31)).is_ok());
+
+        assert_eq!(
+            validate_pay_day_rule(&PayDayRule::DayOfMonth(0)),
+            Err(PayrollError::InvalidPaymentSchedule)
+        );
+        assert_eq!(
+            validate_pay_day_rule(&PayDayRule::DayOfMonth(32)),
+            Err(PayrollError::InvalidPaymentSchedule)
+        );
+    }
+
     #[test]
     fn test_validate_payment_amount() {
         // Valid amounts